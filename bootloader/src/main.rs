@@ -138,6 +138,7 @@ fn get_graphics_output_config() -> Result<(usize, usize, usize, usize), Status>
 
 fn get_rsdp_addr() -> Result<usize, Status> {
     let system_table = unsafe { system_table_raw().ok_or(Status::LOAD_ERROR)?.as_ref() };
+    let mut acpi1_0 = None;
     for i in 0..system_table.number_of_configuration_table_entries {
         let table = unsafe {
             system_table
@@ -149,8 +150,34 @@ fn get_rsdp_addr() -> Result<usize, Status> {
         if table.vendor_guid == guid!("8868e871-e4f1-11d3-bc22-0080c73c8881") {
             return Ok(table.vendor_table as usize);
         }
+        // ACPI 1.0, kept in case no ACPI 2.0 table follows
+        if table.vendor_guid == guid!("eb9d2d30-2d88-11d3-9a16-0090273fc14d") {
+            acpi1_0 = Some(table.vendor_table as usize);
+        }
     }
-    Err(Status::LOAD_ERROR)
+    acpi1_0.ok_or(Status::LOAD_ERROR)
+}
+
+/// SMBIOS 3.0 entry point, or the 2.1 one on firmware without it, zero if neither is there
+fn get_smbios_addr() -> Result<usize, Status> {
+    let system_table = unsafe { system_table_raw().ok_or(Status::LOAD_ERROR)?.as_ref() };
+    let mut smbios2_1 = 0;
+    for i in 0..system_table.number_of_configuration_table_entries {
+        let table = unsafe {
+            system_table
+                .configuration_table
+                .add(i)
+                .as_ref()
+                .ok_or(Status::LOAD_ERROR)?
+        };
+        if table.vendor_guid == guid!("f2fd1544-9794-4a2c-992e-e5bbcf20e394") {
+            return Ok(table.vendor_table as usize);
+        }
+        if table.vendor_guid == guid!("eb9d2d31-2d88-11d3-9a16-0090273fc14d") {
+            smbios2_1 = table.vendor_table as usize;
+        }
+    }
+    Ok(smbios2_1)
 }
 
 fn wait_for_key_press() -> Result<(), Status> {
@@ -191,6 +218,18 @@ fn main() -> Status {
     };
     log::info!("RSDP address: {:#x}", rsdp_addr);
 
+    let smbios_addr = match get_smbios_addr() {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    log::info!("SMBIOS address: {:#x}", smbios_addr);
+
+    // Still where runtime services are found once boot services are gone
+    let system_table = match system_table_raw() {
+        Some(p) => p.as_ptr() as usize,
+        None => return Status::LOAD_ERROR,
+    };
+
     log::info!("Press any key to continue......");
     match wait_for_key_press() {
         Ok(_) => {}
@@ -211,6 +250,8 @@ fn main() -> Status {
             in("r13") memory_map_owned.buffer().as_ptr() as usize,
             in("r14") memory_map_meta.desc_size,
             in("r15") memory_map_meta.entry_count(),
+            in("rdi") smbios_addr,
+            in("rsi") system_table,
             options(noreturn),
         );
     }
@@ -0,0 +1,101 @@
+//! Error
+
+pub enum Error {
+    /// Opcode the interpreter does not implement, extended ones as `0x5B00 | opcode`
+    InvalidOpcode(u16),
+
+    /// Term running past the end of its package
+    UnexpectedEnd,
+
+    InvalidName,
+
+    NotFound([u8; 4]),
+
+    AlreadyExists([u8; 4]),
+
+    /// Operand of the wrong object type
+    InvalidType,
+
+    InvalidIndex(u64),
+
+    InvalidAddress(u64),
+
+    /// Operation region space without a handler
+    InvalidRegionSpace(u8),
+
+    DivideByZero,
+
+    NamespaceFull,
+
+    ArenaFull,
+
+    /// Method calls nested past the stack budget
+    TooDeep,
+
+    /// While loop that never ends
+    LoopTimeout,
+
+    /// Firmware held the global lock past the longest wait
+    GlobalLockTimeout,
+
+    /// Raised by the firmware through `Fatal`
+    Fatal(u8, u32, u64),
+}
+impl From<Error> for super::super::Error {
+    fn from(err: Error) -> Self {
+        super::super::Error::AML(err)
+    }
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::ACPI(err.into())
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "/AML ".out();
+        match self {
+            Error::InvalidOpcode(opcode) => {
+                "Opcode ".out();
+                (*opcode as u64).out();
+            }
+            Error::UnexpectedEnd => "Unexpected End".out(),
+            Error::InvalidName => "Name String".out(),
+            Error::NotFound(name) => {
+                name.out();
+                " Not Found".out();
+            }
+            Error::AlreadyExists(name) => {
+                name.out();
+                " Already Exists".out();
+            }
+            Error::InvalidType => "Object Type".out(),
+            Error::InvalidIndex(index) => {
+                "Index ".out();
+                (*index as usize).out();
+            }
+            Error::InvalidAddress(addr) => {
+                "Address ".out();
+                addr.out();
+            }
+            Error::InvalidRegionSpace(space) => {
+                "Region Space ".out();
+                (*space as usize).out();
+            }
+            Error::DivideByZero => "Divide by Zero".out(),
+            Error::NamespaceFull => "Namespace Full".out(),
+            Error::ArenaFull => "Arena Full".out(),
+            Error::TooDeep => "Nesting Too Deep".out(),
+            Error::LoopTimeout => "Loop Timeout".out(),
+            Error::GlobalLockTimeout => "Global Lock Timeout".out(),
+            Error::Fatal(kind, code, arg) => {
+                "Fatal ".out();
+                (*kind as usize).out();
+                ", ".out();
+                (*code as u64).out();
+                ", ".out();
+                arg.out();
+            }
+        }
+    }
+}
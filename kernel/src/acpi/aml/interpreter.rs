@@ -0,0 +1,1699 @@
+//! Interpreter
+//!
+//! Walks term lists straight from the bytecode. Loading a definition block runs its top level,
+//! which declares objects and records methods, and invoking a method runs its body the same way.
+
+use core::cmp::Ordering;
+
+use crate::{debug, io::port, time};
+
+use super::{
+    super::facs,
+    Error,
+    namespace::{Field, FieldKind, Handle, Namespace, Object, Region},
+    parser::{EXT_OP_PREFIX, Parser},
+    region::{self, Space},
+    value::{self, Bytes, Element, Package, Value},
+};
+
+const ALIAS_OP: u8 = 0x06;
+const NAME_OP: u8 = 0x08;
+const SCOPE_OP: u8 = 0x10;
+const METHOD_OP: u8 = 0x14;
+const EXTERNAL_OP: u8 = 0x15;
+const LOCAL0_OP: u8 = 0x60;
+const LOCAL7_OP: u8 = 0x67;
+const ARG0_OP: u8 = 0x68;
+const ARG6_OP: u8 = 0x6E;
+const STORE_OP: u8 = 0x70;
+const REF_OF_OP: u8 = 0x71;
+const ADD_OP: u8 = 0x72;
+const CONCAT_OP: u8 = 0x73;
+const SUBTRACT_OP: u8 = 0x74;
+const INCREMENT_OP: u8 = 0x75;
+const DECREMENT_OP: u8 = 0x76;
+const MULTIPLY_OP: u8 = 0x77;
+const DIVIDE_OP: u8 = 0x78;
+const SHIFT_LEFT_OP: u8 = 0x79;
+const SHIFT_RIGHT_OP: u8 = 0x7A;
+const AND_OP: u8 = 0x7B;
+const NAND_OP: u8 = 0x7C;
+const OR_OP: u8 = 0x7D;
+const NOR_OP: u8 = 0x7E;
+const XOR_OP: u8 = 0x7F;
+const NOT_OP: u8 = 0x80;
+const FIND_SET_LEFT_BIT_OP: u8 = 0x81;
+const FIND_SET_RIGHT_BIT_OP: u8 = 0x82;
+const DEREF_OF_OP: u8 = 0x83;
+const CONCAT_RES_OP: u8 = 0x84;
+const MOD_OP: u8 = 0x85;
+const NOTIFY_OP: u8 = 0x86;
+const SIZE_OF_OP: u8 = 0x87;
+const INDEX_OP: u8 = 0x88;
+const MATCH_OP: u8 = 0x89;
+const CREATE_DWORD_FIELD_OP: u8 = 0x8A;
+const CREATE_WORD_FIELD_OP: u8 = 0x8B;
+const CREATE_BYTE_FIELD_OP: u8 = 0x8C;
+const CREATE_BIT_FIELD_OP: u8 = 0x8D;
+const OBJECT_TYPE_OP: u8 = 0x8E;
+const CREATE_QWORD_FIELD_OP: u8 = 0x8F;
+const LAND_OP: u8 = 0x90;
+const LOR_OP: u8 = 0x91;
+const LNOT_OP: u8 = 0x92;
+const LEQUAL_OP: u8 = 0x93;
+const LGREATER_OP: u8 = 0x94;
+const LLESS_OP: u8 = 0x95;
+const TO_BUFFER_OP: u8 = 0x96;
+const TO_DECIMAL_STRING_OP: u8 = 0x97;
+const TO_HEX_STRING_OP: u8 = 0x98;
+const TO_INTEGER_OP: u8 = 0x99;
+const TO_STRING_OP: u8 = 0x9C;
+const COPY_OBJECT_OP: u8 = 0x9D;
+const MID_OP: u8 = 0x9E;
+const CONTINUE_OP: u8 = 0x9F;
+const IF_OP: u8 = 0xA0;
+const ELSE_OP: u8 = 0xA1;
+const WHILE_OP: u8 = 0xA2;
+const NOOP_OP: u8 = 0xA3;
+const RETURN_OP: u8 = 0xA4;
+const BREAK_OP: u8 = 0xA5;
+const BREAK_POINT_OP: u8 = 0xCC;
+
+const MUTEX_OP: u8 = 0x01;
+const EVENT_OP: u8 = 0x02;
+const COND_REF_OF_OP: u8 = 0x12;
+const CREATE_FIELD_OP: u8 = 0x13;
+const STALL_OP: u8 = 0x21;
+const SLEEP_OP: u8 = 0x22;
+const ACQUIRE_OP: u8 = 0x23;
+const SIGNAL_OP: u8 = 0x24;
+const WAIT_OP: u8 = 0x25;
+const RESET_OP: u8 = 0x26;
+const RELEASE_OP: u8 = 0x27;
+const FROM_BCD_OP: u8 = 0x28;
+const TO_BCD_OP: u8 = 0x29;
+const REVISION_OP: u8 = 0x30;
+const DEBUG_OP: u8 = 0x31;
+const FATAL_OP: u8 = 0x32;
+const TIMER_OP: u8 = 0x33;
+const OP_REGION_OP: u8 = 0x80;
+const FIELD_OP: u8 = 0x81;
+const DEVICE_OP: u8 = 0x82;
+const PROCESSOR_OP: u8 = 0x83;
+const POWER_RES_OP: u8 = 0x84;
+const THERMAL_ZONE_OP: u8 = 0x85;
+const INDEX_FIELD_OP: u8 = 0x86;
+const BANK_FIELD_OP: u8 = 0x87;
+
+const RESERVED_FIELD: u8 = 0x00;
+const ACCESS_FIELD: u8 = 0x01;
+const EXTENDED_ACCESS_FIELD: u8 = 0x03;
+
+/// Revision of the interpreter `Revision` reports
+const REVISION: u64 = 1;
+
+/// Method calls nested at once, each costing a frame of kernel stack
+const MAX_DEPTH: usize = 8;
+
+/// Iterations after which a while loop is assumed stuck on hardware that never answers
+const MAX_LOOP_COUNT: usize = 0x10_0000;
+
+/// Field flags
+/// - Bit 4: LockRule, accessed under the global lock
+const LOCK_RULE: u8 = 1 << 4;
+
+/// Acquire timeout meaning no timeout
+const WAIT_FOREVER: u64 = 0xFFFF;
+
+/// Runtime strings and buffers, reclaimed after every evaluation
+const ARENA_SIZE: usize = 0x4000;
+
+/// Strings and buffers held by named objects, never reclaimed
+const STORAGE_SIZE: usize = 0x10000;
+
+/// Longest `Sleep` busy waited for, since interrupts stay off throughout
+const MAX_SLEEP_US: u64 = 100_000;
+
+const LOCAL_COUNT: usize = 8;
+const ARG_COUNT: usize = 7;
+
+enum Flow {
+    Next,
+    Return(Value),
+    Break,
+    Continue,
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    None,
+    Debug,
+    Local(usize),
+    Arg(usize),
+    Node(Handle),
+    BufferElement(Bytes, usize),
+    PackageElement(Package, usize),
+}
+
+struct Frame {
+    /// Where declared objects go and relative names resolve from
+    scope: Handle,
+
+    locals: [Value; LOCAL_COUNT],
+    args: [Value; ARG_COUNT],
+}
+impl Frame {
+    fn new(scope: Handle) -> Self {
+        Self {
+            scope,
+            locals: [Value::Uninitialized; LOCAL_COUNT],
+            args: [Value::Uninitialized; ARG_COUNT],
+        }
+    }
+}
+
+struct Arena<const SIZE: usize> {
+    buffer: [u8; SIZE],
+    len: usize,
+}
+impl<const SIZE: usize> Arena<SIZE> {
+    const fn new() -> Self {
+        Self {
+            buffer: [0; SIZE],
+            len: 0,
+        }
+    }
+
+    fn allocate(&mut self, len: usize) -> Result<Bytes, Error> {
+        if len > SIZE - self.len {
+            return Err(Error::ArenaFull);
+        }
+        let bytes = Bytes::from_slice(unsafe {
+            core::slice::from_raw_parts(self.buffer.as_ptr().add(self.len), len)
+        });
+        bytes.as_mut_slice().fill(0);
+        self.len += len;
+        Ok(bytes)
+    }
+
+    /// Copies the concatenation of `parts`
+    fn concat(&mut self, parts: &[&[u8]]) -> Result<Bytes, Error> {
+        let bytes = self.allocate(parts.iter().map(|part| part.len()).sum())?;
+        let mut offset = 0;
+        for part in parts {
+            bytes.as_mut_slice()[offset..offset + part.len()].copy_from_slice(part);
+            offset += part.len();
+        }
+        Ok(bytes)
+    }
+
+    fn contains(&self, bytes: Bytes) -> bool {
+        let start = self.buffer.as_ptr() as usize;
+        let addr = bytes.as_slice().as_ptr() as usize;
+        bytes.len() > 0 && addr >= start && addr < start + SIZE
+    }
+}
+
+fn bit_mask(count: usize) -> u64 {
+    u64::MAX.checked_shr(64 - count as u32).unwrap_or(0)
+}
+
+/// Busy waits, since methods run with the interpreter locked
+fn stall(us: u64) {
+    for _ in 0..us {
+        port::out_byte(port::POST, 0);
+    }
+}
+
+fn hex_digit(nibble: u8) -> u8 {
+    match nibble {
+        0..=9 => b'0' + nibble,
+        _ => b'A' + nibble - 10,
+    }
+}
+
+pub struct Machine {
+    pub namespace: Namespace,
+
+    arena: Arena<ARENA_SIZE>,
+
+    storage: Arena<STORAGE_SIZE>,
+
+    /// Integers are 32-bit in definition blocks of revision 1
+    ones: u64,
+
+    depth: usize,
+
+    /// Acquires of `\_GL_` and accesses of Lock fields in progress, holding the firmware lock
+    global_lock: usize,
+}
+impl Machine {
+    pub const fn new() -> Self {
+        Self {
+            namespace: Namespace::new(),
+            arena: Arena::new(),
+            storage: Arena::new(),
+            ones: u64::MAX,
+            depth: 0,
+            global_lock: 0,
+        }
+    }
+
+    pub fn set_revision(&mut self, revision: u8) {
+        self.ones = if revision < 2 {
+            u32::MAX as u64
+        } else {
+            u64::MAX
+        };
+    }
+
+    /// Runs the top level of a definition block at the root
+    pub fn load(&mut self, code: Bytes) -> Result<(), Error> {
+        let mut parser = Parser::new(code);
+        let end = parser.len();
+        self.term_list(&mut parser, end, &mut Frame::new(Handle::ROOT))
+            .map(|_| ())
+    }
+
+    /// Invokes a method or reads any other object, reclaiming the arena afterwards
+    ///
+    /// Strings and buffers built by the call stay readable until the next evaluation.
+    pub fn evaluate(&mut self, handle: Handle, args: &[Value]) -> Result<Value, Error> {
+        let mark = self.arena.len;
+        let result = self.invoke(handle, args);
+        self.arena.len = mark;
+        // A method that failed or never released would keep firmware out for good
+        if self.global_lock > 0 {
+            self.global_lock = 1;
+            self.release_global_lock();
+        }
+        result
+    }
+
+    fn is_global_lock(&self, target: &Target) -> bool {
+        matches!(target, Target::Node(handle)
+            if self.namespace.child(Handle::ROOT, *b"_GL_") == Some(*handle))
+    }
+
+    /// Takes the firmware lock on the outermost of nested acquires
+    fn acquire_global_lock(&mut self, timeout_ms: u64) -> bool {
+        if self.global_lock == 0 && !facs::acquire_global_lock(timeout_ms) {
+            return false;
+        }
+        self.global_lock += 1;
+        true
+    }
+
+    fn release_global_lock(&mut self) {
+        match self.global_lock {
+            0 => {}
+            1 => {
+                self.global_lock = 0;
+                facs::release_global_lock();
+            }
+            _ => self.global_lock -= 1,
+        }
+    }
+
+    /// Runs `f` under the global lock if the LockRule of `field` asks for it
+    fn with_field_lock<T>(
+        &mut self,
+        field: &Field,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if field.flags & LOCK_RULE == 0 {
+            return f(self);
+        }
+        if !self.acquire_global_lock(WAIT_FOREVER) {
+            return Err(Error::GlobalLockTimeout);
+        }
+        let result = f(self);
+        self.release_global_lock();
+        result
+    }
+
+    /// Element `index` of `package`, with a name resolved to a reference
+    pub fn element(&self, package: &Package, index: usize) -> Result<Value, Error> {
+        match package.element(index)? {
+            Element::Value(Value::Integer(u64::MAX)) => Ok(Value::Integer(self.ones)),
+            Element::Value(value) => Ok(value),
+            Element::Name(path) => self
+                .namespace
+                .resolve(package.scope, &path)
+                .map(Value::Reference)
+                .ok_or(Error::NotFound(path.last().unwrap_or([0; 4]))),
+        }
+    }
+
+    fn term_list(&mut self, p: &mut Parser, end: usize, frame: &mut Frame) -> Result<Flow, Error> {
+        while p.pc < end {
+            match self.term(p, frame)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Runs `term_list` inside the object just declared at `handle`
+    fn scoped(
+        &mut self,
+        p: &mut Parser,
+        end: usize,
+        frame: &mut Frame,
+        handle: Handle,
+    ) -> Result<Flow, Error> {
+        let scope = frame.scope;
+        frame.scope = handle;
+        let flow = self.term_list(p, end, frame);
+        frame.scope = scope;
+        p.pc = end;
+        flow
+    }
+
+    fn declare(&mut self, p: &mut Parser, frame: &Frame, object: Object) -> Result<Handle, Error> {
+        let path = p.name_string()?;
+        let (parent, name) = self.namespace.parent_of(frame.scope, &path)?;
+        self.namespace.add(parent, name, object)
+    }
+
+    fn term(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Flow, Error> {
+        match p.peek()? {
+            ALIAS_OP => {
+                p.pc += 1;
+                let source = p.name_string()?;
+                let target = self
+                    .namespace
+                    .resolve(frame.scope, &source)
+                    .ok_or(Error::NotFound(source.last().unwrap_or([0; 4])))?;
+                self.declare(p, frame, Object::Alias(target))?;
+            }
+            NAME_OP => {
+                p.pc += 1;
+                let path = p.name_string()?;
+                let value = self.term_arg(p, frame)?;
+                let (parent, name) = self.namespace.parent_of(frame.scope, &path)?;
+                self.namespace.add(parent, name, Object::Name(value))?;
+            }
+            SCOPE_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let path = p.name_string()?;
+                let handle = self
+                    .namespace
+                    .resolve(frame.scope, &path)
+                    .ok_or(Error::NotFound(path.last().unwrap_or([0; 4])))?;
+                return self.scoped(p, end, frame, handle);
+            }
+            METHOD_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let path = p.name_string()?;
+                let flags = p.byte()?;
+                let code = p.rest(end)?;
+                let (parent, name) = self.namespace.parent_of(frame.scope, &path)?;
+                self.namespace
+                    .add(parent, name, Object::Method { code, flags })?;
+            }
+            EXTERNAL_OP => {
+                p.pc += 1;
+                let path = p.name_string()?;
+                // ObjectType and ArgumentCount
+                p.byte()?;
+                p.byte()?;
+                // The parent may itself be external and missing, which is harmless until used
+                if let Ok((parent, name)) = self.namespace.parent_of(frame.scope, &path) {
+                    self.namespace.add(parent, name, Object::External)?;
+                }
+            }
+            EXT_OP_PREFIX => return self.ext_term(p, frame),
+            IF_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let predicate = self.integer_arg(p, frame)?;
+                if predicate != 0 {
+                    let flow = self.term_list(p, end, frame)?;
+                    p.pc = end;
+                    if p.peek().ok() == Some(ELSE_OP) {
+                        p.pc += 1;
+                        p.pc = p.pkg_length()?;
+                    }
+                    return Ok(flow);
+                }
+                p.pc = end;
+                if p.peek().ok() == Some(ELSE_OP) {
+                    p.pc += 1;
+                    let end = p.pkg_length()?;
+                    let flow = self.term_list(p, end, frame)?;
+                    p.pc = end;
+                    return Ok(flow);
+                }
+            }
+            WHILE_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let start = p.pc;
+                let mut count = 0;
+                loop {
+                    p.pc = start;
+                    if self.integer_arg(p, frame)? == 0 {
+                        break;
+                    }
+                    count += 1;
+                    if count == MAX_LOOP_COUNT {
+                        return Err(Error::LoopTimeout);
+                    }
+                    match self.term_list(p, end, frame)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+                p.pc = end;
+            }
+            RETURN_OP => {
+                p.pc += 1;
+                return Ok(Flow::Return(self.term_arg(p, frame)?));
+            }
+            BREAK_OP => {
+                p.pc += 1;
+                return Ok(Flow::Break);
+            }
+            CONTINUE_OP => {
+                p.pc += 1;
+                return Ok(Flow::Continue);
+            }
+            NOOP_OP | BREAK_POINT_OP => p.pc += 1,
+            NOTIFY_OP => {
+                p.pc += 1;
+                let target = self.target(p, frame)?;
+                let value = self.integer_arg(p, frame)?;
+                if let Target::Node(handle) = target {
+                    debug!("AML", "Notify ", handle, " ", value);
+                }
+            }
+            opcode @ (CREATE_DWORD_FIELD_OP
+            | CREATE_WORD_FIELD_OP
+            | CREATE_BYTE_FIELD_OP
+            | CREATE_BIT_FIELD_OP
+            | CREATE_QWORD_FIELD_OP) => {
+                p.pc += 1;
+                let buffer = self.buffer_arg(p, frame)?;
+                let index = self.integer_arg(p, frame)? as usize;
+                let (bit_offset, bit_length) = match opcode {
+                    CREATE_BIT_FIELD_OP => (index, 1),
+                    CREATE_BYTE_FIELD_OP => (index * 8, 8),
+                    CREATE_WORD_FIELD_OP => (index * 8, 16),
+                    CREATE_DWORD_FIELD_OP => (index * 8, 32),
+                    _ => (index * 8, 64),
+                };
+                self.create_buffer_field(p, frame, buffer, bit_offset, bit_length)?;
+            }
+            _ => {
+                self.term_arg(p, frame)?;
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn ext_term(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Flow, Error> {
+        let opcode = p.peek_second()?;
+        match opcode {
+            MUTEX_OP => {
+                p.pc += 2;
+                let path = p.name_string()?;
+                // SyncFlags
+                p.byte()?;
+                let (parent, name) = self.namespace.parent_of(frame.scope, &path)?;
+                self.namespace.add(parent, name, Object::Mutex)?;
+            }
+            EVENT_OP => {
+                p.pc += 2;
+                self.declare(p, frame, Object::Event)?;
+            }
+            OP_REGION_OP => {
+                p.pc += 2;
+                let path = p.name_string()?;
+                let space = p.byte()?;
+                let offset = self.integer_arg(p, frame)?;
+                let length = self.integer_arg(p, frame)?;
+                let (parent, name) = self.namespace.parent_of(frame.scope, &path)?;
+                let region = Region {
+                    space,
+                    offset,
+                    length,
+                };
+                self.namespace.add(parent, name, Object::Region(region))?;
+            }
+            FIELD_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let region = self.lookup(p, frame)?;
+                let flags = p.byte()?;
+                self.field_list(p, end, frame, FieldKind::Region(region), flags)?;
+            }
+            INDEX_FIELD_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let index = self.lookup(p, frame)?;
+                let data = self.lookup(p, frame)?;
+                let flags = p.byte()?;
+                self.field_list(p, end, frame, FieldKind::Index { index, data }, flags)?;
+            }
+            BANK_FIELD_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let region = self.lookup(p, frame)?;
+                let bank = self.lookup(p, frame)?;
+                let value = self.integer_arg(p, frame)?;
+                let flags = p.byte()?;
+                let kind = FieldKind::Bank {
+                    region,
+                    bank,
+                    value,
+                };
+                self.field_list(p, end, frame, kind, flags)?;
+            }
+            DEVICE_OP | THERMAL_ZONE_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let object = if opcode == DEVICE_OP {
+                    Object::Device
+                } else {
+                    Object::ThermalZone
+                };
+                let handle = self.declare(p, frame, object)?;
+                return self.scoped(p, end, frame, handle);
+            }
+            PROCESSOR_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let handle = self.declare(p, frame, Object::Processor)?;
+                // ProcID, PblkAddr and PblkLen
+                p.byte()?;
+                p.dword()?;
+                p.byte()?;
+                return self.scoped(p, end, frame, handle);
+            }
+            POWER_RES_OP => {
+                p.pc += 2;
+                let end = p.pkg_length()?;
+                let handle = self.declare(p, frame, Object::PowerResource)?;
+                // SystemLevel and ResourceOrder
+                p.byte()?;
+                p.word()?;
+                return self.scoped(p, end, frame, handle);
+            }
+            CREATE_FIELD_OP => {
+                p.pc += 2;
+                let buffer = self.buffer_arg(p, frame)?;
+                let bit_offset = self.integer_arg(p, frame)? as usize;
+                let bit_length = self.integer_arg(p, frame)? as usize;
+                self.create_buffer_field(p, frame, buffer, bit_offset, bit_length)?;
+            }
+            STALL_OP => {
+                p.pc += 2;
+                stall(self.integer_arg(p, frame)?);
+            }
+            SLEEP_OP => {
+                p.pc += 2;
+                stall(
+                    self.integer_arg(p, frame)?
+                        .saturating_mul(1000)
+                        .min(MAX_SLEEP_US),
+                );
+            }
+            // Methods already run one at a time
+            SIGNAL_OP | RESET_OP => {
+                p.pc += 2;
+                self.target(p, frame)?;
+            }
+            RELEASE_OP => {
+                p.pc += 2;
+                let target = self.target(p, frame)?;
+                if self.is_global_lock(&target) {
+                    self.release_global_lock();
+                }
+            }
+            FATAL_OP => {
+                p.pc += 2;
+                let kind = p.byte()?;
+                let code = p.dword()?;
+                let arg = self.integer_arg(p, frame)?;
+                return Err(Error::Fatal(kind, code, arg));
+            }
+            _ => {
+                self.term_arg(p, frame)?;
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Resolves a NameString that must already exist
+    fn lookup(&mut self, p: &mut Parser, frame: &Frame) -> Result<Handle, Error> {
+        let path = p.name_string()?;
+        self.namespace
+            .resolve(frame.scope, &path)
+            .ok_or(Error::NotFound(path.last().unwrap_or([0; 4])))
+    }
+
+    fn create_buffer_field(
+        &mut self,
+        p: &mut Parser,
+        frame: &Frame,
+        buffer: Bytes,
+        bit_offset: usize,
+        bit_length: usize,
+    ) -> Result<(), Error> {
+        if bit_length == 0 || bit_length > 64 || bit_offset + bit_length > buffer.len() * 8 {
+            return Err(Error::InvalidIndex(bit_offset as u64));
+        }
+        let object = Object::BufferField {
+            buffer,
+            bit_offset,
+            bit_length,
+        };
+        self.declare(p, frame, object)?;
+        Ok(())
+    }
+
+    fn field_list(
+        &mut self,
+        p: &mut Parser,
+        end: usize,
+        frame: &Frame,
+        kind: FieldKind,
+        mut flags: u8,
+    ) -> Result<(), Error> {
+        let mut bit_offset = 0;
+        while p.pc < end {
+            match p.peek()? {
+                RESERVED_FIELD => {
+                    p.pc += 1;
+                    bit_offset += p.pkg_length_value()?;
+                }
+                ACCESS_FIELD => {
+                    p.pc += 1;
+                    let access_type = p.byte()?;
+                    // AccessAttrib
+                    p.byte()?;
+                    flags = (flags & !0xF) | (access_type & 0xF);
+                }
+                EXTENDED_ACCESS_FIELD => {
+                    p.pc += 1;
+                    let access_type = p.byte()?;
+                    // AccessAttrib and AccessLength
+                    p.byte()?;
+                    p.byte()?;
+                    flags = (flags & !0xF) | (access_type & 0xF);
+                }
+                _ => {
+                    let name = p.name_seg()?;
+                    let bit_length = p.pkg_length_value()?;
+                    let field = Field {
+                        kind,
+                        bit_offset,
+                        bit_length,
+                        flags,
+                    };
+                    self.namespace
+                        .add(frame.scope, name, Object::Field(field))?;
+                    bit_offset += bit_length;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn integer_arg(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<u64, Error> {
+        let value = self.term_arg(p, frame)?;
+        self.integer(value)
+    }
+
+    fn buffer_arg(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Bytes, Error> {
+        match self.term_arg(p, frame)? {
+            Value::Buffer(bytes) => Ok(bytes),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    fn term_arg(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Value, Error> {
+        let opcode = p.peek()?;
+        if Parser::is_name_start(opcode) {
+            return self.name_term(p, frame);
+        }
+        match opcode {
+            value::ZERO_OP
+            | value::ONE_OP
+            | value::BYTE_PREFIX
+            | value::WORD_PREFIX
+            | value::DWORD_PREFIX
+            | value::QWORD_PREFIX
+            | value::STRING_PREFIX => value::constant(p, frame.scope),
+            value::ONES_OP => {
+                p.pc += 1;
+                Ok(Value::Integer(self.ones))
+            }
+            value::BUFFER_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let size = self.integer_arg(p, frame)? as usize;
+                let initializer = p.rest(end)?.as_slice();
+                // Ones declared at load time belong to their node, not to an evaluation
+                let bytes = if self.depth == 0 {
+                    self.storage.allocate(size)?
+                } else {
+                    self.arena.allocate(size)?
+                };
+                let len = size.min(initializer.len());
+                bytes.as_mut_slice()[..len].copy_from_slice(&initializer[..len]);
+                Ok(Value::Buffer(bytes))
+            }
+            value::PACKAGE_OP | value::VAR_PACKAGE_OP => {
+                p.pc += 1;
+                let end = p.pkg_length()?;
+                let count = if opcode == value::PACKAGE_OP {
+                    p.byte()? as usize
+                } else {
+                    self.integer_arg(p, frame)? as usize
+                };
+                Ok(Value::Package(Package::new(
+                    p.rest(end)?,
+                    count,
+                    frame.scope,
+                )))
+            }
+            LOCAL0_OP..=LOCAL7_OP => {
+                p.pc += 1;
+                Ok(frame.locals[(opcode - LOCAL0_OP) as usize])
+            }
+            ARG0_OP..=ARG6_OP => {
+                p.pc += 1;
+                Ok(frame.args[(opcode - ARG0_OP) as usize])
+            }
+            STORE_OP | COPY_OBJECT_OP => {
+                p.pc += 1;
+                let value = self.term_arg(p, frame)?;
+                let target = self.target(p, frame)?;
+                self.store(target, value, frame, opcode == STORE_OP)?;
+                Ok(value)
+            }
+            REF_OF_OP => {
+                p.pc += 1;
+                match self.target(p, frame)? {
+                    Target::Node(handle) => Ok(Value::Reference(handle)),
+                    Target::BufferElement(bytes, index) => Ok(Value::BufferElement(bytes, index)),
+                    Target::PackageElement(package, index) => {
+                        Ok(Value::PackageElement(package, index))
+                    }
+                    _ => Err(Error::InvalidType),
+                }
+            }
+            ADD_OP | SUBTRACT_OP | MULTIPLY_OP | SHIFT_LEFT_OP | SHIFT_RIGHT_OP | AND_OP
+            | NAND_OP | OR_OP | NOR_OP | XOR_OP | MOD_OP => {
+                p.pc += 1;
+                let a = self.integer_arg(p, frame)?;
+                let b = self.integer_arg(p, frame)?;
+                let result = match opcode {
+                    ADD_OP => a.wrapping_add(b),
+                    SUBTRACT_OP => a.wrapping_sub(b),
+                    MULTIPLY_OP => a.wrapping_mul(b),
+                    SHIFT_LEFT_OP if b < 64 => a << b,
+                    SHIFT_RIGHT_OP if b < 64 => a >> b,
+                    SHIFT_LEFT_OP | SHIFT_RIGHT_OP => 0,
+                    AND_OP => a & b,
+                    NAND_OP => !(a & b),
+                    OR_OP => a | b,
+                    NOR_OP => !(a | b),
+                    XOR_OP => a ^ b,
+                    _ => a.checked_rem(b).ok_or(Error::DivideByZero)?,
+                };
+                self.result(p, frame, Value::Integer(result & self.ones))
+            }
+            NOT_OP | FIND_SET_LEFT_BIT_OP | FIND_SET_RIGHT_BIT_OP => {
+                p.pc += 1;
+                let a = self.integer_arg(p, frame)?;
+                let result = match opcode {
+                    NOT_OP => !a,
+                    // 1-based, 0 when no bit is set
+                    FIND_SET_LEFT_BIT_OP => (64 - a.leading_zeros()) as u64,
+                    _ if a == 0 => 0,
+                    _ => a.trailing_zeros() as u64 + 1,
+                };
+                self.result(p, frame, Value::Integer(result & self.ones))
+            }
+            INCREMENT_OP | DECREMENT_OP => {
+                p.pc += 1;
+                let target = self.target(p, frame)?;
+                let value = self.read_target(target, frame)?;
+                let value = self.integer(value)?;
+                let value = if opcode == INCREMENT_OP {
+                    value.wrapping_add(1)
+                } else {
+                    value.wrapping_sub(1)
+                };
+                let value = Value::Integer(value & self.ones);
+                self.store(target, value, frame, true)?;
+                Ok(value)
+            }
+            DIVIDE_OP => {
+                p.pc += 1;
+                let dividend = self.integer_arg(p, frame)?;
+                let divisor = self.integer_arg(p, frame)?;
+                if divisor == 0 {
+                    return Err(Error::DivideByZero);
+                }
+                let remainder = self.target(p, frame)?;
+                self.store(remainder, Value::Integer(dividend % divisor), frame, true)?;
+                self.result(p, frame, Value::Integer(dividend / divisor))
+            }
+            DEREF_OF_OP => {
+                p.pc += 1;
+                let value = self.term_arg(p, frame)?;
+                self.deref(value)
+            }
+            CONCAT_OP | CONCAT_RES_OP => {
+                p.pc += 1;
+                let a = self.term_arg(p, frame)?;
+                let b = self.term_arg(p, frame)?;
+                let result = if opcode == CONCAT_OP {
+                    self.concat(a, b)?
+                } else {
+                    self.concat_res(a, b)?
+                };
+                self.result(p, frame, result)
+            }
+            SIZE_OF_OP => {
+                p.pc += 1;
+                let target = self.target(p, frame)?;
+                let value = self.read_target(target, frame)?;
+                let size = match value {
+                    Value::String(bytes) | Value::Buffer(bytes) => bytes.len(),
+                    Value::Package(package) => package.len(),
+                    _ => return Err(Error::InvalidType),
+                };
+                Ok(Value::Integer(size as u64))
+            }
+            INDEX_OP => {
+                p.pc += 1;
+                let source = self.term_arg(p, frame)?;
+                let index = self.integer_arg(p, frame)?;
+                let element = match source {
+                    Value::String(bytes) | Value::Buffer(bytes)
+                        if (index as usize) < bytes.len() =>
+                    {
+                        Value::BufferElement(bytes, index as usize)
+                    }
+                    Value::Package(package) if (index as usize) < package.len() => {
+                        Value::PackageElement(package, index as usize)
+                    }
+                    Value::String(_) | Value::Buffer(_) | Value::Package(_) => {
+                        return Err(Error::InvalidIndex(index));
+                    }
+                    _ => return Err(Error::InvalidType),
+                };
+                let target = self.target(p, frame)?;
+                self.store(target, element, frame, false)?;
+                Ok(element)
+            }
+            MATCH_OP => {
+                p.pc += 1;
+                let package = self.term_arg(p, frame)?.as_package()?;
+                let op1 = p.byte()?;
+                let a = self.integer_arg(p, frame)?;
+                let op2 = p.byte()?;
+                let b = self.integer_arg(p, frame)?;
+                let start = self.integer_arg(p, frame)? as usize;
+                for i in start..package.len() {
+                    let Ok(element) = self.element(&package, i).and_then(|e| self.integer(e))
+                    else {
+                        continue;
+                    };
+                    if matches(op1, element, a) && matches(op2, element, b) {
+                        return Ok(Value::Integer(i as u64));
+                    }
+                }
+                Ok(Value::Integer(self.ones))
+            }
+            OBJECT_TYPE_OP => {
+                p.pc += 1;
+                let code = match self.target(p, frame)? {
+                    Target::Node(handle) => self.namespace.get(handle).object.type_code(),
+                    Target::Local(i) => frame.locals[i].type_code(),
+                    Target::Arg(i) => frame.args[i].type_code(),
+                    Target::Debug => 16,
+                    Target::BufferElement(..) => 1,
+                    Target::PackageElement(package, index) => {
+                        self.element(&package, index)?.type_code()
+                    }
+                    Target::None => 0,
+                };
+                Ok(Value::Integer(code))
+            }
+            LAND_OP | LOR_OP => {
+                p.pc += 1;
+                let a = self.integer_arg(p, frame)? != 0;
+                let b = self.integer_arg(p, frame)? != 0;
+                Ok(self.boolean(if opcode == LAND_OP { a && b } else { a || b }))
+            }
+            LNOT_OP => {
+                p.pc += 1;
+                let a = self.integer_arg(p, frame)?;
+                Ok(self.boolean(a == 0))
+            }
+            LEQUAL_OP | LGREATER_OP | LLESS_OP => {
+                p.pc += 1;
+                let a = self.term_arg(p, frame)?;
+                let b = self.term_arg(p, frame)?;
+                let ordering = self.compare(a, b)?;
+                Ok(self.boolean(
+                    ordering
+                        == match opcode {
+                            LEQUAL_OP => Ordering::Equal,
+                            LGREATER_OP => Ordering::Greater,
+                            _ => Ordering::Less,
+                        },
+                ))
+            }
+            TO_BUFFER_OP | TO_DECIMAL_STRING_OP | TO_HEX_STRING_OP | TO_INTEGER_OP => {
+                p.pc += 1;
+                let value = self.term_arg(p, frame)?;
+                let result = match opcode {
+                    TO_BUFFER_OP => self.to_buffer(value)?,
+                    TO_DECIMAL_STRING_OP => self.to_string(value, 10)?,
+                    TO_HEX_STRING_OP => self.to_string(value, 16)?,
+                    _ => Value::Integer(self.to_integer(value)?),
+                };
+                self.result(p, frame, result)
+            }
+            TO_STRING_OP => {
+                p.pc += 1;
+                let bytes = self.buffer_arg(p, frame)?.as_slice();
+                let length = self.integer_arg(p, frame)? as usize;
+                let end = bytes
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(bytes.len())
+                    .min(length);
+                self.result(p, frame, Value::String(Bytes::from_slice(&bytes[..end])))
+            }
+            MID_OP => {
+                p.pc += 1;
+                let source = self.term_arg(p, frame)?;
+                let index = self.integer_arg(p, frame)? as usize;
+                let length = self.integer_arg(p, frame)? as usize;
+                let bytes = source.as_bytes()?;
+                let start = index.min(bytes.len());
+                let end = start.saturating_add(length).min(bytes.len());
+                let bytes = Bytes::from_slice(&bytes[start..end]);
+                let result = match source {
+                    Value::String(_) => Value::String(bytes),
+                    _ => Value::Buffer(bytes),
+                };
+                self.result(p, frame, result)
+            }
+            EXT_OP_PREFIX => self.ext_term_arg(p, frame),
+            _ => Err(Error::InvalidOpcode(opcode as u16)),
+        }
+    }
+
+    fn ext_term_arg(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Value, Error> {
+        let opcode = p.peek_second()?;
+        p.pc += 2;
+        match opcode {
+            COND_REF_OF_OP => {
+                let found = if Parser::is_name_start(p.peek()?) {
+                    let path = p.name_string()?;
+                    self.namespace
+                        .resolve(frame.scope, &path)
+                        .map(Value::Reference)
+                } else {
+                    match self.target(p, frame)? {
+                        Target::Local(i) => Some(frame.locals[i]),
+                        Target::Arg(i) => Some(frame.args[i]),
+                        _ => None,
+                    }
+                    .filter(|value| !matches!(value, Value::Uninitialized))
+                };
+                let target = self.target(p, frame)?;
+                let Some(reference) = found else {
+                    return Ok(Value::Integer(0));
+                };
+                self.store(target, reference, frame, false)?;
+                Ok(Value::Integer(self.ones))
+            }
+            // Nothing else runs AML, so only the global lock, shared with firmware, is waited on
+            ACQUIRE_OP => {
+                let target = self.target(p, frame)?;
+                let timeout = p.word()?;
+                let acquired =
+                    !self.is_global_lock(&target) || self.acquire_global_lock(timeout as u64);
+                Ok(Value::Integer(if acquired { 0 } else { self.ones }))
+            }
+            WAIT_OP => {
+                self.target(p, frame)?;
+                self.integer_arg(p, frame)?;
+                Ok(Value::Integer(0))
+            }
+            FROM_BCD_OP | TO_BCD_OP => {
+                let mut a = self.integer_arg(p, frame)?;
+                let mut result: u64 = 0;
+                let mut digit = 0;
+                while a != 0 && digit < 16 {
+                    if opcode == FROM_BCD_OP {
+                        result += (a & 0xF) * 10u64.pow(digit);
+                        a >>= 4;
+                    } else {
+                        result |= (a % 10) << (4 * digit);
+                        a /= 10;
+                    }
+                    digit += 1;
+                }
+                self.result(p, frame, Value::Integer(result & self.ones))
+            }
+            REVISION_OP => Ok(Value::Integer(REVISION)),
+            // 100 ns units
+            TIMER_OP => Ok(Value::Integer(time::uptime_ms() * 10_000)),
+            _ => Err(Error::InvalidOpcode(
+                (EXT_OP_PREFIX as u16) << 8 | opcode as u16,
+            )),
+        }
+    }
+
+    /// Stores the result of an operator in its Target operand and returns it
+    fn result(&mut self, p: &mut Parser, frame: &mut Frame, value: Value) -> Result<Value, Error> {
+        let target = self.target(p, frame)?;
+        self.store(target, value, frame, true)?;
+        Ok(value)
+    }
+
+    fn boolean(&self, value: bool) -> Value {
+        Value::Integer(if value { self.ones } else { 0 })
+    }
+
+    /// A name as an operand: a method is called, anything else is read
+    fn name_term(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Value, Error> {
+        let path = p.name_string()?;
+        let handle = self
+            .namespace
+            .resolve(frame.scope, &path)
+            .ok_or(Error::NotFound(path.last().unwrap_or([0; 4])))?;
+        let arg_count = match self.namespace.get(handle).object {
+            Object::Method { flags, .. } => (flags & 0x7) as usize,
+            Object::Native { arg_count, .. } => arg_count,
+            _ => return self.read(handle),
+        };
+        let mut args = [Value::Uninitialized; ARG_COUNT];
+        for arg in &mut args[..arg_count] {
+            *arg = self.term_arg(p, frame)?;
+        }
+        self.invoke(handle, &args[..arg_count])
+    }
+
+    fn invoke(&mut self, handle: Handle, args: &[Value]) -> Result<Value, Error> {
+        let code = match self.namespace.get(handle).object {
+            Object::Method { code, .. } => code,
+            Object::Native { function, .. } => {
+                return Ok(match function(args) {
+                    Value::Integer(value) => Value::Integer(value & self.ones),
+                    value => value,
+                });
+            }
+            _ => return self.read(handle),
+        };
+        if self.depth == MAX_DEPTH {
+            return Err(Error::TooDeep);
+        }
+
+        let mut frame = Frame::new(handle);
+        let count = args.len().min(ARG_COUNT);
+        frame.args[..count].copy_from_slice(&args[..count]);
+        let mark = self.namespace.len();
+        self.depth += 1;
+        let mut parser = Parser::new(code);
+        let end = parser.len();
+        let flow = self.term_list(&mut parser, end, &mut frame);
+        self.depth -= 1;
+        self.namespace.truncate(mark);
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Integer(0)),
+        }
+    }
+
+    fn read(&mut self, handle: Handle) -> Result<Value, Error> {
+        match self.namespace.get(handle).object {
+            Object::Name(value) => Ok(value),
+            Object::Field(field) => {
+                self.with_field_lock(&field, |machine| machine.read_field(&field))
+            }
+            Object::BufferField {
+                buffer,
+                bit_offset,
+                bit_length,
+            } => Ok(Value::Integer(read_bits(
+                buffer.as_slice(),
+                bit_offset,
+                bit_length,
+            ))),
+            Object::Method { .. } | Object::Native { .. } => self.invoke(handle, &[]),
+            _ => Ok(Value::Reference(handle)),
+        }
+    }
+
+    fn target(&mut self, p: &mut Parser, frame: &mut Frame) -> Result<Target, Error> {
+        let opcode = p.peek()?;
+        if Parser::is_name_start(opcode) {
+            let path = p.name_string()?;
+            return self
+                .namespace
+                .resolve(frame.scope, &path)
+                .map(Target::Node)
+                .ok_or(Error::NotFound(path.last().unwrap_or([0; 4])));
+        }
+        match opcode {
+            value::ZERO_OP => {
+                p.pc += 1;
+                Ok(Target::None)
+            }
+            LOCAL0_OP..=LOCAL7_OP => {
+                p.pc += 1;
+                Ok(Target::Local((opcode - LOCAL0_OP) as usize))
+            }
+            ARG0_OP..=ARG6_OP => {
+                p.pc += 1;
+                Ok(Target::Arg((opcode - ARG0_OP) as usize))
+            }
+            EXT_OP_PREFIX if p.peek_second()? == DEBUG_OP => {
+                p.pc += 2;
+                Ok(Target::Debug)
+            }
+            _ => match self.term_arg(p, frame)? {
+                Value::Reference(handle) => Ok(Target::Node(handle)),
+                Value::BufferElement(bytes, index) => Ok(Target::BufferElement(bytes, index)),
+                Value::PackageElement(package, index) => Ok(Target::PackageElement(package, index)),
+                _ => Err(Error::InvalidType),
+            },
+        }
+    }
+
+    fn read_target(&mut self, target: Target, frame: &Frame) -> Result<Value, Error> {
+        match target {
+            Target::Local(i) => Ok(frame.locals[i]),
+            Target::Arg(i) => match frame.args[i] {
+                Value::Reference(handle) => self.read(handle),
+                value => Ok(value),
+            },
+            Target::Node(handle) => self.read(handle),
+            Target::BufferElement(bytes, index) => {
+                Ok(Value::Integer(bytes.as_slice()[index] as u64))
+            }
+            Target::PackageElement(package, index) => self.element(&package, index),
+            Target::None | Target::Debug => Err(Error::InvalidType),
+        }
+    }
+
+    /// `convert` applies the implicit conversion of `Store`, leaving `CopyObject` to replace
+    fn store(
+        &mut self,
+        target: Target,
+        value: Value,
+        frame: &mut Frame,
+        convert: bool,
+    ) -> Result<(), Error> {
+        match target {
+            Target::None => {}
+            Target::Debug => debug!("AML", value),
+            Target::Local(i) => frame.locals[i] = value,
+            Target::Arg(i) => match frame.args[i] {
+                Value::Reference(handle) => self.store_node(handle, value, convert)?,
+                _ => frame.args[i] = value,
+            },
+            Target::Node(handle) => self.store_node(handle, value, convert)?,
+            Target::BufferElement(bytes, index) => {
+                bytes.as_mut_slice()[index] = self.integer(value)? as u8;
+            }
+            Target::PackageElement(..) => return Err(Error::InvalidType),
+        }
+        Ok(())
+    }
+
+    fn store_node(&mut self, handle: Handle, value: Value, convert: bool) -> Result<(), Error> {
+        match self.namespace.get(handle).object {
+            Object::Name(Value::Integer(_)) if convert => {
+                let value = Value::Integer(self.integer(value)?);
+                self.namespace.get_mut(handle).object = Object::Name(value);
+            }
+            // Stays the same size, truncated or zero-filled
+            Object::Name(Value::Buffer(bytes)) if convert => {
+                let mut scratch = [0; 8];
+                let source = self.bytes(value, &mut scratch)?;
+                let target = bytes.as_mut_slice();
+                let len = source.len().min(target.len());
+                target[..len].copy_from_slice(&source[..len]);
+                target[len..].fill(0);
+            }
+            Object::Name(replaced) => {
+                let value = self.persist(value, replaced)?;
+                self.namespace.get_mut(handle).object = Object::Name(value);
+            }
+            Object::External => {
+                let value = self.persist(value, Value::Uninitialized)?;
+                self.namespace.get_mut(handle).object = Object::Name(value);
+            }
+            Object::Field(field) => {
+                let value = self.integer(value)?;
+                self.with_field_lock(&field, |machine| machine.write_field(&field, value))?;
+            }
+            Object::BufferField {
+                buffer,
+                bit_offset,
+                bit_length,
+            } => {
+                let value = self.integer(value)?;
+                write_bits(buffer.as_mut_slice(), bit_offset, bit_length, value);
+            }
+            _ => return Err(Error::InvalidType),
+        }
+        Ok(())
+    }
+
+    /// Copies a string or buffer out of the arena for a named object to hold, into the storage
+    /// of the value it replaces when that is large enough
+    fn persist(&mut self, value: Value, replaced: Value) -> Result<Value, Error> {
+        let source = match value {
+            Value::String(bytes) | Value::Buffer(bytes) => bytes,
+            _ => return Ok(value),
+        };
+        let target = match replaced {
+            Value::String(bytes) | Value::Buffer(bytes)
+                if self.storage.contains(bytes) && bytes.len() >= source.len() =>
+            {
+                Bytes::from_slice(&bytes.as_slice()[..source.len()])
+            }
+            _ => self.storage.allocate(source.len())?,
+        };
+        // Storing a value into its own node copies over itself
+        unsafe {
+            core::ptr::copy(
+                source.as_slice().as_ptr(),
+                target.as_mut_slice().as_mut_ptr(),
+                source.len(),
+            );
+        }
+        Ok(match value {
+            Value::String(_) => Value::String(target),
+            _ => Value::Buffer(target),
+        })
+    }
+
+    fn deref(&mut self, value: Value) -> Result<Value, Error> {
+        match value {
+            Value::Reference(handle) => self.read(handle),
+            Value::BufferElement(bytes, index) => {
+                Ok(Value::Integer(bytes.as_slice()[index] as u64))
+            }
+            Value::PackageElement(package, index) => self.element(&package, index),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    /// Implicit conversion to an integer
+    fn integer(&mut self, value: Value) -> Result<u64, Error> {
+        match value {
+            Value::Integer(value) => Ok(value),
+            Value::Buffer(bytes) => {
+                let width = if self.ones == u64::MAX { 8 } else { 4 };
+                Ok(bytes
+                    .as_slice()
+                    .iter()
+                    .take(width)
+                    .rev()
+                    .fold(0, |acc, &byte| acc << 8 | byte as u64))
+            }
+            // Hexadecimal up to the first other character
+            Value::String(bytes) => Ok(bytes
+                .as_slice()
+                .iter()
+                .map_while(|&c| (c as char).to_digit(16))
+                .fold(0u64, |acc, digit| acc << 4 | digit as u64)
+                & self.ones),
+            Value::Reference(_) | Value::BufferElement(..) | Value::PackageElement(..) => {
+                let value = self.deref(value)?;
+                self.integer(value)
+            }
+            Value::Uninitialized | Value::Package(_) => Err(Error::InvalidType),
+        }
+    }
+
+    /// Bytes of a string or buffer, or of an integer at the table width
+    fn bytes<'a>(&mut self, value: Value, scratch: &'a mut [u8; 8]) -> Result<&'a [u8], Error> {
+        match value {
+            Value::String(bytes) | Value::Buffer(bytes) => Ok(bytes.as_slice()),
+            _ => {
+                *scratch = self.integer(value)?.to_le_bytes();
+                let width = if self.ones == u64::MAX { 8 } else { 4 };
+                Ok(&scratch[..width])
+            }
+        }
+    }
+
+    fn compare(&mut self, a: Value, b: Value) -> Result<Ordering, Error> {
+        match a {
+            Value::String(a) | Value::Buffer(a) => {
+                let mut scratch = [0; 8];
+                let b = self.bytes(b, &mut scratch)?;
+                Ok(a.as_slice().cmp(b))
+            }
+            _ => Ok(self.integer(a)?.cmp(&self.integer(b)?)),
+        }
+    }
+
+    /// Takes the type of `a`, with an integer `a` making a buffer
+    fn concat(&mut self, a: Value, b: Value) -> Result<Value, Error> {
+        let (mut scratch_a, mut scratch_b) = ([0; 8], [0; 8]);
+        let is_string = matches!(a, Value::String(_));
+        let b = if is_string && !matches!(b, Value::String(_)) {
+            self.to_string(b, 16)?
+        } else {
+            b
+        };
+        let a = self.bytes(a, &mut scratch_a)?;
+        let b = self.bytes(b, &mut scratch_b)?;
+        let bytes = self.arena.concat(&[a, b])?;
+        Ok(if is_string {
+            Value::String(bytes)
+        } else {
+            Value::Buffer(bytes)
+        })
+    }
+
+    /// Joins two resource templates, keeping only the end tag of `b`
+    fn concat_res(&mut self, a: Value, b: Value) -> Result<Value, Error> {
+        let a = a.as_bytes()?;
+        let b = b.as_bytes()?;
+        let a = &a[..a.len().saturating_sub(2)];
+        Ok(Value::Buffer(self.arena.concat(&[a, b])?))
+    }
+
+    fn to_buffer(&mut self, value: Value) -> Result<Value, Error> {
+        match value {
+            Value::Buffer(_) => Ok(value),
+            // The NUL comes along
+            Value::String(bytes) => {
+                Ok(Value::Buffer(self.arena.concat(&[bytes.as_slice(), &[0]])?))
+            }
+            _ => {
+                let mut scratch = [0; 8];
+                let bytes = self.bytes(value, &mut scratch)?;
+                Ok(Value::Buffer(self.arena.concat(&[bytes])?))
+            }
+        }
+    }
+
+    /// Explicit conversion, which also reads decimal and `0x`-prefixed strings
+    fn to_integer(&mut self, value: Value) -> Result<u64, Error> {
+        let Value::String(bytes) = value else {
+            return self.integer(value);
+        };
+        let bytes = bytes.as_slice();
+        let (digits, radix) = match bytes {
+            [b'0', b'x' | b'X', digits @ ..] => (digits, 16),
+            _ => (bytes, 10),
+        };
+        Ok(digits
+            .iter()
+            .map_while(|&c| (c as char).to_digit(radix))
+            .fold(0u64, |acc, digit| {
+                acc.wrapping_mul(radix as u64).wrapping_add(digit as u64)
+            })
+            & self.ones)
+    }
+
+    /// Integers in full width, buffers as comma-separated bytes
+    fn to_string(&mut self, value: Value, radix: u64) -> Result<Value, Error> {
+        let bytes = match value {
+            Value::String(_) => return Ok(value),
+            Value::Buffer(bytes) => bytes.as_slice(),
+            _ => {
+                let value = self.integer(value)?;
+                let mut digits = [0u8; 20];
+                let len = if radix == 16 {
+                    let len = if self.ones == u64::MAX { 16 } else { 8 };
+                    for (i, digit) in digits[..len].iter_mut().enumerate() {
+                        *digit = hex_digit((value >> (4 * (len - 1 - i))) as u8 & 0xF);
+                    }
+                    len
+                } else {
+                    decimal(value, &mut digits)
+                };
+                return Ok(Value::String(self.arena.concat(&[&digits[..len]])?));
+            }
+        };
+
+        // "0xAB," or "171," per byte
+        let string = self.arena.allocate(bytes.len() * 5)?;
+        let out = string.as_mut_slice();
+        let mut len = 0;
+        for (i, &byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                out[len] = b',';
+                len += 1;
+            }
+            if radix == 16 {
+                out[len..len + 4].copy_from_slice(&[
+                    b'0',
+                    b'x',
+                    hex_digit(byte >> 4),
+                    hex_digit(byte & 0xF),
+                ]);
+                len += 4;
+            } else {
+                let mut digits = [0u8; 20];
+                let count = decimal(byte as u64, &mut digits);
+                out[len..len + count].copy_from_slice(&digits[..count]);
+                len += count;
+            }
+        }
+        Ok(Value::String(Bytes::from_slice(&string.as_slice()[..len])))
+    }
+
+    fn region_space(&mut self, handle: Handle, region: &Region) -> Result<Space, Error> {
+        match region.space {
+            region::SYSTEM_MEMORY => Ok(Space::Memory),
+            region::SYSTEM_IO => Ok(Space::IO),
+            region::PCI_CONFIG => {
+                // _ADR of the device holding the region, _BBN of the closest bridge above
+                let device = self.namespace.get(handle).parent;
+                let address = self.child_integer(device, *b"_ADR").unwrap_or(0);
+                let mut bus = 0;
+                let mut scope = device;
+                while scope != Handle::ROOT {
+                    if let Some(number) = self.child_integer(scope, *b"_BBN") {
+                        bus = number;
+                        break;
+                    }
+                    scope = self.namespace.get(scope).parent;
+                }
+                Ok(Space::PCI {
+                    bus: bus as u8,
+                    device: (address >> 16) as u8,
+                    function: address as u8,
+                })
+            }
+            space => Err(Error::InvalidRegionSpace(space)),
+        }
+    }
+
+    fn child_integer(&mut self, parent: Handle, name: [u8; 4]) -> Option<u64> {
+        let handle = self.namespace.child(parent, name)?;
+        let value = self.invoke(handle, &[]).ok()?;
+        self.integer(value).ok()
+    }
+
+    /// Reads the access unit at `offset` bytes into what the field is declared over
+    fn read_unit(&mut self, kind: &FieldKind, offset: u64, width: usize) -> Result<u64, Error> {
+        match *kind {
+            FieldKind::Region(handle) => {
+                let Object::Region(region) = self.namespace.get(handle).object else {
+                    return Err(Error::InvalidType);
+                };
+                let space = self.region_space(handle, &region)?;
+                region::read(space, region.offset + offset, width)
+            }
+            FieldKind::Index { index, data } => {
+                self.store_node(index, Value::Integer(offset), true)?;
+                let value = self.read(data)?;
+                self.integer(value)
+            }
+            FieldKind::Bank {
+                region,
+                bank,
+                value,
+            } => {
+                self.store_node(bank, Value::Integer(value), true)?;
+                self.read_unit(&FieldKind::Region(region), offset, width)
+            }
+        }
+    }
+
+    fn write_unit(
+        &mut self,
+        kind: &FieldKind,
+        offset: u64,
+        width: usize,
+        value: u64,
+    ) -> Result<(), Error> {
+        match *kind {
+            FieldKind::Region(handle) => {
+                let Object::Region(region) = self.namespace.get(handle).object else {
+                    return Err(Error::InvalidType);
+                };
+                let space = self.region_space(handle, &region)?;
+                region::write(space, region.offset + offset, width, value)
+            }
+            FieldKind::Index { index, data } => {
+                self.store_node(index, Value::Integer(offset), true)?;
+                self.store_node(data, Value::Integer(value), true)
+            }
+            FieldKind::Bank {
+                region,
+                bank,
+                value: bank_value,
+            } => {
+                self.store_node(bank, Value::Integer(bank_value), true)?;
+                self.write_unit(&FieldKind::Region(region), offset, width, value)
+            }
+        }
+    }
+
+    /// Units of AnyAcc and BufferAcc fields are bytes
+    fn access_width(field: &Field) -> usize {
+        match field.flags & 0xF {
+            2 => 2,
+            3 => 4,
+            4 => 8,
+            _ => 1,
+        }
+    }
+
+    /// Fields wider than 64 bits read as buffers
+    fn read_field(&mut self, field: &Field) -> Result<Value, Error> {
+        if field.bit_length <= 64 {
+            return Ok(Value::Integer(self.read_field_bits(
+                field,
+                0,
+                field.bit_length,
+            )?));
+        }
+        let bytes = self.arena.allocate(field.bit_length.div_ceil(8))?;
+        for (i, byte) in bytes.as_mut_slice().iter_mut().enumerate() {
+            let count = (field.bit_length - 8 * i).min(8);
+            *byte = self.read_field_bits(field, 8 * i, count)? as u8;
+        }
+        Ok(Value::Buffer(bytes))
+    }
+
+    fn read_field_bits(&mut self, field: &Field, start: usize, count: usize) -> Result<u64, Error> {
+        let width = Self::access_width(field);
+        let unit_bits = width * 8;
+        let first = field.bit_offset + start;
+        let end = first + count;
+        let mut value = 0;
+        for unit in first / unit_bits..end.div_ceil(unit_bits) {
+            let unit_start = unit * unit_bits;
+            let raw = self.read_unit(&field.kind, (unit * width) as u64, width)?;
+            let low = first.max(unit_start);
+            let high = end.min(unit_start + unit_bits);
+            let bits = (raw >> (low - unit_start)) & bit_mask(high - low);
+            value |= bits << (low - first);
+        }
+        Ok(value)
+    }
+
+    /// Bits of a unit outside the field follow the update rule
+    fn write_field(&mut self, field: &Field, value: u64) -> Result<(), Error> {
+        let width = Self::access_width(field);
+        let unit_bits = width * 8;
+        let first = field.bit_offset;
+        let end = first + field.bit_length.min(64);
+        for unit in first / unit_bits..end.div_ceil(unit_bits) {
+            let unit_start = unit * unit_bits;
+            let low = first.max(unit_start);
+            let high = end.min(unit_start + unit_bits);
+            let mask = bit_mask(high - low) << (low - unit_start);
+            let bits = ((value >> (low - first)) << (low - unit_start)) & mask;
+            let offset = (unit * width) as u64;
+            let rest = if mask == bit_mask(unit_bits) {
+                0
+            } else {
+                match (field.flags >> 5) & 0x3 {
+                    1 => !mask,
+                    2 => 0,
+                    _ => self.read_unit(&field.kind, offset, width)? & !mask,
+                }
+            };
+            self.write_unit(
+                &field.kind,
+                offset,
+                width,
+                (bits | rest) & bit_mask(unit_bits),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Match operators
+/// - 0: MTR, always
+/// - 1: MEQ
+/// - 2: MLE
+/// - 3: MLT
+/// - 4: MGE
+/// - 5: MGT
+fn matches(op: u8, element: u64, operand: u64) -> bool {
+    match op {
+        0 => true,
+        1 => element == operand,
+        2 => element <= operand,
+        3 => element < operand,
+        4 => element >= operand,
+        5 => element > operand,
+        _ => false,
+    }
+}
+
+fn decimal(mut value: u64, digits: &mut [u8; 20]) -> usize {
+    let mut buffer = [0u8; 20];
+    let mut i = buffer.len();
+    loop {
+        i -= 1;
+        buffer[i] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    let len = buffer.len() - i;
+    digits[..len].copy_from_slice(&buffer[i..]);
+    len
+}
+
+fn read_bits(bytes: &[u8], bit_offset: usize, bit_length: usize) -> u64 {
+    (0..bit_length).fold(0, |value, i| {
+        let bit = bit_offset + i;
+        value | (((bytes[bit / 8] >> (bit % 8)) & 1) as u64) << i
+    })
+}
+
+fn write_bits(bytes: &mut [u8], bit_offset: usize, bit_length: usize, value: u64) {
+    for i in 0..bit_length {
+        let bit = bit_offset + i;
+        let mask = 1 << (bit % 8);
+        if (value >> i) & 1 != 0 {
+            bytes[bit / 8] |= mask;
+        } else {
+            bytes[bit / 8] &= !mask;
+        }
+    }
+}
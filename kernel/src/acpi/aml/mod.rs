@@ -0,0 +1,131 @@
+//! ACPI Machine Language
+//!
+//! Interprets the definition blocks of the DSDT and SSDTs into a namespace of devices, methods
+//! and operation regions that drivers evaluate objects such as `_PRT`, `_S5_` or `_CRS` in.
+//!
+//! Evaluation runs under one lock with interrupts disabled, the way firmware expects methods
+//! to run one at a time. Strings and buffers a method builds live in an arena that is reclaimed
+//! when the next evaluation starts, so they must be read before then. Ones stored into named
+//! objects are copied out to storage that lives as long as the namespace.
+
+mod error;
+mod interpreter;
+mod namespace;
+mod parser;
+pub(super) mod region;
+mod value;
+
+pub use error::Error;
+pub use namespace::Handle;
+pub use value::{Bytes, Package, Value};
+
+use crate::sync::IrqSpinlock;
+
+use interpreter::Machine;
+use namespace::Object;
+use parser::Path;
+
+static STATE: IrqSpinlock<Machine> = IrqSpinlock::new(Machine::new());
+
+/// Interfaces `\_OSI` answers true for, the ones firmware tests to enable its full feature set
+const INTERFACES: [&[u8]; 12] = [
+    b"Windows 2000",
+    b"Windows 2001",
+    b"Windows 2001 SP1",
+    b"Windows 2001.1",
+    b"Windows 2001 SP2",
+    b"Windows 2006",
+    b"Windows 2009",
+    b"Windows 2012",
+    b"Windows 2013",
+    b"Windows 2015",
+    b"Module Device",
+    b"Processor Device",
+];
+
+fn osi(args: &[Value]) -> Value {
+    let supported = match args.first() {
+        Some(Value::String(name)) => INTERFACES.contains(&name.as_slice()),
+        _ => false,
+    };
+    Value::Integer(if supported { u64::MAX } else { 0 })
+}
+
+/// Objects the specification predefines before any definition block is loaded
+fn predefine(machine: &mut Machine) -> Result<(), Error> {
+    let namespace = &mut machine.namespace;
+    for name in [*b"_GPE", *b"_PR_", *b"_SB_", *b"_SI_", *b"_TZ_"] {
+        namespace.add(Handle::ROOT, name, Object::Scope)?;
+    }
+    namespace.add(Handle::ROOT, *b"_GL_", Object::Mutex)?;
+    let os = Value::String(Bytes::from_slice(b"Microsoft Windows NT"));
+    namespace.add(Handle::ROOT, *b"_OS_", Object::Name(os))?;
+    namespace.add(Handle::ROOT, *b"_REV", Object::Name(Value::Integer(2)))?;
+    let osi = Object::Native {
+        arg_count: 1,
+        function: osi,
+    };
+    namespace.add(Handle::ROOT, *b"_OSI", osi)?;
+    Ok(())
+}
+
+/// Loads a definition block, the DSDT first with its revision fixing the integer width
+pub fn load(code: &'static [u8], revision: Option<u8>) -> Result<(), Error> {
+    let mut machine = STATE.lock();
+    if let Some(revision) = revision {
+        machine.set_revision(revision);
+        predefine(&mut machine)?;
+    }
+    // A block failing halfway leaves what it declared so far, which later blocks may need
+    machine.load(Bytes::from_slice(code))
+}
+
+/// Count of objects in the namespace
+pub fn len() -> usize {
+    STATE.lock().namespace.len()
+}
+
+/// Looks up an absolute path like `\_SB_.PCI0`
+pub fn find(path: &'static str) -> Option<Handle> {
+    let path = Path::from_str(path).ok()?;
+    STATE.lock().namespace.resolve(Handle::ROOT, &path)
+}
+
+/// Object `name` directly under `parent`, without searching enclosing scopes
+pub fn child(parent: Handle, name: [u8; 4]) -> Option<Handle> {
+    STATE.lock().namespace.child(parent, name)
+}
+
+/// Objects directly under `parent`, looked up one at a time so each can be evaluated in turn
+pub fn children(parent: Handle) -> impl Iterator<Item = Handle> {
+    let mut last = None;
+    core::iter::from_fn(move || {
+        last = Some(STATE.lock().namespace.next_child(parent, last)?);
+        last
+    })
+}
+
+/// Evaluates the object at `handle`, calling it with `args` if it is a method
+pub fn evaluate_handle(handle: Handle, args: &[Value]) -> Result<Value, Error> {
+    STATE.lock().evaluate(handle, args)
+}
+
+/// Evaluates the object at an absolute `path`, calling it with `args` if it is a method
+pub fn evaluate(path: &'static str, args: &[Value]) -> Result<Value, Error> {
+    evaluate_at(Handle::ROOT, path, args)
+}
+
+/// Evaluates `path` relative to `scope`, such as `_PRT` under a host bridge
+pub fn evaluate_at(scope: Handle, path: &'static str, args: &[Value]) -> Result<Value, Error> {
+    let path = Path::from_str(path)?;
+    let mut machine = STATE.lock();
+    let handle = machine
+        .namespace
+        .resolve(scope, &path)
+        .ok_or(Error::NotFound(path.last().unwrap_or([0; 4])))?;
+    machine.evaluate(handle, args)
+}
+
+fn resolve_element(package: &Package, index: usize) -> Result<Value, Error> {
+    STATE.lock().element(package, index)
+}
@@ -0,0 +1,311 @@
+//! Namespace
+//!
+//! Tree of named objects kept as a flat array of nodes pointing at their parent. Objects a
+//! method creates are appended after everything else and dropped when it returns.
+
+use crate::io::text::Output;
+
+use super::{
+    Error,
+    parser::Path,
+    value::{Bytes, Value},
+};
+
+const MAX_NODE_COUNT: usize = 4096;
+
+/// Deepest path `Handle::out` prints
+const MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Handle(u16);
+impl Handle {
+    pub const ROOT: Self = Self(0);
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+impl Output for Handle {
+    /// Absolute path, or the node index while the interpreter is busy
+    fn out(&self) {
+        let Some(state) = super::STATE.try_lock() else {
+            "#".out();
+            self.index().out();
+            return;
+        };
+        let namespace = &state.namespace;
+        let mut segments = [[0u8; 4]; MAX_DEPTH];
+        let mut count = 0;
+        let mut handle = *self;
+        while handle != Handle::ROOT && count < MAX_DEPTH {
+            let node = namespace.get(handle);
+            segments[count] = node.name;
+            count += 1;
+            handle = node.parent;
+        }
+        '\\'.out();
+        for (i, segment) in segments[..count].iter().rev().enumerate() {
+            if i > 0 {
+                '.'.out();
+            }
+            segment.out();
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Region {
+    /// - 0x00: SystemMemory
+    /// - 0x01: SystemIO
+    /// - 0x02: PCI_Config
+    /// - 0x03 ..= 0xFF: Without a handler
+    pub space: u8,
+
+    pub offset: u64,
+
+    pub length: u64,
+}
+
+#[derive(Clone, Copy)]
+pub enum FieldKind {
+    Region(Handle),
+
+    /// Offset written to `index` before `data` is accessed
+    Index {
+        index: Handle,
+        data: Handle,
+    },
+
+    /// `value` written to `bank` before `region` is accessed
+    Bank {
+        region: Handle,
+        bank: Handle,
+        value: u64,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct Field {
+    pub kind: FieldKind,
+
+    pub bit_offset: usize,
+    pub bit_length: usize,
+
+    /// - Bits 0 ..= 3: AccessType
+    ///   - 0: AnyAcc
+    ///   - 1: ByteAcc
+    ///   - 2: WordAcc
+    ///   - 3: DWordAcc
+    ///   - 4: QWordAcc
+    ///   - 5: BufferAcc
+    /// - Bit 4: LockRule
+    /// - Bits 5 ..= 6: UpdateRule
+    ///   - 0: Preserve
+    ///   - 1: WriteAsOnes
+    ///   - 2: WriteAsZeros
+    pub flags: u8,
+}
+
+pub type NativeMethod = fn(args: &[Value]) -> Value;
+
+#[derive(Clone, Copy)]
+pub enum Object {
+    Scope,
+    Device,
+    Processor,
+    PowerResource,
+    ThermalZone,
+    Name(Value),
+    Method {
+        code: Bytes,
+
+        /// - Bits 0 ..= 2: ArgCount
+        /// - Bit 3: SerializeFlag
+        /// - Bits 4 ..= 7: SyncLevel
+        flags: u8,
+    },
+    /// Implemented by the kernel, such as `\_OSI`
+    Native {
+        arg_count: usize,
+        function: NativeMethod,
+    },
+    Region(Region),
+    Field(Field),
+    BufferField {
+        buffer: Bytes,
+        bit_offset: usize,
+        bit_length: usize,
+    },
+    Mutex,
+    Event,
+    Alias(Handle),
+    /// Declared by `External` and not defined yet
+    External,
+}
+impl Object {
+    /// ObjectType code
+    pub fn type_code(&self) -> u64 {
+        match self {
+            Object::Scope | Object::External | Object::Alias(_) => 0,
+            Object::Name(value) => value.type_code(),
+            Object::Field(_) => 5,
+            Object::Device => 6,
+            Object::Event => 7,
+            Object::Method { .. } | Object::Native { .. } => 8,
+            Object::Mutex => 9,
+            Object::Region(_) => 10,
+            Object::PowerResource => 11,
+            Object::Processor => 12,
+            Object::ThermalZone => 13,
+            Object::BufferField { .. } => 14,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Node {
+    pub name: [u8; 4],
+
+    pub parent: Handle,
+
+    pub object: Object,
+}
+impl Node {
+    const fn null() -> Self {
+        Self {
+            name: *b"\\___",
+            parent: Handle::ROOT,
+            object: Object::Scope,
+        }
+    }
+}
+
+pub struct Namespace {
+    nodes: [Node; MAX_NODE_COUNT],
+
+    /// Root included
+    len: usize,
+}
+impl Namespace {
+    pub const fn new() -> Self {
+        Self {
+            nodes: [Node::null(); MAX_NODE_COUNT],
+            len: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Drops every node added after the first `len`
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len.max(1));
+    }
+
+    pub fn get(&self, handle: Handle) -> &Node {
+        &self.nodes[handle.index()]
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> &mut Node {
+        &mut self.nodes[handle.index()]
+    }
+
+    pub fn children(&self, parent: Handle) -> impl Iterator<Item = Handle> + '_ {
+        (1..self.len)
+            .filter(move |&i| self.nodes[i].parent == parent)
+            .map(|i| Handle(i as u16))
+    }
+
+    /// First child of `parent` added after `after`, or the first of all without it
+    pub fn next_child(&self, parent: Handle, after: Option<Handle>) -> Option<Handle> {
+        let start = after.map_or(1, |handle| handle.index() + 1);
+        (start..self.len)
+            .find(|&i| self.nodes[i].parent == parent)
+            .map(|i| Handle(i as u16))
+    }
+
+    pub fn child(&self, parent: Handle, name: [u8; 4]) -> Option<Handle> {
+        self.children(parent)
+            .find(|&handle| self.get(handle).name == name)
+    }
+
+    /// Adds `name` under `parent`, completing an `External` declaration of it if there is one
+    pub fn add(&mut self, parent: Handle, name: [u8; 4], object: Object) -> Result<Handle, Error> {
+        if let Some(handle) = self.child(parent, name) {
+            let node = self.get_mut(handle);
+            if !matches!(node.object, Object::External) {
+                return Err(Error::AlreadyExists(name));
+            }
+            if !matches!(object, Object::External) {
+                node.object = object;
+            }
+            return Ok(handle);
+        }
+        if self.len == MAX_NODE_COUNT {
+            return Err(Error::NamespaceFull);
+        }
+        self.nodes[self.len] = Node {
+            name,
+            parent,
+            object,
+        };
+        self.len += 1;
+        Ok(Handle(self.len as u16 - 1))
+    }
+
+    /// Scope `path` starts from once its `\` and `^` prefixes are applied
+    fn base(&self, scope: Handle, path: &Path) -> Result<Handle, Error> {
+        if path.root {
+            return Ok(Handle::ROOT);
+        }
+        let mut handle = scope;
+        for _ in 0..path.parents {
+            if handle == Handle::ROOT {
+                return Err(Error::InvalidName);
+            }
+            handle = self.get(handle).parent;
+        }
+        Ok(handle)
+    }
+
+    /// Looks `path` up from `scope`, searching every enclosing scope for a lone segment
+    pub fn resolve(&self, scope: Handle, path: &Path) -> Option<Handle> {
+        let handle = if path.is_simple() {
+            let name = path.segment(0);
+            let mut scope = scope;
+            loop {
+                if let Some(handle) = self.child(scope, name) {
+                    break handle;
+                }
+                if scope == Handle::ROOT {
+                    return None;
+                }
+                scope = self.get(scope).parent;
+            }
+        } else {
+            let mut handle = self.base(scope, path).ok()?;
+            for i in 0..path.count() {
+                handle = self.child(handle, path.segment(i))?;
+            }
+            handle
+        };
+        match self.get(handle).object {
+            Object::Alias(target) => Some(target),
+            _ => Some(handle),
+        }
+    }
+
+    /// Parent an object declared as `path` goes under, along with its own name
+    pub fn parent_of(&self, scope: Handle, path: &Path) -> Result<(Handle, [u8; 4]), Error> {
+        let name = path.last().ok_or(Error::InvalidName)?;
+        let mut handle = self.base(scope, path)?;
+        for i in 0..path.count() - 1 {
+            let segment = path.segment(i);
+            handle = self
+                .child(handle, segment)
+                .ok_or(Error::NotFound(segment))?;
+        }
+        Ok((handle, name))
+    }
+}
@@ -0,0 +1,230 @@
+//! Parser
+//!
+//! Cursor over AML bytecode decoding the encodings every term shares: package lengths, name
+//! strings and constants.
+
+use super::{Error, value::Bytes};
+
+pub const EXT_OP_PREFIX: u8 = 0x5B;
+
+const ROOT_CHAR: u8 = b'\\';
+const PARENT_PREFIX_CHAR: u8 = b'^';
+const DUAL_NAME_PREFIX: u8 = 0x2E;
+const MULTI_NAME_PREFIX: u8 = 0x2F;
+const NULL_NAME: u8 = 0x00;
+
+pub struct Parser {
+    code: &'static [u8],
+
+    pub pc: usize,
+}
+impl Parser {
+    pub fn new(code: Bytes) -> Self {
+        Self {
+            code: code.as_slice(),
+            pc: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn done(&self) -> bool {
+        self.pc >= self.code.len()
+    }
+
+    pub fn peek(&self) -> Result<u8, Error> {
+        self.code.get(self.pc).copied().ok_or(Error::UnexpectedEnd)
+    }
+
+    /// Byte after the next one, to tell extended opcodes apart
+    pub fn peek_second(&self) -> Result<u8, Error> {
+        self.code
+            .get(self.pc + 1)
+            .copied()
+            .ok_or(Error::UnexpectedEnd)
+    }
+
+    pub fn byte(&mut self) -> Result<u8, Error> {
+        let byte = self.peek()?;
+        self.pc += 1;
+        Ok(byte)
+    }
+
+    pub fn word(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+
+    pub fn dword(&mut self) -> Result<u32, Error> {
+        Ok(self.word()? as u32 | (self.word()? as u32) << 16)
+    }
+
+    pub fn qword(&mut self) -> Result<u64, Error> {
+        Ok(self.dword()? as u64 | (self.dword()? as u64) << 32)
+    }
+
+    /// Next `len` bytes, left in the definition block
+    pub fn bytes(&mut self, len: usize) -> Result<Bytes, Error> {
+        let bytes = self
+            .code
+            .get(self.pc..self.pc + len)
+            .ok_or(Error::UnexpectedEnd)?;
+        self.pc += len;
+        Ok(Bytes::from_slice(bytes))
+    }
+
+    /// Bytes from here to `end`
+    pub fn rest(&mut self, end: usize) -> Result<Bytes, Error> {
+        let len = end.checked_sub(self.pc).ok_or(Error::UnexpectedEnd)?;
+        self.bytes(len)
+    }
+
+    /// Decodes a PkgLength, which counts its own bytes when it sizes a package
+    ///
+    /// - Bits 0 ..= 3: Length, bits 0 ..= 5 without following bytes
+    /// - Bits 6 ..= 7: Count of following bytes, each adding 8 bits above the nibble
+    pub fn pkg_length_value(&mut self) -> Result<usize, Error> {
+        let lead = self.byte()?;
+        let count = lead >> 6;
+        if count == 0 {
+            return Ok((lead & 0x3F) as usize);
+        }
+        let mut len = (lead & 0x0F) as usize;
+        for i in 0..count {
+            len |= (self.byte()? as usize) << (4 + 8 * i);
+        }
+        Ok(len)
+    }
+
+    /// Decodes the PkgLength of a package and returns where it ends
+    pub fn pkg_length(&mut self) -> Result<usize, Error> {
+        let end = self.pc + self.pkg_length_value()?;
+        if end > self.code.len() {
+            return Err(Error::UnexpectedEnd);
+        }
+        Ok(end)
+    }
+
+    pub fn name_seg(&mut self) -> Result<[u8; 4], Error> {
+        let seg = [self.byte()?, self.byte()?, self.byte()?, self.byte()?];
+        let valid = |c: u8| c == b'_' || c.is_ascii_uppercase() || c.is_ascii_digit();
+        if !seg.iter().copied().all(valid) || seg[0].is_ascii_digit() {
+            return Err(Error::InvalidName);
+        }
+        Ok(seg)
+    }
+
+    pub fn is_name_start(byte: u8) -> bool {
+        matches!(
+            byte,
+            ROOT_CHAR | PARENT_PREFIX_CHAR | DUAL_NAME_PREFIX | MULTI_NAME_PREFIX | b'_' | b'A'
+                ..=b'Z'
+        )
+    }
+
+    pub fn name_string(&mut self) -> Result<Path, Error> {
+        let mut path = Path {
+            root: false,
+            parents: 0,
+            segments: Bytes::empty(),
+        };
+        if self.peek()? == ROOT_CHAR {
+            self.pc += 1;
+            path.root = true;
+        } else {
+            while self.peek()? == PARENT_PREFIX_CHAR {
+                self.pc += 1;
+                path.parents += 1;
+            }
+        }
+
+        let count = match self.peek()? {
+            NULL_NAME => {
+                self.pc += 1;
+                0
+            }
+            DUAL_NAME_PREFIX => {
+                self.pc += 1;
+                2
+            }
+            MULTI_NAME_PREFIX => {
+                self.pc += 1;
+                self.byte()? as usize
+            }
+            _ => 1,
+        };
+        let start = self.pc;
+        for _ in 0..count {
+            self.name_seg()?;
+        }
+        path.segments = Bytes::from_slice(&self.code[start..self.pc]);
+        Ok(path)
+    }
+}
+
+/// Decoded NameString, its segments left in the definition block
+#[derive(Clone, Copy)]
+pub struct Path {
+    /// Starts at `\`
+    pub root: bool,
+
+    /// Count of leading `^`
+    pub parents: usize,
+
+    segments: Bytes,
+}
+impl Path {
+    /// Parses an ASCII path of 4-character segments like `\_SB_.PCI0._PRT`
+    pub fn from_str(path: &'static str) -> Result<Self, Error> {
+        let (root, rest) = match path.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, path),
+        };
+        let parents = rest.bytes().take_while(|&c| c == b'^').count();
+        let rest = &rest[parents..];
+        // Dots are kept, `stride` steps over them
+        if rest.split('.').any(|seg| seg.len() != 4) && !rest.is_empty() {
+            return Err(Error::InvalidName);
+        }
+        Ok(Self {
+            root,
+            parents,
+            segments: Bytes::from_slice(rest.as_bytes()),
+        })
+    }
+
+    /// Dots included when built by `from_str`
+    fn stride(&self) -> usize {
+        let len = self.segments.len();
+        if len > 4 && self.segments.as_slice()[4] == b'.' {
+            5
+        } else {
+            4
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        let len = self.segments.len();
+        if len == 0 {
+            0
+        } else {
+            len.div_ceil(self.stride())
+        }
+    }
+
+    pub fn segment(&self, index: usize) -> [u8; 4] {
+        let start = index * self.stride();
+        let seg = &self.segments.as_slice()[start..start + 4];
+        [seg[0], seg[1], seg[2], seg[3]]
+    }
+
+    pub fn last(&self) -> Option<[u8; 4]> {
+        self.count().checked_sub(1).map(|i| self.segment(i))
+    }
+
+    /// Single segment without prefixes, which is searched for up the tree
+    pub fn is_simple(&self) -> bool {
+        !self.root && self.parents == 0 && self.count() == 1
+    }
+}
@@ -0,0 +1,125 @@
+//! Operation Region
+//!
+//! Accesses of 1, 2, 4 or 8 bytes to the address spaces fields may live in. PCI configuration
+//! goes through the legacy I/O ports, which reach the first 256 bytes of every function.
+
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::{io::port, mem::paging};
+
+use super::Error;
+
+pub const SYSTEM_MEMORY: u8 = 0x00;
+pub const SYSTEM_IO: u8 = 0x01;
+pub const PCI_CONFIG: u8 = 0x02;
+
+const PCI_CONFIG_SIZE: u64 = 0x100;
+
+#[derive(Clone, Copy)]
+pub enum Space {
+    Memory,
+    IO,
+    PCI { bus: u8, device: u8, function: u8 },
+}
+
+fn pci_select(bus: u8, device: u8, function: u8, offset: u64) {
+    port::out_dword(
+        port::PCI_CONFIG_ADDRESS,
+        1 << 31
+            | (bus as u32) << 16
+            | (device as u32 & 0x1F) << 11
+            | (function as u32 & 0x7) << 8
+            | (offset as u32 & 0xFC),
+    );
+}
+
+fn check(space: Space, address: u64, width: usize) -> Result<(), Error> {
+    let last = address + width as u64 - 1;
+    let valid = match space {
+        Space::Memory => paging::mapped(address as usize) && paging::mapped(last as usize),
+        Space::IO => last <= u16::MAX as u64,
+        Space::PCI { .. } => last < PCI_CONFIG_SIZE,
+    };
+    if !valid || !matches!(width, 1 | 2 | 4 | 8) {
+        return Err(Error::InvalidAddress(address));
+    }
+    Ok(())
+}
+
+pub fn read(space: Space, address: u64, width: usize) -> Result<u64, Error> {
+    check(space, address, width)?;
+    if width == 8 && !matches!(space, Space::Memory) {
+        return Ok(read(space, address, 4)? | read(space, address + 4, 4)? << 32);
+    }
+    Ok(match space {
+        Space::Memory => unsafe {
+            match width {
+                1 => read_volatile(address as *const u8) as u64,
+                2 => read_volatile(address as *const u16) as u64,
+                4 => read_volatile(address as *const u32) as u64,
+                _ => read_volatile(address as *const u64),
+            }
+        },
+        Space::IO => {
+            let port = address as u16;
+            match width {
+                1 => port::in_byte(port) as u64,
+                2 => port::in_word(port) as u64,
+                _ => port::in_dword(port) as u64,
+            }
+        }
+        Space::PCI {
+            bus,
+            device,
+            function,
+        } => {
+            pci_select(bus, device, function, address);
+            let port = port::PCI_CONFIG_DATA + (address & 3) as u16;
+            match width {
+                1 => port::in_byte(port) as u64,
+                2 => port::in_word(port) as u64,
+                _ => port::in_dword(port) as u64,
+            }
+        }
+    })
+}
+
+pub fn write(space: Space, address: u64, width: usize, value: u64) -> Result<(), Error> {
+    check(space, address, width)?;
+    if width == 8 && !matches!(space, Space::Memory) {
+        write(space, address, 4, value)?;
+        return write(space, address + 4, 4, value >> 32);
+    }
+    match space {
+        Space::Memory => unsafe {
+            match width {
+                1 => write_volatile(address as *mut u8, value as u8),
+                2 => write_volatile(address as *mut u16, value as u16),
+                4 => write_volatile(address as *mut u32, value as u32),
+                _ => write_volatile(address as *mut u64, value),
+            }
+        },
+        Space::IO => {
+            let port = address as u16;
+            match width {
+                1 => port::out_byte(port, value as u8),
+                2 => port::out_word(port, value as u16),
+                _ => port::out_dword(port, value as u32),
+            }
+        }
+        Space::PCI {
+            bus,
+            device,
+            function,
+        } => {
+            pci_select(bus, device, function, address);
+            let port = port::PCI_CONFIG_DATA + (address & 3) as u16;
+            match width {
+                1 => port::out_byte(port, value as u8),
+                2 => port::out_word(port, value as u16),
+                _ => port::out_dword(port, value as u32),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Value
+
+use core::slice::{from_raw_parts, from_raw_parts_mut};
+
+use crate::io::text::Output;
+
+use super::{
+    Error, Handle,
+    parser::{Parser, Path},
+};
+
+pub const ZERO_OP: u8 = 0x00;
+pub const ONE_OP: u8 = 0x01;
+pub const BYTE_PREFIX: u8 = 0x0A;
+pub const WORD_PREFIX: u8 = 0x0B;
+pub const DWORD_PREFIX: u8 = 0x0C;
+pub const STRING_PREFIX: u8 = 0x0D;
+pub const QWORD_PREFIX: u8 = 0x0E;
+pub const BUFFER_OP: u8 = 0x11;
+pub const PACKAGE_OP: u8 = 0x12;
+pub const VAR_PACKAGE_OP: u8 = 0x13;
+pub const ONES_OP: u8 = 0xFF;
+
+/// Bytes in a definition block, the arena or the storage of named objects
+///
+/// Buffer objects are copied out of the definition block they are declared in. Only buffers
+/// among package elements, decoded on access, still point into the table.
+#[derive(Clone, Copy)]
+pub struct Bytes {
+    addr: usize,
+    len: usize,
+}
+impl Bytes {
+    pub const fn empty() -> Self {
+        Self { addr: 0, len: 0 }
+    }
+
+    pub fn from_slice(slice: &'static [u8]) -> Self {
+        Self {
+            addr: slice.as_ptr() as usize,
+            len: slice.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn as_slice(&self) -> &'static [u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { from_raw_parts(self.addr as *const u8, self.len) }
+    }
+
+    pub fn as_mut_slice(&self) -> &'static mut [u8] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { from_raw_parts_mut(self.addr as *mut u8, self.len) }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Package {
+    /// Elements left encoded and decoded on access
+    code: Bytes,
+
+    count: usize,
+
+    /// Scope the names among the elements resolve from
+    pub(super) scope: Handle,
+}
+impl Package {
+    pub(super) fn new(code: Bytes, count: usize, scope: Handle) -> Self {
+        Self { code, count, scope }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Element `index`, with a name left unresolved
+    ///
+    /// Elements past the encoded ones are uninitialized.
+    pub(super) fn element(&self, index: usize) -> Result<Element, Error> {
+        if index >= self.count {
+            return Err(Error::InvalidIndex(index as u64));
+        }
+        let mut parser = Parser::new(self.code);
+        for _ in 0..index {
+            if parser.done() {
+                return Ok(Element::Value(Value::Uninitialized));
+            }
+            skip_element(&mut parser, self.scope)?;
+        }
+        if parser.done() {
+            return Ok(Element::Value(Value::Uninitialized));
+        }
+        decode_element(&mut parser, self.scope)
+    }
+
+    /// Element `index`, with names resolved to references
+    pub fn get(&self, index: usize) -> Result<Value, Error> {
+        super::resolve_element(self, index)
+    }
+}
+
+pub(super) enum Element {
+    Value(Value),
+    Name(Path),
+}
+
+fn skip_element(parser: &mut Parser, scope: Handle) -> Result<(), Error> {
+    decode_element(parser, scope).map(|_| ())
+}
+
+/// PackageElement, a constant data object or a name
+fn decode_element(parser: &mut Parser, scope: Handle) -> Result<Element, Error> {
+    if Parser::is_name_start(parser.peek()?) {
+        return Ok(Element::Name(parser.name_string()?));
+    }
+    constant(parser, scope).map(Element::Value)
+}
+
+/// DataObject with constant operands, as found in packages
+pub(super) fn constant(parser: &mut Parser, scope: Handle) -> Result<Value, Error> {
+    let opcode = parser.byte()?;
+    Ok(match opcode {
+        ZERO_OP => Value::Integer(0),
+        ONE_OP => Value::Integer(1),
+        ONES_OP => Value::Integer(u64::MAX),
+        BYTE_PREFIX => Value::Integer(parser.byte()? as u64),
+        WORD_PREFIX => Value::Integer(parser.word()? as u64),
+        DWORD_PREFIX => Value::Integer(parser.dword()? as u64),
+        QWORD_PREFIX => Value::Integer(parser.qword()?),
+        STRING_PREFIX => Value::String(string(parser)?),
+        BUFFER_OP => {
+            let end = parser.pkg_length()?;
+            let size = constant(parser, scope)?.as_integer()? as usize;
+            let bytes = parser.rest(end)?;
+            if size > bytes.len() {
+                // Zero filling would need the arena, which constants never touch
+                return Err(Error::InvalidType);
+            }
+            Value::Buffer(Bytes {
+                addr: bytes.addr,
+                len: size,
+            })
+        }
+        PACKAGE_OP | VAR_PACKAGE_OP => {
+            let end = parser.pkg_length()?;
+            let count = if opcode == PACKAGE_OP {
+                parser.byte()? as usize
+            } else {
+                constant(parser, scope)?.as_integer()? as usize
+            };
+            Value::Package(Package::new(parser.rest(end)?, count, scope))
+        }
+        _ => return Err(Error::InvalidOpcode(opcode as u16)),
+    })
+}
+
+/// AsciiCharList up to its NullChar, which is consumed but left out
+pub(super) fn string(parser: &mut Parser) -> Result<Bytes, Error> {
+    let start = parser.pc;
+    while parser.byte()? != 0 {}
+    let end = parser.pc - 1;
+    parser.pc = start;
+    let bytes = parser.bytes(end - start)?;
+    parser.pc += 1;
+    Ok(bytes)
+}
+
+#[derive(Clone, Copy)]
+pub enum Value {
+    Uninitialized,
+
+    Integer(u64),
+
+    /// Without the terminating NUL
+    String(Bytes),
+
+    Buffer(Bytes),
+
+    Package(Package),
+
+    /// Named object, from `RefOf` or a name inside a package
+    Reference(Handle),
+
+    /// Byte `Index` returned into a buffer or string
+    BufferElement(Bytes, usize),
+
+    /// Element `Index` returned into a package
+    PackageElement(Package, usize),
+}
+impl Value {
+    pub fn as_integer(&self) -> Result<u64, Error> {
+        match self {
+            Value::Integer(value) => Ok(*value),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<&'static [u8], Error> {
+        match self {
+            Value::String(bytes) | Value::Buffer(bytes) => Ok(bytes.as_slice()),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn as_package(&self) -> Result<Package, Error> {
+        match self {
+            Value::Package(package) => Ok(*package),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    /// ObjectType code
+    pub fn type_code(&self) -> u64 {
+        match self {
+            Value::Uninitialized => 0,
+            Value::Integer(_) => 1,
+            Value::String(_) => 2,
+            Value::Buffer(_) => 3,
+            Value::Package(_) => 4,
+            Value::Reference(_) | Value::BufferElement(..) | Value::PackageElement(..) => 14,
+        }
+    }
+}
+impl Output for Value {
+    fn out(&self) {
+        match self {
+            Value::Uninitialized => "Uninitialized".out(),
+            Value::Integer(value) => value.out(),
+            Value::String(bytes) => bytes.as_slice().out(),
+            Value::Buffer(bytes) => {
+                "Buffer(".out();
+                bytes.len().out();
+                ")".out();
+            }
+            Value::Package(package) => {
+                "Package(".out();
+                package.len().out();
+                ")".out();
+            }
+            Value::Reference(handle) => {
+                "RefOf(".out();
+                handle.out();
+                ")".out();
+            }
+            Value::BufferElement(_, index) | Value::PackageElement(_, index) => {
+                "Index(".out();
+                index.out();
+                ")".out();
+            }
+        }
+    }
+}
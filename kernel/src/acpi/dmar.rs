@@ -0,0 +1,162 @@
+//! DMA Remapping Reporting Table
+
+use core::ptr::{addr_of, read_unaligned};
+
+use crate::{
+    drivers::{iommu, pcie},
+    mem::Memory,
+    warn,
+};
+
+use super::{Error, Header, table};
+
+pub const SIGNATURE: &[u8; 4] = b"DMAR";
+
+/// DMA Remapping Hardware Unit Definition
+const DRHD: u16 = 0;
+
+/// Reserved Memory Region Reporting
+const RMRR: u16 = 1;
+
+/// Device scope types
+const PCI_ENDPOINT: u8 = 1;
+const PCI_SUB_HIERARCHY: u8 = 2;
+
+#[repr(C, packed)]
+struct DMAR {
+    header: Header,
+
+    /// Maximum DMA physical addressability minus one
+    host_address_width: u8,
+
+    /// - Bit 0: INTR_REMAP
+    /// - Bit 1: X2APIC_OPT_OUT
+    /// - Bit 2: DMA_CTRL_PLATFORM_OPT_IN_FLAG
+    flags: u8,
+
+    reserved: [u8; 10],
+
+    remapping_structures: [u8; 0],
+}
+impl Memory for DMAR {}
+impl DMAR {
+    fn init(&self) -> Result<(), crate::Error> {
+        self.header.init(*SIGNATURE)?;
+        let structures = addr_of!(self.remapping_structures) as usize;
+        let mut offset = 0;
+        while offset + 4 <= self.header.length as usize - size_of::<Self>() {
+            let structure = structures + offset;
+            // Type, Length
+            let type_ = unsafe { read_unaligned(structure as *const u16) };
+            let len = unsafe { read_unaligned((structure + 2) as *const u16) } as usize;
+            if len < 4 {
+                return Err(Error::InvalidLength(*SIGNATURE).into());
+            }
+            match type_ {
+                DRHD => drhd(structure, len)?,
+                RMRR => rmrr(structure, len)?,
+                _ => {}
+            }
+            offset += len;
+        }
+        Ok(())
+    }
+}
+
+/// - Byte 4: Flags
+///   - Bit 0: INCLUDE_PCI_ALL
+/// - Byte 5: Size
+/// - Bytes 6 ..= 7: Segment Number
+/// - Bytes 8 ..= 15: Register Base Address
+/// - Bytes 16 ..: Device Scopes
+fn drhd(structure: usize, len: usize) -> Result<(), crate::Error> {
+    let flags = unsafe { read_unaligned((structure + 4) as *const u8) };
+    let segment = unsafe { read_unaligned((structure + 6) as *const u16) };
+    let addr = unsafe { read_unaligned((structure + 8) as *const u64) };
+    if segment != 0 {
+        warn!(
+            "DMAR",
+            "Unit ", addr, " on segment ", segment as usize, " skipped"
+        );
+        return Ok(());
+    }
+    let unit = iommu::append(addr as usize, flags & 1 != 0)?;
+    scopes(
+        structure + 16,
+        structure + len,
+        |type_, bus, device, function| {
+            match type_ {
+                PCI_ENDPOINT => iommu::add_scope(unit, bus, device, function, false)?,
+                PCI_SUB_HIERARCHY => iommu::add_scope(unit, bus, device, function, true)?,
+                // IOAPIC and HPET, which matter only to interrupt remapping
+                _ => {}
+            }
+            Ok(())
+        },
+    )
+}
+
+/// - Bytes 4 ..= 5: Reserved
+/// - Bytes 6 ..= 7: Segment Number
+/// - Bytes 8 ..= 15: Base Address
+/// - Bytes 16 ..= 23: Limit Address, inclusive
+/// - Bytes 24 ..: Device Scopes
+fn rmrr(structure: usize, len: usize) -> Result<(), crate::Error> {
+    let segment = unsafe { read_unaligned((structure + 6) as *const u16) };
+    let base = unsafe { read_unaligned((structure + 8) as *const u64) };
+    let limit = unsafe { read_unaligned((structure + 16) as *const u64) };
+    if segment != 0 {
+        return Ok(());
+    }
+    scopes(
+        structure + 24,
+        structure + len,
+        |_, bus, device, function| {
+            iommu::add_reserved(base, limit, bus, device, function)?;
+            Ok(())
+        },
+    )
+}
+
+/// Calls `f` with the type and the function each device scope between `start` and `end` names
+///
+/// - Byte 0: Type
+/// - Byte 1: Length
+/// - Bytes 2 ..= 3: Reserved
+/// - Byte 4: Enumeration ID
+/// - Byte 5: Start Bus Number
+/// - Bytes 6 ..: Path of device and function pairs, one per bridge on the way down
+fn scopes(
+    start: usize,
+    end: usize,
+    mut f: impl FnMut(u8, u8, u8, u8) -> Result<(), crate::Error>,
+) -> Result<(), crate::Error> {
+    let mut scope = start;
+    while scope + 6 <= end {
+        let bytes = unsafe { core::slice::from_raw_parts(scope as *const u8, end - scope) };
+        let len = bytes[1] as usize;
+        if len < 8 || len > bytes.len() {
+            return Err(Error::InvalidLength(*SIGNATURE).into());
+        }
+        let mut bus = bytes[5];
+        let path = bytes[6..len].chunks_exact(2);
+        let hops = path.len();
+        for (i, hop) in path.enumerate() {
+            if i + 1 == hops {
+                f(bytes[0], bus, hop[0], hop[1])?;
+                break;
+            }
+            let Some(addr) = super::mcfg::address(bus, hop[0], hop[1]) else {
+                break;
+            };
+            bus = pcie::Type1::get_ref(addr).secondary_bus();
+        }
+        scope += len;
+    }
+    Ok(())
+}
+
+pub fn init() -> Result<(), crate::Error> {
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    DMAR::get_ref(table.addr).init()
+}
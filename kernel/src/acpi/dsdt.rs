@@ -2,29 +2,24 @@
 
 use crate::mem::Memory;
 
-use super::{Error, Header};
+use super::{Error, Header, aml, table};
 
 pub const SIGNATURE: &[u8; 4] = b"DSDT";
 
-static mut ADDR: usize = 0;
-
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
-
 #[repr(C, packed)]
 struct DSDT {
     header: Header,
-
-    definition_block: [u8; 0],
 }
 impl Memory for DSDT {}
 impl DSDT {
     fn init(&self) -> Result<(), Error> {
-        self.header.init(*SIGNATURE)
+        self.header.init(*SIGNATURE)?;
+        aml::load(self.header.body(), Some(self.header.revision))?;
+        Ok(())
     }
 }
 
 pub fn init() -> Result<(), Error> {
-    unsafe { DSDT::get_ref(ADDR).init() }
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    DSDT::get_ref(table.addr).init()
 }
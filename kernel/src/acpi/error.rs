@@ -1,11 +1,13 @@
 //! Error
 
 pub enum Error {
+    AML(super::aml::Error),
     InvalidAddress([u8; 4]),
     InvalidChecksum([u8; 4]),
     InvalidLength([u8; 4]),
     InvalidRevision([u8; 4]),
     InvalidSignature([u8; 4]),
+    Unsupported(&'static str),
 }
 impl From<Error> for crate::Error {
     fn from(err: Error) -> Self {
@@ -14,8 +16,13 @@ impl From<Error> for crate::Error {
 }
 impl crate::Output for Error {
     fn out(&self) {
-        "ACPI ".out();
+        "ACPI".out();
+        if let Error::AML(err) = self {
+            return err.out();
+        }
+        " ".out();
         match self {
+            Error::AML(_) => unreachable!(),
             Error::InvalidAddress(signature) => {
                 signature.out();
                 " Address"
@@ -36,6 +43,10 @@ impl crate::Output for Error {
                 signature.out();
                 " Signature"
             }
+            Error::Unsupported(feature) => {
+                feature.out();
+                " Unsupported"
+            }
         }
         .out();
     }
@@ -1,28 +1,180 @@
 //! Firmware ACPI Control Structure
+//!
+//! Read-write memory the FADT points at, shared with firmware: the waking vector it jumps to on
+//! resume and the global lock that keeps OSPM and SMM code off the same hardware.
 
-use crate::mem::Memory;
+use core::{
+    ptr::addr_of,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
-use super::{Error, Header};
+use crate::{debug, io::port, mem::Memory, warn};
+
+use super::{Error, fadt, table};
 
 pub const SIGNATURE: &[u8; 4] = b"FACS";
 
-static mut ADDR: usize = 0;
+/// Through X_Firmware_Waking_Vector, which version 1 added
+const MIN_LENGTH: usize = 64;
 
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
+/// Physical address is aligned to 64 bytes
+const ALIGNMENT: usize = 64;
+
+/// Global Lock
+/// - Bit 0: Pending
+/// - Bit 1: Owned
+const PENDING: u32 = 1 << 0;
+const OWNED: u32 = 1 << 1;
+
+/// PM1 Control
+/// - Bit 2: GBL_RLS, tells firmware a lock it waited on is free
+const GBL_RLS: u64 = 1 << 2;
+
+/// Longest wait for the lock, standing in for the 0xFFFF forever of `Acquire`
+const MAX_TIMEOUT_MS: u64 = 5000;
 
 #[repr(C, packed)]
 struct FACS {
-    header: Header,
+    signature: [u8; 4],
+
+    /// Bytes, 64 or more
+    length: u32,
+
+    /// Changes when the hardware configuration does, which makes resuming from S4 unsafe
+    hardware_signature: u32,
+
+    /// Real-mode address firmware jumps to on waking, zero if none
+    firmware_waking_vector: u32,
+
+    global_lock: u32,
+
+    /// - Bit 0: S4BIOS_F
+    /// - Bit 1: 64BIT_WAKE_SUPPORTED_F
+    flags: u32,
+
+    /// Preferred over `firmware_waking_vector` when nonzero
+    x_firmware_waking_vector: u64,
+
+    /// - 0: ACPI 1.0
+    /// - 1: ACPI 2.0 ..= 3.0
+    /// - 2: ACPI 4.0 and later
+    version: u8,
+
+    reserved0: [u8; 3],
+
+    /// - Bit 0: 64BIT_WAKE_F, set to be woken in long mode
+    ospm_flags: u32,
+
+    reserved1: [u8; 24],
 }
 impl Memory for FACS {}
 impl FACS {
+    fn validate(&self) -> Result<(), Error> {
+        if self.signature != *SIGNATURE {
+            return Err(Error::InvalidSignature(*SIGNATURE));
+        }
+        if (self.length as usize) < MIN_LENGTH {
+            return Err(Error::InvalidLength(*SIGNATURE));
+        }
+        if !self.addr().is_multiple_of(ALIGNMENT) {
+            return Err(Error::InvalidAddress(*SIGNATURE));
+        }
+        Ok(())
+    }
+
     fn init(&self) -> Result<(), Error> {
-        self.header.init(*SIGNATURE)
+        self.validate()?;
+        debug!(
+            "FACS",
+            "Version ",
+            self.version as usize,
+            " Hardware Signature ",
+            self.hardware_signature as u64
+        );
+        Ok(())
+    }
+
+    fn lock(&self) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(addr_of!(self.global_lock) as *mut u32) }
     }
 }
 
+fn get() -> Option<&'static FACS> {
+    table::find(SIGNATURE, 0)
+        .map(|table| FACS::get_ref(table.addr))
+        .filter(|facs| facs.validate().is_ok())
+}
+
+pub fn is_present() -> bool {
+    get().is_some()
+}
+
+/// Validates the FACS the FADT registered through X_FIRMWARE_CTRL or FIRMWARE_CTRL
 pub fn init() -> Result<(), Error> {
-    unsafe { FACS::get_ref(ADDR).init() }
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    FACS::get_ref(table.addr).init()
+}
+
+/// Where firmware resumes execution from S2 and S3, in long mode if asked and supported
+pub fn set_waking_vector(addr: u64, long_mode: bool) -> Result<(), Error> {
+    let facs = FACS::get_mut(get().ok_or(Error::InvalidAddress(*SIGNATURE))?.addr());
+    if facs.version >= 1 {
+        let long_mode = long_mode && facs.flags & 1 << 1 != 0;
+        facs.ospm_flags = (facs.ospm_flags & !1) | long_mode as u32;
+        facs.x_firmware_waking_vector = addr;
+        facs.firmware_waking_vector = 0;
+    } else {
+        facs.firmware_waking_vector =
+            u32::try_from(addr).map_err(|_| Error::Unsupported("Waking Vector above 4 GiB"))?;
+    }
+    Ok(())
+}
+
+/// Marks the lock owned, or pending if firmware holds it, and returns whether it was taken
+fn try_acquire(lock: &AtomicU32) -> bool {
+    let mut old = lock.load(Ordering::Acquire);
+    loop {
+        let new = ((old & !PENDING) | OWNED) + ((old & OWNED) >> 1);
+        match lock.compare_exchange(old, new, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => return new & PENDING == 0,
+            Err(current) => old = current,
+        }
+    }
+}
+
+/// Takes the global lock within `timeout_ms` and returns whether it did
+///
+/// Without a FACS there is no firmware to share with, so it always succeeds.
+pub fn acquire_global_lock(timeout_ms: u64) -> bool {
+    let Some(facs) = get() else {
+        return true;
+    };
+    let lock = facs.lock();
+    // Polled in microseconds, since AML runs with interrupts off and the SCI cannot wake us
+    for _ in 0..=timeout_ms.min(MAX_TIMEOUT_MS) * 1000 {
+        if try_acquire(lock) {
+            return true;
+        }
+        port::out_byte(port::POST, 0);
+    }
+    false
+}
+
+/// Gives the global lock back, signaling firmware if it is waiting
+pub fn release_global_lock() {
+    let Some(facs) = get() else {
+        return;
+    };
+    let old = facs.lock().fetch_and(!(PENDING | OWNED), Ordering::AcqRel);
+    if old & PENDING == 0 {
+        return;
+    }
+    let Some((pm1a, pm1b)) = fadt::pm1_control() else {
+        return;
+    };
+    for pm1 in [Some(pm1a), pm1b].into_iter().flatten() {
+        if let Err(err) = pm1.read().and_then(|value| pm1.write(value | GBL_RLS)) {
+            warn!("FACS", err);
+        }
+    }
 }
@@ -1,17 +1,20 @@
 //! Fixed ACPI Description Table
 
+use core::ptr::addr_of;
+
 use crate::mem::Memory;
 
-use super::{Error, Header, dsdt, facs};
+use super::{
+    Error, Header,
+    aml::{
+        self,
+        region::{self, Space},
+    },
+    table,
+};
 
 pub const SIGNATURE: &[u8; 4] = b"FACP";
 
-static mut ADDR: usize = 0;
-
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
-
 #[repr(C, packed)]
 struct GenericAddressStructure {
     /// - 0x00 System Memory space
@@ -48,6 +51,70 @@ struct GenericAddressStructure {
     address: u64,
 }
 
+impl GenericAddressStructure {
+    /// Accessed as `width` bytes unless the structure gives an access size or bit width
+    fn register(&self, width: usize) -> Option<Register> {
+        let address = self.address;
+        let space = match self.address_space_id {
+            0x00 => Space::Memory,
+            0x01 => Space::IO,
+            // Bus 0 with the device in bits 32 ..= 47, the function in 16 ..= 31
+            0x02 => Space::PCI {
+                bus: 0,
+                device: (address >> 32) as u8,
+                function: (address >> 16) as u8,
+            },
+            _ => return None,
+        };
+        let width = match self.access_size {
+            1..=4 => 1 << (self.access_size - 1),
+            _ if matches!(self.register_bit_width, 8 | 16 | 32 | 64) => {
+                self.register_bit_width as usize / 8
+            }
+            _ => width,
+        };
+        let address = match space {
+            Space::PCI { .. } => address & 0xFFFF,
+            _ => address,
+        };
+        (address != 0).then_some(Register {
+            space,
+            address,
+            width,
+        })
+    }
+}
+
+/// Fixed hardware register, in whichever address space the firmware put it
+#[derive(Clone, Copy)]
+pub struct Register {
+    space: Space,
+    address: u64,
+    width: usize,
+}
+impl Register {
+    pub fn read(&self) -> Result<u64, aml::Error> {
+        region::read(self.space, self.address, self.width)
+    }
+
+    pub fn write(&self, value: u64) -> Result<(), aml::Error> {
+        region::write(self.space, self.address, self.width, value)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// `width` bytes at `offset` bytes into this register, for registers packed into a block
+    pub fn at(&self, offset: u64, width: usize) -> Self {
+        Self {
+            space: self.space,
+            address: self.address + offset,
+            width,
+        }
+    }
+}
+
 #[repr(C, packed)]
 struct FADT {
     header: Header,
@@ -322,17 +389,48 @@ impl Memory for FADT {}
 impl FADT {
     fn init(&self) -> Result<(), Error> {
         self.header.init(*SIGNATURE)?;
-        facs::set_config(match self.x_firmware_ctrl {
+        // ACPI 1.0 tables end before the 64-bit addresses
+        let x_firmware_ctrl = if self.has(addr_of!(self.x_firmware_ctrl)) {
+            self.x_firmware_ctrl
+        } else {
+            0
+        };
+        let x_dsdt = if self.has(addr_of!(self.x_dsdt)) {
+            self.x_dsdt
+        } else {
+            0
+        };
+        table::add(match x_firmware_ctrl {
             0 => self.firmware_ctrl as usize,
             addr => addr as usize,
         });
-        dsdt::set_config(match self.x_dsdt {
+        table::add(match x_dsdt {
             0 => self.dsdt as usize,
             addr => addr as usize,
         });
         Ok(())
     }
 
+    /// Whether the table is long enough to hold the field at `field`
+    fn has<T>(&self, field: *const T) -> bool {
+        let end = field as usize + size_of::<T>();
+        end - self as *const Self as usize <= self.header.length as usize
+    }
+
+    /// The extended structure if present, otherwise the 32-bit I/O port
+    fn register(&self, extended: &GenericAddressStructure, port: u32, len: u8) -> Option<Register> {
+        if self.has(extended)
+            && let Some(register) = extended.register(len as usize)
+        {
+            return Some(register);
+        }
+        (port != 0).then_some(Register {
+            space: Space::IO,
+            address: port as u64,
+            width: len as usize,
+        })
+    }
+
     fn preferred_pm_profile_to_str(&self) -> &'static str {
         match self.preferred_pm_profile {
             0 => "Unspecified",
@@ -350,10 +448,87 @@ impl FADT {
 }
 
 pub fn init() -> Result<(), Error> {
-    unsafe {
-        if ADDR == 0 {
-            return Err(Error::InvalidAddress(*SIGNATURE));
-        }
-        FADT::get_ref(ADDR).init()
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    FADT::get_ref(table.addr).init()
+}
+
+fn get() -> Option<&'static FADT> {
+    table::find(SIGNATURE, 0).map(|table| FADT::get_ref(table.addr))
+}
+
+/// RESET_REG with the value that resets the system, if RESET_REG_SUP is set
+pub fn reset_register() -> Option<(Register, u8)> {
+    let fadt = get()?;
+    if fadt.flags & (1 << 10) == 0 || !fadt.has(addr_of!(fadt.reset_value)) {
+        return None;
+    }
+    let register = fadt.reset_reg.register(1)?;
+    Some((register, fadt.reset_value))
+}
+
+/// PM1a and the optional PM1b control blocks
+pub fn pm1_control() -> Option<(Register, Option<Register>)> {
+    let fadt = get()?;
+    let len = fadt.pm1_cnt_len;
+    let a = fadt.register(&fadt.x_pm1a_cnt_blk, fadt.pm1a_cnt_blk, len)?;
+    let b = fadt.register(&fadt.x_pm1b_cnt_blk, fadt.pm1b_cnt_blk, len);
+    Some((a, b))
+}
+
+/// Fixed feature flags
+pub fn flags() -> u32 {
+    get().map_or(0, |fadt| fadt.flags)
+}
+
+/// GSI, or the ISA IRQ an override redirects, of the SCI
+pub fn sci_int() -> Option<u16> {
+    get().map(|fadt| fadt.sci_int)
+}
+
+/// SMI_CMD port with the ACPI_ENABLE value, absent when the hardware is always in ACPI mode
+pub fn acpi_enable() -> Option<(u16, u8)> {
+    let fadt = get()?;
+    if fadt.smi_cmd == 0 || fadt.acpi_enable == 0 {
+        return None;
     }
+    Some((fadt.smi_cmd as u16, fadt.acpi_enable))
+}
+
+/// PM1a and the optional PM1b event blocks, each a status register followed by an enable one
+pub fn pm1_event() -> Option<(Register, Option<Register>)> {
+    let fadt = get()?;
+    let len = fadt.pm1_evt_len;
+    let a = fadt.register(&fadt.x_pm1a_evt_blk, fadt.pm1a_evt_blk, len)?;
+    let b = fadt.register(&fadt.x_pm1b_evt_blk, fadt.pm1b_evt_blk, len);
+    let half = len as usize / 2;
+    Some((a.at(0, half), b.map(|b| b.at(0, half))))
+}
+
+/// GPE0 and GPE1 blocks with their lengths and the number of their first GPE
+///
+/// Each block is status bytes followed by as many enable bytes.
+pub fn gpe_blocks() -> [Option<(Register, usize, u8)>; 2] {
+    let Some(fadt) = get() else {
+        return [None, None];
+    };
+    let gpe0_len = fadt.gpe0_blk_len;
+    let gpe1_len = fadt.gpe1_blk_len;
+    let block = |extended, port, len: u8, base| {
+        let register = fadt.register(extended, port, len).filter(|_| len != 0)?;
+        Some((register.at(0, 1), len as usize, base))
+    };
+    [
+        block(&fadt.x_gpe0_blk, fadt.gpe0_blk, gpe0_len, 0),
+        block(&fadt.x_gpe1_blk, fadt.gpe1_blk, gpe1_len, fadt.gpe1_base),
+    ]
+}
+
+/// PM timer with the number of bits it counts, 24 or 32 with TMR_VAL_EXT
+pub fn pm_timer() -> Option<(Register, u32)> {
+    let fadt = get()?;
+    let register = fadt
+        .register(&fadt.x_pm_tmr_blk, fadt.pm_tmr_blk, fadt.pm_tmr_len)
+        .filter(|_| fadt.pm_tmr_len != 0)?;
+    let bits = if fadt.flags & 1 << 8 != 0 { 32 } else { 24 };
+    Some((register, bits))
 }
@@ -3,7 +3,9 @@
 pub mod type0;
 pub mod type1;
 pub mod type2;
+pub mod type3;
 pub mod type4;
+pub mod type5;
 
 #[repr(C, packed)]
 pub struct Header {
@@ -1,9 +1,12 @@
 //! Processor Local APIC
 
-use crate::mem::Memory;
+use crate::{mem::Memory, sync::Once, x86_64::cpu};
 
 use super::{super::Error, Header};
 
+/// ACPI processor UID of the bootstrap processor
+static PROCESSOR_UID: Once<u8> = Once::new();
+
 #[repr(C, packed)]
 struct Type0 {
     header: Header,
@@ -23,10 +26,17 @@ impl Type0 {
         if self.header.length as usize != size_of::<Self>() {
             return Err(Error::InvalidLength(*super::super::SIGNATURE));
         }
+        if self.apic_id as u32 == cpu::features().apic_id() {
+            PROCESSOR_UID.call_once(|| self.acpi_processor_uid);
+        }
         Ok(())
     }
 }
 
+pub fn processor_uid() -> Option<u8> {
+    PROCESSOR_UID.get().copied()
+}
+
 pub fn handle(addr: usize) -> Result<(), Error> {
     Type0::get_ref(addr).handle()
 }
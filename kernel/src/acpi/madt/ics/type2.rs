@@ -26,12 +26,10 @@ impl Type2 {
         if self.header.length as usize != size_of::<Self>() {
             return Err(Error::InvalidLength(*super::super::SIGNATURE).into());
         }
-        ioapic::handle_override(
-            self.source,
-            self.global_system_interrupt,
-            (self.flags & 0b11) as u8,
-            ((self.flags >> 2) & 0b11) as u8,
-        )?;
+        if self.bus != 0 {
+            return Ok(());
+        }
+        ioapic::handle_override(self.source, self.global_system_interrupt, self.flags)?;
         Ok(())
     }
 }
@@ -0,0 +1,31 @@
+//! Non-Maskable Interrupt Source
+
+use crate::{mem::Memory, x86_64::apic::ioapic};
+
+use super::{super::Error, Header};
+
+#[repr(C, packed)]
+struct Type3 {
+    header: Header,
+
+    /// - Bits 0 ..= 1: Polarity
+    /// - Bits 2 ..= 3: Trigger Mode
+    /// - Bits 4 ..= 15: Reserved
+    flags: u16,
+
+    global_system_interrupt: u32,
+}
+impl Memory for Type3 {}
+impl Type3 {
+    fn handle(&self) -> Result<(), crate::Error> {
+        if self.header.length as usize != size_of::<Self>() {
+            return Err(Error::InvalidLength(*super::super::SIGNATURE).into());
+        }
+        ioapic::handle_nmi(self.global_system_interrupt, self.flags)?;
+        Ok(())
+    }
+}
+
+pub fn handle(addr: usize) -> Result<(), crate::Error> {
+    Type3::get_ref(addr).handle()
+}
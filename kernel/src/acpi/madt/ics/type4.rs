@@ -2,9 +2,12 @@
 //!
 //! NMI stands for Non-Maskable Interrupt
 
-use crate::mem::Memory;
+use crate::{
+    mem::Memory,
+    x86_64::apic::{self, lapic},
+};
 
-use super::{super::Error, Header};
+use super::{super::Error, Header, type0};
 
 #[repr(C, packed)]
 struct Type4 {
@@ -22,14 +25,21 @@ struct Type4 {
 }
 impl Memory for Type4 {}
 impl Type4 {
-    fn handle(&self) -> Result<(), Error> {
+    fn handle(&self) -> Result<(), crate::Error> {
         if self.header.length as usize != size_of::<Self>() {
-            return Err(Error::InvalidLength(*super::super::SIGNATURE));
+            return Err(Error::InvalidLength(*super::super::SIGNATURE).into());
         }
+        if self.acpi_processor_uid != 0xFF
+            && Some(self.acpi_processor_uid) != type0::processor_uid()
+        {
+            return Ok(());
+        }
+        let (polarity, _) = apic::decode_inti(self.flags);
+        lapic::handle_nmi(self.local_apic_lint, polarity)?;
         Ok(())
     }
 }
 
-pub fn handle(addr: usize) -> Result<(), Error> {
+pub fn handle(addr: usize) -> Result<(), crate::Error> {
     Type4::get_ref(addr).handle()
 }
@@ -0,0 +1,28 @@
+//! Local APIC Address Override
+
+use crate::mem::Memory;
+
+use super::{super::Error, Header};
+
+#[repr(C, packed)]
+struct Type5 {
+    header: Header,
+
+    reserved: u16,
+
+    /// Replaces the 32-bit address in the MADT header
+    local_apic_address: u64,
+}
+impl Memory for Type5 {}
+impl Type5 {
+    fn handle(&self) -> Result<usize, Error> {
+        if self.header.length as usize != size_of::<Self>() {
+            return Err(Error::InvalidLength(*super::super::SIGNATURE));
+        }
+        Ok(self.local_apic_address as usize)
+    }
+}
+
+pub fn handle(addr: usize) -> Result<usize, Error> {
+    Type5::get_ref(addr).handle()
+}
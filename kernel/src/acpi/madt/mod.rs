@@ -4,18 +4,12 @@ use core::ptr::{addr_of, read_unaligned};
 
 use crate::{io::port, mem::Memory};
 
-use super::{Error, Header};
+use super::{Error, Header, table};
 
 mod ics;
 
 pub const SIGNATURE: &[u8; 4] = b"APIC";
 
-static mut ADDR: usize = 0;
-
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
-
 #[repr(C, packed)]
 struct MADT {
     header: Header,
@@ -30,7 +24,8 @@ struct MADT {
 }
 impl Memory for MADT {}
 impl MADT {
-    fn init(&self) -> Result<u32, crate::Error> {
+    /// Returns the local APIC address, preferring a 64-bit override
+    fn init(&self) -> Result<usize, crate::Error> {
         self.header.init(*SIGNATURE)?;
 
         // Programmable Interrupt Controller
@@ -39,6 +34,7 @@ impl MADT {
             port::out_byte(port::SLAVE_PIC_DATA, 0xFF);
         }
 
+        let mut local_apic_address = self.local_interrupt_controller_address as usize;
         let mut offset = 0usize;
         let structures = addr_of!(self.interrupt_controller_structures) as *const u8;
         while offset < self.header.length as usize - size_of::<Self>() {
@@ -49,16 +45,19 @@ impl MADT {
                     0 => ics::type0::handle(entry as usize)?,
                     1 => ics::type1::handle(entry as usize)?,
                     2 => ics::type2::handle(entry as usize)?,
+                    3 => ics::type3::handle(entry as usize)?,
                     4 => ics::type4::handle(entry as usize)?,
+                    5 => local_apic_address = ics::type5::handle(entry as usize)?,
                     _ => {}
                 }
                 offset += header.length as usize;
             }
         }
-        Ok(self.local_interrupt_controller_address)
+        Ok(local_apic_address)
     }
 }
 
-pub fn init() -> Result<u32, crate::Error> {
-    unsafe { MADT::get_ref(ADDR).init() }
+pub fn init() -> Result<usize, crate::Error> {
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    MADT::get_ref(table.addr).init()
 }
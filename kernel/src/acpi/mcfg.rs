@@ -2,16 +2,10 @@
 
 use crate::{drivers::pcie, mem::Memory};
 
-use super::{Error, Header};
+use super::{Error, Header, table};
 
 pub const SIGNATURE: &[u8; 4] = b"MCFG";
 
-static mut ADDR: usize = 0;
-
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
-
 #[repr(C, packed)]
 struct AllocationStructure {
     base_address: u64,
@@ -34,16 +28,21 @@ struct MCFG {
 }
 impl Memory for MCFG {}
 impl MCFG {
-    fn init(&self) -> Result<(), crate::Error> {
-        self.header.init(*SIGNATURE)?;
-        for structure in unsafe {
+    fn structures(&self) -> &[AllocationStructure] {
+        unsafe {
             core::slice::from_raw_parts(
                 self.structures.as_ptr(),
                 (self.header.length as usize - size_of::<Self>())
                     / size_of::<AllocationStructure>(),
             )
-        } {
-            for bus in 0..=(structure.end_pci_bus - structure.start_pci_bus) {
+        }
+    }
+
+    fn init(&self) -> Result<(), crate::Error> {
+        self.header.init(*SIGNATURE)?;
+        for structure in self.structures() {
+            // The base address is that of bus 0, even when the range starts above it
+            for bus in structure.start_pci_bus..=structure.end_pci_bus {
                 let bus_addr = structure.base_address as usize + ((bus as usize) << 20);
                 for device in 0..32 {
                     let device_addr = bus_addr + (device << 15);
@@ -70,10 +69,33 @@ impl MCFG {
 }
 
 pub fn init() -> Result<(), crate::Error> {
-    unsafe {
-        if ADDR == 0 {
-            return Err(Error::InvalidAddress(*SIGNATURE).into());
-        }
-        MCFG::get_ref(ADDR).init()
-    }
+    let table = table::find(SIGNATURE, 0).ok_or(Error::InvalidAddress(*SIGNATURE))?;
+    MCFG::get_ref(table.addr).init()
+}
+
+fn get() -> Option<&'static MCFG> {
+    table::find(SIGNATURE, 0).map(|table| MCFG::get_ref(table.addr))
+}
+
+/// Configuration space of `bus`:`device`.`function` in segment 0
+pub fn address(bus: u8, device: u8, function: u8) -> Option<usize> {
+    let structure = get()?.structures().iter().find(|structure| {
+        structure.pci_segment_group == 0
+            && (structure.start_pci_bus..=structure.end_pci_bus).contains(&bus)
+    })?;
+    Some(
+        structure.base_address as usize
+            + ((bus as usize) << 20 | (device as usize) << 15 | (function as usize) << 12),
+    )
+}
+
+/// Bus, device and function whose configuration space is at `addr`
+pub fn locate(addr: usize) -> Option<(u8, u8, u8)> {
+    get()?.structures().iter().find_map(|structure| {
+        let offset = addr.checked_sub(structure.base_address as usize)?;
+        let bus = (offset >> 20) as u8;
+        (offset >> 20 <= u8::MAX as usize
+            && (structure.start_pci_bus..=structure.end_pci_bus).contains(&bus))
+        .then_some((bus, (offset >> 15) as u8 & 0x1F, (offset >> 12) as u8 & 0x7))
+    })
 }
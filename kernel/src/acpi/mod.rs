@@ -1,22 +1,45 @@
 //! Advanced Configuration and Power Interface
 
+pub mod aml;
+pub mod dmar;
 mod dsdt;
 mod error;
 mod facs;
 mod fadt;
 pub mod madt;
 pub mod mcfg;
+pub mod pm_timer;
+pub mod power;
+pub mod prt;
 mod rsdp;
+mod rsdt;
+pub mod sci;
+mod ssdt;
+pub mod table;
 mod xsdt;
 
 pub use error::Error;
 
-use crate::math::Checksum;
+use crate::{debug, info, math::Checksum, warn};
 
 pub fn init(rsdp_addr: usize) -> Result<(), Error> {
-    let xsdt_addr = rsdp::init(rsdp_addr)?;
-    xsdt::init(xsdt_addr)?;
-    fadt::init()
+    match rsdp::init(rsdp_addr)? {
+        rsdp::Root::RSDT(addr) => rsdt::init(addr)?,
+        rsdp::Root::XSDT(addr) => xsdt::init(addr)?,
+    }
+    fadt::init()?;
+    // Hardware-reduced platforms need not have one
+    if let Err(err) = facs::init() {
+        warn!("ACPI", err);
+    }
+    dsdt::init()?;
+    ssdt::init();
+    prt::init();
+    for table in (0..table::count()).filter_map(table::get) {
+        debug!("ACPI", table);
+    }
+    info!("ACPI", "Namespace of ", aml::len(), " objects");
+    Ok(())
 }
 
 #[repr(C, packed)]
@@ -47,4 +70,11 @@ impl Header {
         }
         Ok(())
     }
+
+    /// Bytes following the header, such as the definition block of the DSDT
+    fn body(&self) -> &'static [u8] {
+        let addr = self as *const Self as usize + size_of::<Self>();
+        let len = (self.length as usize).saturating_sub(size_of::<Self>());
+        unsafe { core::slice::from_raw_parts(addr as *const u8, len) }
+    }
 }
@@ -0,0 +1,64 @@
+//! Power Management Timer
+//!
+//! Free-running 3.579545 MHz counter, 24 or 32 bits wide. The SCI raised every time its top bit
+//! toggles reads it at least twice per wrap, which is enough to extend the count to 64 bits.
+
+use crate::{sync::IrqSpinlock, time};
+
+use super::{
+    Error,
+    fadt::{self, Register},
+    sci::{self, Event},
+};
+
+/// Ticks per second
+pub const HZ: u64 = 3_579_545;
+
+struct State {
+    timer: Option<(Register, u32)>,
+
+    /// Counter at the last read
+    last: u64,
+
+    /// Ticks since boot as of the last read
+    count: u64,
+}
+
+static STATE: IrqSpinlock<State> = IrqSpinlock::new(State {
+    timer: None,
+    last: 0,
+    count: 0,
+});
+
+/// Ticks since boot, `None` without a PM timer
+pub fn ticks() -> Option<u64> {
+    let mut state = STATE.lock();
+    let (register, bits) = state.timer?;
+    let mask = (1 << bits) - 1;
+    let value = register.read().ok()? & mask;
+    state.count += value.wrapping_sub(state.last) & mask;
+    state.last = value;
+    Some(state.count)
+}
+
+pub fn uptime_ns() -> Option<u64> {
+    let ticks = ticks()?;
+    Some(ticks / HZ * 1_000_000_000 + ticks % HZ * 1_000_000_000 / HZ)
+}
+
+/// Only the read matters, to catch the wrap
+fn overflow() {
+    ticks();
+}
+
+/// Starts the count where the timer interrupt has got to
+pub(super) fn init() -> Result<(), crate::Error> {
+    let (register, bits) = fadt::pm_timer().ok_or(Error::Unsupported("PM Timer"))?;
+    let value = register.read()? & ((1 << bits) - 1);
+    *STATE.lock() = State {
+        timer: Some((register, bits)),
+        last: value,
+        count: time::uptime_ms() * HZ / 1000,
+    };
+    sci::register(Event::Timer, overflow)
+}
@@ -0,0 +1,110 @@
+//! Power
+//!
+//! Soft-off through the S5 sleep state and reset through the FADT, after the drives have been
+//! told to flush.
+
+use crate::{
+    drivers::storage,
+    error, info,
+    io::port,
+    x86_64::{self, rflags},
+};
+
+use super::{Error, aml, fadt};
+
+/// PM1 Control
+/// - Bits 10 ..= 12: SLP_TYP
+/// - Bit 13: SLP_EN
+const SLP_TYP_SHIFT: u64 = 10;
+const SLP_TYP_MASK: u64 = 0b111 << SLP_TYP_SHIFT;
+const SLP_EN: u64 = 1 << 13;
+
+/// PS/2 controller command pulsing the CPU reset line
+const PS2_RESET: u8 = 0xFE;
+
+/// Microseconds to wait for the hardware to act before trying the next way
+const SETTLE_US: usize = 100_000;
+
+fn settle() {
+    for _ in 0..SETTLE_US {
+        port::out_byte(port::POST, 0);
+    }
+}
+
+fn flush() {
+    if let Err(err) = storage::shutdown() {
+        error!("Power", err);
+    }
+}
+
+/// SLP_TYPa and SLP_TYPb of `\_S5_`
+fn s5() -> Result<(u64, u64), aml::Error> {
+    let package = aml::evaluate("\\_S5_", &[])?.as_package()?;
+    let a = package.get(0)?.as_integer()?;
+    let b = package.get(1).and_then(|b| b.as_integer()).unwrap_or(0);
+    Ok((a, b))
+}
+
+fn enter_s5() -> Result<(), crate::Error> {
+    let (a, b) = s5()?;
+    let (pm1a, pm1b) = fadt::pm1_control().ok_or(Error::Unsupported("PM1 Control"))?;
+    // Prepare To Sleep
+    if aml::find("\\_PTS").is_some() {
+        aml::evaluate("\\_PTS", &[aml::Value::Integer(5)])?;
+    }
+
+    rflags::disable_interrupts();
+    let value_a = (pm1a.read()? & !(SLP_TYP_MASK | SLP_EN)) | (a << SLP_TYP_SHIFT) & SLP_TYP_MASK;
+    let value_b = match pm1b {
+        Some(pm1b) => {
+            let value =
+                (pm1b.read()? & !(SLP_TYP_MASK | SLP_EN)) | (b << SLP_TYP_SHIFT) & SLP_TYP_MASK;
+            pm1b.write(value)?;
+            value
+        }
+        None => 0,
+    };
+    pm1a.write(value_a)?;
+    pm1a.write(value_a | SLP_EN)?;
+    if let Some(pm1b) = pm1b {
+        pm1b.write(value_b | SLP_EN)?;
+    }
+    settle();
+    Err(Error::Unsupported("S5").into())
+}
+
+/// Powers the machine off, halting instead if the firmware does not let it
+pub fn shutdown() -> ! {
+    info!("Power", "Shutting down");
+    flush();
+    if let Err(err) = enter_s5() {
+        error!("Power", err);
+    }
+    x86_64::halt()
+}
+
+/// Resets through RESET_REG, then the PS/2 controller, then a triple fault
+pub fn reboot() -> ! {
+    info!("Power", "Rebooting");
+    flush();
+    rflags::disable_interrupts();
+
+    if let Some((register, value)) = fadt::reset_register() {
+        match register.write(value as u64) {
+            Ok(()) => settle(),
+            Err(err) => error!("Power", err),
+        }
+    }
+
+    // Input buffer empty, which never comes without a controller
+    for _ in 0..SETTLE_US {
+        if port::in_byte(port::PS2_COMMAND) & 0b10 == 0 {
+            break;
+        }
+        port::out_byte(port::POST, 0);
+    }
+    port::out_byte(port::PS2_COMMAND, PS2_RESET);
+    settle();
+
+    x86_64::triple_fault()
+}
@@ -0,0 +1,285 @@
+//! PCI Routing Table
+//!
+//! Maps the INTx pins of PCI devices to GSIs through the `_PRT` of their bus, either straight to
+//! a GSI or through an interrupt link device whose `_CRS` names the IRQ it drives. A link the
+//! firmware left disabled is set to the first IRQ its `_PRS` offers. An IRQ descriptor names an
+//! ISA IRQ, which reaches its GSI through any MADT interrupt source override.
+
+use crate::{
+    debug,
+    drivers::pcie,
+    sync::Spinlock,
+    warn,
+    x86_64::apic::{Polarity, TriggerMode, ioapic},
+};
+
+use super::aml::{self, Bytes, Handle, Value};
+
+/// PCI Bus and PCI Express Root Complex
+const ROOT_BRIDGE_IDS: [&[u8; 7]; 2] = [b"PNP0A03", b"PNP0A08"];
+
+/// Resource descriptor tags
+const IRQ_TAG: u8 = 0x04;
+const END_TAG: u8 = 0x0F;
+const EXTENDED_IRQ_TAG: u8 = 0x09;
+
+/// Largest template `_SRS` is handed: an Extended Interrupt with one IRQ and an End Tag
+const SRS_SIZE: usize = 11;
+
+/// Template handed to `_SRS`, static since AML buffers point into memory that outlives the call
+static SRS_BUFFER: Spinlock<[u8; SRS_SIZE]> = Spinlock::new([0; SRS_SIZE]);
+
+#[derive(Clone, Copy)]
+pub struct Route {
+    pub gsi: u32,
+    pub polarity: Polarity,
+    pub trigger_mode: TriggerMode,
+}
+
+/// Interrupt descriptor decoded from a resource template
+#[derive(Clone, Copy)]
+struct Resource {
+    /// Extended Interrupt rather than IRQ
+    extended: bool,
+
+    /// Every IRQ the descriptor lists, lowest first
+    irqs: [Option<u32>; 16],
+
+    polarity: Polarity,
+    trigger_mode: TriggerMode,
+}
+
+/// Compressed EISA ID, as `EISAID("PNP0A03")` encodes it
+fn eisa_id(id: &[u8; 7]) -> u64 {
+    let letter = |c: u8| (c - 0x40) as u32 & 0x1F;
+    let hex = |c: u8| (c as char).to_digit(16).unwrap_or(0);
+    let id = letter(id[0]) << 26
+        | letter(id[1]) << 21
+        | letter(id[2]) << 16
+        | hex(id[3]) << 12
+        | hex(id[4]) << 8
+        | hex(id[5]) << 4
+        | hex(id[6]);
+    id.swap_bytes() as u64
+}
+
+fn is_id(value: &Value, id: &[u8; 7]) -> bool {
+    match value {
+        Value::Integer(value) => *value == eisa_id(id),
+        Value::String(bytes) => bytes.as_slice() == id,
+        _ => false,
+    }
+}
+
+/// Whether the `_HID` or any `_CID` of `device` names a host bridge
+fn is_root_bridge(device: Handle) -> bool {
+    let matches = |value: &Value| ROOT_BRIDGE_IDS.iter().any(|id| is_id(value, id));
+    let evaluate =
+        |name| aml::child(device, name).and_then(|handle| aml::evaluate_handle(handle, &[]).ok());
+    if evaluate(*b"_HID").is_some_and(|hid| matches(&hid)) {
+        return true;
+    }
+    match evaluate(*b"_CID") {
+        Some(Value::Package(ids)) => {
+            (0..ids.len()).any(|i| ids.get(i).is_ok_and(|id| matches(&id)))
+        }
+        Some(cid) => matches(&cid),
+        None => false,
+    }
+}
+
+/// Integer object `name` under `device`, such as `_BBN` or `_ADR`
+fn integer(device: Handle, name: [u8; 4]) -> Option<u64> {
+    let handle = aml::child(device, name)?;
+    aml::evaluate_handle(handle, &[]).ok()?.as_integer().ok()
+}
+
+/// Namespace device of `bus`: the host bridge numbering it, or the bridge leading to it found by
+/// its `_ADR` under the device of the bus above
+fn scope(bus: u8) -> Option<Handle> {
+    let sb = aml::find("\\_SB_")?;
+    let root = aml::children(sb).find(|&device| {
+        is_root_bridge(device) && integer(device, *b"_BBN").unwrap_or(0) == bus as u64
+    });
+    if root.is_some() {
+        return root;
+    }
+    let (parent, device, function) = pcie::bridge(bus)?;
+    let address = (device as u64) << 16 | function as u64;
+    aml::children(scope(parent)?).find(|&child| integer(child, *b"_ADR") == Some(address))
+}
+
+/// Decodes the first IRQ or Extended Interrupt descriptor of a resource template
+///
+/// - Small: Bit 7 clear, bits 3 ..= 6 name, bits 0 ..= 2 length
+/// - Large: Bit 7 set, bits 0 ..= 6 name, followed by a 16-bit length
+fn parse(template: &[u8]) -> Option<Resource> {
+    let mut offset = 0;
+    while let Some(&tag) = template.get(offset) {
+        if tag & 0x80 == 0 {
+            let name = (tag >> 3) & 0xF;
+            let len = (tag & 0x7) as usize;
+            let body = template.get(offset + 1..offset + 1 + len)?;
+            if name == END_TAG {
+                return None;
+            }
+            if name == IRQ_TAG && len >= 2 {
+                let mask = u16::from_le_bytes([body[0], body[1]]);
+                // Active high and edge-triggered without the flags byte
+                // - Bit 0: Edge
+                // - Bit 3: Active Low
+                let flags = body.get(2).copied().unwrap_or(0b0001);
+                let mut irqs = [None; 16];
+                for (slot, irq) in irqs.iter_mut().zip((0..16).filter(|i| mask & 1 << i != 0)) {
+                    *slot = Some(irq);
+                }
+                return Some(Resource {
+                    extended: false,
+                    irqs,
+                    polarity: if flags & 1 << 3 != 0 {
+                        Polarity::Low
+                    } else {
+                        Polarity::High
+                    },
+                    trigger_mode: if flags & 1 << 0 != 0 {
+                        TriggerMode::Edge
+                    } else {
+                        TriggerMode::Level
+                    },
+                });
+            }
+            offset += 1 + len;
+        } else {
+            let len = u16::from_le_bytes([*template.get(offset + 1)?, *template.get(offset + 2)?])
+                as usize;
+            let body = template.get(offset + 3..offset + 3 + len)?;
+            if tag & 0x7F == EXTENDED_IRQ_TAG && len >= 2 {
+                // - Bit 1: Edge
+                // - Bit 2: Active Low
+                let flags = body[0];
+                let count = body[1] as usize;
+                let mut irqs = [None; 16];
+                for (slot, irq) in irqs.iter_mut().zip(body[2..].chunks_exact(4).take(count)) {
+                    *slot = Some(u32::from_le_bytes([irq[0], irq[1], irq[2], irq[3]]));
+                }
+                return Some(Resource {
+                    extended: true,
+                    irqs,
+                    polarity: if flags & 1 << 2 != 0 {
+                        Polarity::Low
+                    } else {
+                        Polarity::High
+                    },
+                    trigger_mode: if flags & 1 << 1 != 0 {
+                        TriggerMode::Edge
+                    } else {
+                        TriggerMode::Level
+                    },
+                });
+            }
+            offset += 3 + len;
+        }
+    }
+    None
+}
+
+fn resources(link: Handle, name: [u8; 4]) -> Result<Option<Resource>, crate::Error> {
+    let Some(handle) = aml::child(link, name) else {
+        return Ok(None);
+    };
+    Ok(parse(aml::evaluate_handle(handle, &[])?.as_bytes()?))
+}
+
+/// Points `link` at `irq` through `_SRS`, in the kind of descriptor `_PRS` offered it in
+fn set(link: Handle, possible: &Resource, irq: u32) -> Result<(), crate::Error> {
+    let srs = aml::child(link, *b"_SRS").ok_or(super::Error::Unsupported("_SRS"))?;
+    let mut buffer = SRS_BUFFER.lock();
+    let low = possible.polarity == Polarity::Low;
+    let level = possible.trigger_mode == TriggerMode::Level;
+    let len = if possible.extended {
+        // Consumer, with sharing allowed for level-triggered lines
+        let flags = 1 | (!level as u8) << 1 | (low as u8) << 2 | (level as u8) << 3;
+        buffer[..5].copy_from_slice(&[0x80 | EXTENDED_IRQ_TAG, 6, 0, flags, 1]);
+        buffer[5..9].copy_from_slice(&irq.to_le_bytes());
+        9
+    } else {
+        let mask = (1u16 << irq).to_le_bytes();
+        let flags = !level as u8 | (low as u8) << 3 | (level as u8) << 4;
+        buffer[..4].copy_from_slice(&[IRQ_TAG << 3 | 3, mask[0], mask[1], flags]);
+        4
+    };
+    // Checksum of zero reads as already valid
+    buffer[len..len + 2].copy_from_slice(&[END_TAG << 3 | 1, 0]);
+    let template = unsafe { core::slice::from_raw_parts(buffer.as_ptr(), len + 2) };
+    aml::evaluate_handle(srs, &[Value::Buffer(Bytes::from_slice(template))])?;
+    Ok(())
+}
+
+/// GSI an interrupt link device drives, set up from its options if it drives none yet
+fn link_route(link: Handle) -> Result<Route, crate::Error> {
+    let current = resources(link, *b"_CRS")?;
+    let (resource, irq) = match current.and_then(|resource| Some((resource, resource.irqs[0]?))) {
+        Some(current) => current,
+        None => {
+            let possible = resources(link, *b"_PRS")?.ok_or(super::Error::Unsupported("_PRS"))?;
+            let irq = possible.irqs[0].ok_or(super::Error::Unsupported("_PRS"))?;
+            set(link, &possible, irq)?;
+            debug!("PRT", link, " set to IRQ ", irq as usize);
+            (possible, irq)
+        }
+    };
+    let gsi = if resource.extended {
+        irq
+    } else {
+        ioapic::isa_gsi(irq as u8)?
+    };
+    Ok(Route {
+        gsi,
+        polarity: resource.polarity,
+        trigger_mode: resource.trigger_mode,
+    })
+}
+
+/// Route of INTx `pin` of `device` on `bus` from the `_PRT` of that bus, if it has one
+///
+/// - `pin`: 0 ..= 3 for INTA# ..= INTD#
+pub fn resolve(bus: u8, device: u8, pin: u8) -> Result<Option<Route>, crate::Error> {
+    let Some(prt) = scope(bus).and_then(|scope| aml::child(scope, *b"_PRT")) else {
+        return Ok(None);
+    };
+    let table = aml::evaluate_handle(prt, &[])?.as_package()?;
+
+    // Picked out before evaluating a link, which reclaims whatever `_PRT` built
+    let mut source = None;
+    for i in 0..table.len() {
+        // Address, Pin, Source, Source Index
+        // - Address: Device in bits 16 ..= 31, function 0xFFFF for any
+        let entry = table.get(i)?.as_package()?;
+        if entry.get(0)?.as_integer()? >> 16 != device as u64
+            || entry.get(1)?.as_integer()? != pin as u64
+        {
+            continue;
+        }
+        source = Some((entry.get(2)?, entry.get(3)?.as_integer()? as u32));
+        break;
+    }
+    Ok(match source {
+        None => None,
+        Some((Value::Reference(link), _)) => Some(link_route(link)?),
+        // Hardwired to a GSI, which PCI signals as shared, level-triggered and active low
+        Some((_, gsi)) => Some(Route {
+            gsi,
+            polarity: Polarity::Low,
+            trigger_mode: TriggerMode::Level,
+        }),
+    })
+}
+
+/// Tells the firmware interrupts go through IOAPICs, so `_PRT` returns GSIs rather than PIC IRQs
+pub fn init() {
+    if let Some(pic) = aml::find("\\_PIC")
+        && let Err(err) = aml::evaluate_handle(pic, &[Value::Integer(1)])
+    {
+        warn!("PRT", err);
+    }
+}
@@ -34,7 +34,7 @@ impl RSDP1_0 {
         if !self.checksum(size_of::<Self>()) {
             return Err(Error::InvalidChecksum(*FAKE_SIGNATURE));
         }
-        if self.revision != 2 {
+        if !matches!(self.revision, 0 | 2) {
             return Err(Error::InvalidRevision(*FAKE_SIGNATURE));
         }
         Ok(())
@@ -56,19 +56,32 @@ struct RSDP {
 impl Checksum for RSDP {}
 impl Memory for RSDP {}
 impl RSDP {
-    fn init(&self) -> Result<usize, Error> {
+    fn init(&self) -> Result<Root, Error> {
         self.rsdp1_0.init()?;
+        // ACPI 1.0 ends before `length`
+        if self.rsdp1_0.revision == 0 {
+            return Ok(Root::RSDT(self.rsdp1_0.rsdt_address as usize));
+        }
         if self.length != size_of::<Self>() as u32 {
             return Err(Error::InvalidLength(*FAKE_SIGNATURE));
         }
         if !self.checksum(self.length as usize) {
             return Err(Error::InvalidChecksum(*FAKE_SIGNATURE));
         }
-        Ok(self.xsdt_address as usize)
+        Ok(match self.xsdt_address {
+            0 => Root::RSDT(self.rsdp1_0.rsdt_address as usize),
+            addr => Root::XSDT(addr as usize),
+        })
     }
 }
 
-pub fn init(addr: usize) -> Result<usize, Error> {
+/// Table listing every other one, with 64-bit entries from ACPI 2.0 on
+pub enum Root {
+    RSDT(usize),
+    XSDT(usize),
+}
+
+pub fn init(addr: usize) -> Result<Root, Error> {
     if addr == 0 {
         return Err(Error::InvalidAddress(*FAKE_SIGNATURE));
     }
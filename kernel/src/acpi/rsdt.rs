@@ -0,0 +1,36 @@
+//! Root System Description Table
+
+use core::ptr::{addr_of, read_unaligned};
+
+use crate::mem::Memory;
+
+use super::{Error, Header, table};
+
+const SIGNATURE: &[u8; 4] = b"RSDT";
+
+#[repr(C, packed)]
+struct RSDT {
+    header: Header,
+
+    entries: [u32; 0],
+}
+impl Memory for RSDT {}
+impl RSDT {
+    fn init(&self) -> Result<(), Error> {
+        self.header.init(*SIGNATURE)?;
+        let count = (self.header.length as usize - size_of::<Self>()) / size_of::<u32>();
+        let entries = addr_of!(self.entries) as *const u32;
+        for i in 0..count {
+            table::add(unsafe { read_unaligned(entries.add(i)) } as usize);
+        }
+        Ok(())
+    }
+}
+
+pub fn init(addr: usize) -> Result<(), Error> {
+    if addr == 0 {
+        return Err(Error::InvalidAddress(*SIGNATURE));
+    }
+    table::add(addr);
+    RSDT::get_ref(addr).init()
+}
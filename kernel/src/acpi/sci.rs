@@ -0,0 +1,330 @@
+//! System Control Interrupt
+//!
+//! Switches the chipset into ACPI mode and takes the SCI, dispatching fixed PM1 events and
+//! general-purpose events to their `\_GPE._Lxx` or `\_GPE._Exx` methods. The interrupt only
+//! masks a GPE, queues it and wakes a task that runs the method and unmasks it.
+//!
+//! Firmware without an SCI, or that never hands the hardware over, leaves the kernel without
+//! fixed events and GPEs but boots all the same.
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::{
+    debug, error, info,
+    io::port,
+    sync::IrqSpinlock,
+    task, warn,
+    x86_64::{apic::ioapic, idt::vector},
+};
+
+use super::{
+    Error, aml, facs,
+    fadt::{self, Register},
+    pm_timer, power,
+};
+
+/// PM1 Control
+/// - Bit 0: SCI_EN
+const SCI_EN: u64 = 1 << 0;
+
+/// Fixed feature flags
+const PWR_BUTTON: u32 = 1 << 4;
+const SLP_BUTTON: u32 = 1 << 5;
+const FIX_RTC: u32 = 1 << 6;
+const HW_REDUCED_ACPI: u32 = 1 << 20;
+
+/// Microseconds to wait for SMI firmware to hand the hardware over
+const ACPI_ENABLE_TIMEOUT_US: usize = 3_000_000;
+
+const GPE_COUNT: usize = 256;
+
+pub type Handler = fn();
+
+/// Fixed events, numbered by their bit in PM1 status and enable
+#[derive(Clone, Copy)]
+pub enum Event {
+    /// Bit 23 or 31 of the PM timer toggled
+    Timer = 0,
+    /// Firmware released the global lock with a request pending
+    GlobalLock = 5,
+    PowerButton = 8,
+    SleepButton = 9,
+    RTC = 10,
+}
+
+static HANDLERS: IrqSpinlock<[Option<Handler>; 16]> = IrqSpinlock::new([None; 16]);
+
+/// GPEs masked by the interrupt, waiting for their method
+static PENDING: [AtomicU64; GPE_COUNT / 64] = [const { AtomicU64::new(0) }; GPE_COUNT / 64];
+
+/// Task running the queued GPE methods, `usize::MAX` until it starts
+static WORKER: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Runs `register` on PM1a and PM1b, whose bits are ORed together
+fn pm1(mut f: impl FnMut(Register) -> Result<(), aml::Error>) -> Result<(), crate::Error> {
+    let (a, b) = fadt::pm1_event().ok_or(Error::Unsupported("PM1 Event"))?;
+    f(a)?;
+    if let Some(b) = b {
+        f(b)?;
+    }
+    Ok(())
+}
+
+fn enable_register(status: Register) -> Register {
+    status.at(status.width() as u64, status.width())
+}
+
+/// Calls `handler` on `event`, replacing any earlier one
+pub fn register(event: Event, handler: Handler) -> Result<(), crate::Error> {
+    HANDLERS.lock()[event as usize] = Some(handler);
+    let bit = 1 << event as u64;
+    pm1(|status| {
+        let enable = enable_register(status);
+        enable.write(enable.read()? | bit)
+    })
+}
+
+/// Status and enable registers of the byte holding GPE `number`, with its bit
+fn locate(number: u8) -> Option<(Register, Register, u8)> {
+    for (block, len, base) in fadt::gpe_blocks().into_iter().flatten() {
+        let Some(index) = number.checked_sub(base).map(usize::from) else {
+            continue;
+        };
+        if index < len / 2 * 8 {
+            let byte = index / 8;
+            let status = block.at(byte as u64, 1);
+            let enable = block.at((len / 2 + byte) as u64, 1);
+            return Some((status, enable, (index % 8) as u8));
+        }
+    }
+    None
+}
+
+fn set_gpe_enable(number: u8, enabled: bool) -> Result<(), crate::Error> {
+    let (_, enable, bit) = locate(number).ok_or(Error::Unsupported("GPE"))?;
+    let value = enable.read()?;
+    let value = if enabled {
+        value | 1 << bit
+    } else {
+        value & !(1 << bit)
+    };
+    enable.write(value)?;
+    Ok(())
+}
+
+/// Name of the method handling GPE `number`, `_L` for level-triggered or `_E` for edge
+fn gpe_method(kind: u8, number: u8) -> [u8; 4] {
+    let hex = |nibble: u8| match nibble {
+        0..=9 => b'0' + nibble,
+        _ => b'A' + nibble - 10,
+    };
+    [b'_', kind, hex(number >> 4), hex(number & 0xF)]
+}
+
+fn handle_fixed() -> Result<(), crate::Error> {
+    let handlers = *HANDLERS.lock();
+    pm1(|status| {
+        let enable = enable_register(status);
+        let pending = status.read()? & enable.read()?;
+        // Write-one-to-clear
+        status.write(pending)?;
+        for (bit, handler) in handlers.iter().enumerate() {
+            if pending & (1 << bit) != 0
+                && let Some(handler) = handler
+            {
+                handler();
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Masks every pending GPE and queues it, leaving its status for the method to clear
+fn handle_gpes() -> Result<(), crate::Error> {
+    for (block, len, base) in fadt::gpe_blocks().into_iter().flatten() {
+        for byte in 0..len / 2 {
+            let status = block.at(byte as u64, 1);
+            let enable = block.at((len / 2 + byte) as u64, 1);
+            let enabled = enable.read()?;
+            let pending = status.read()? & enabled;
+            if pending == 0 {
+                continue;
+            }
+            // Masked first so that a level-triggered GPE cannot raise the SCI again
+            enable.write(enabled & !pending)?;
+            for bit in 0..8 {
+                if pending & (1 << bit) != 0 {
+                    let number = base.wrapping_add((byte * 8 + bit) as u8) as usize;
+                    PENDING[number / 64].fetch_or(1 << (number % 64), Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the method of masked GPE `number`, clearing its status and unmasking it on success
+///
+/// An edge is cleared before the method so that a new one during it is not lost, a level after
+/// the method has dealt with its source.
+fn run_gpe(number: u8) -> Result<(), crate::Error> {
+    let (status, _, bit) = locate(number).ok_or(Error::Unsupported("GPE"))?;
+    let method =
+        |kind| aml::find("\\_GPE").and_then(|scope| aml::child(scope, gpe_method(kind, number)));
+    let result = if let Some(method) = method(b'E') {
+        status.write(1 << bit)?;
+        aml::evaluate_handle(method, &[])
+    } else if let Some(method) = method(b'L') {
+        let result = aml::evaluate_handle(method, &[]);
+        status.write(1 << bit)?;
+        result
+    } else {
+        status.write(1 << bit)?;
+        return Err(Error::Unsupported("GPE Method").into());
+    };
+    // Left masked on failure, since nothing would ever clear it
+    result?;
+    set_gpe_enable(number, true)
+}
+
+fn gpe_worker(_: usize) -> usize {
+    loop {
+        task::sleep_until(u64::MAX);
+        for (word, pending) in PENDING.iter().enumerate() {
+            let mut bits = pending.swap(0, Ordering::Relaxed);
+            while bits != 0 {
+                let number = (word * 64 + bits.trailing_zeros() as usize) as u8;
+                bits &= bits - 1;
+                if let Err(err) = run_gpe(number) {
+                    error!("SCI", "GPE ", number as usize, ": ", err);
+                }
+            }
+        }
+    }
+}
+
+fn interrupt(_: usize) {
+    if let Err(err) = handle_fixed().and_then(|_| handle_gpes()) {
+        error!("SCI", err);
+    }
+    if PENDING
+        .iter()
+        .any(|pending| pending.load(Ordering::Relaxed) != 0)
+    {
+        task::wake(WORKER.load(Ordering::Relaxed));
+    }
+}
+
+/// Shuts down from a task, out of the interrupt that may have cut into a driver holding a lock
+fn orderly_shutdown() {
+    fn entry(_: usize) -> usize {
+        power::shutdown()
+    }
+    if let Err(err) = task::spawn(entry, 0) {
+        error!("SCI", err);
+    }
+}
+
+/// Waiters poll the lock itself, so the event only needs clearing
+fn global_lock_released() {
+    debug!("SCI", "Global lock released");
+}
+
+fn log_sleep_button() {
+    info!("SCI", "Sleep button");
+}
+
+fn log_rtc_alarm() {
+    info!("SCI", "RTC alarm");
+}
+
+/// Sets SCI_EN through SMI_CMD unless the firmware already did
+fn enable_acpi_mode() -> Result<(), crate::Error> {
+    let (pm1a, _) = fadt::pm1_control().ok_or(Error::Unsupported("PM1 Control"))?;
+    if pm1a.read()? & SCI_EN != 0 {
+        return Ok(());
+    }
+    let (smi_cmd, acpi_enable) = fadt::acpi_enable().ok_or(Error::Unsupported("SMI_CMD"))?;
+    port::out_byte(smi_cmd, acpi_enable);
+    for _ in 0..ACPI_ENABLE_TIMEOUT_US {
+        if pm1a.read()? & SCI_EN != 0 {
+            debug!("SCI", "ACPI mode enabled");
+            return Ok(());
+        }
+        port::out_byte(port::POST, 0);
+    }
+    Err(Error::Unsupported("ACPI Mode").into())
+}
+
+/// Takes the SCI, or warns and goes without it
+pub fn init() {
+    if let Err(err) = enable() {
+        warn!("SCI", err);
+    }
+}
+
+fn enable() -> Result<(), crate::Error> {
+    let flags = fadt::flags();
+    if flags & HW_REDUCED_ACPI != 0 {
+        return Err(Error::Unsupported("Fixed Hardware").into());
+    }
+    let sci = fadt::sci_int().ok_or(Error::Unsupported("SCI"))?;
+    enable_acpi_mode()?;
+
+    // Start from a clean slate: nothing enabled, nothing pending
+    pm1(|status| {
+        enable_register(status).write(0)?;
+        status.write(u64::MAX)
+    })?;
+    for (block, len, _) in fadt::gpe_blocks().into_iter().flatten() {
+        for byte in 0..len / 2 {
+            block.at((len / 2 + byte) as u64, 1).write(0)?;
+            block.at(byte as u64, 1).write(0xFF)?;
+        }
+    }
+
+    let vector = vector::allocate(1)?;
+    vector::register(vector, interrupt, 0)?;
+    ioapic::route_sci(sci as u32, vector)?;
+
+    if facs::is_present() {
+        register(Event::GlobalLock, global_lock_released)?;
+    }
+    if flags & PWR_BUTTON == 0 {
+        register(Event::PowerButton, orderly_shutdown)?;
+    }
+    if flags & SLP_BUTTON == 0 {
+        register(Event::SleepButton, log_sleep_button)?;
+    }
+    if flags & FIX_RTC == 0 {
+        register(Event::RTC, log_rtc_alarm)?;
+    }
+    // Only the nanosecond uptime needs it
+    if let Err(err) = pm_timer::init() {
+        warn!("SCI", err);
+    }
+
+    // GPEs the firmware has methods for
+    let mut count: usize = 0;
+    if let Some(scope) = aml::find("\\_GPE") {
+        for (_, len, base) in fadt::gpe_blocks().into_iter().flatten() {
+            for index in 0..len / 2 * 8 {
+                let number = base.wrapping_add(index as u8);
+                let has_method = aml::child(scope, gpe_method(b'L', number)).is_some()
+                    || aml::child(scope, gpe_method(b'E', number)).is_some();
+                if has_method {
+                    set_gpe_enable(number, true)?;
+                    count += 1;
+                }
+            }
+        }
+    }
+    info!("SCI", "GSI ", sci as usize, " with ", count, " GPEs");
+    Ok(())
+}
+
+/// Starts running GPE methods, which needs tasks
+pub fn start_worker() -> Result<(), crate::Error> {
+    WORKER.store(task::spawn(gpe_worker, 0)?, Ordering::Relaxed);
+    Ok(())
+}
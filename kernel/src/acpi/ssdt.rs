@@ -0,0 +1,31 @@
+//! Secondary System Description Table
+
+use crate::{mem::Memory, warn};
+
+use super::{Error, Header, aml, table};
+
+pub const SIGNATURE: &[u8; 4] = b"SSDT";
+
+#[repr(C, packed)]
+struct SSDT {
+    header: Header,
+}
+impl Memory for SSDT {}
+impl SSDT {
+    fn init(&self) -> Result<(), Error> {
+        self.header.init(*SIGNATURE)?;
+        aml::load(self.header.body(), None)?;
+        Ok(())
+    }
+}
+
+/// Loads every SSDT after the DSDT, skipping any that fails
+pub fn init() {
+    let mut instance = 0;
+    while let Some(table) = table::find(SIGNATURE, instance) {
+        instance += 1;
+        if let Err(err) = SSDT::get_ref(table.addr).init() {
+            warn!("ACPI", err);
+        }
+    }
+}
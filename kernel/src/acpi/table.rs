@@ -0,0 +1,176 @@
+//! Table Registry
+//!
+//! Every table reachable from the RSDT or XSDT, plus the DSDT and FACS the FADT points at, with
+//! its checksum checked once on the way in.
+
+use crate::{io::text::Output, sync::RwLock, warn};
+
+use super::Header;
+
+const MAX_TABLE_COUNT: usize = 64;
+
+/// Signature of the only table without the common header
+const FACS_SIGNATURE: &[u8; 4] = b"FACS";
+
+/// Bytes per line of `dump`
+const DUMP_WIDTH: usize = 16;
+
+#[derive(Clone, Copy)]
+pub struct Table {
+    pub signature: [u8; 4],
+
+    /// Blank for the FACS
+    pub oem_id: [u8; 6],
+
+    pub revision: u8,
+
+    pub addr: usize,
+    pub len: usize,
+}
+impl Table {
+    const fn null() -> Self {
+        Self {
+            signature: [0; 4],
+            oem_id: [0; 6],
+            revision: 0,
+            addr: 0,
+            len: 0,
+        }
+    }
+
+    pub fn as_slice(&self) -> &'static [u8] {
+        unsafe { core::slice::from_raw_parts(self.addr as *const u8, self.len) }
+    }
+}
+impl Output for Table {
+    /// `SSDT @ 0x7FFE0000 Length 0x1A4 Revision 2 OEM BOCHS`
+    fn out(&self) {
+        self.signature.out();
+        " @ ".out();
+        (self.addr as u64).out();
+        " Length ".out();
+        (self.len as u64).out();
+        " Revision ".out();
+        (self.revision as usize).out();
+        if self.oem_id != [0; 6] {
+            " OEM ".out();
+            self.oem_id.out();
+        }
+    }
+}
+
+struct Tables {
+    tables: [Table; MAX_TABLE_COUNT],
+    count: usize,
+}
+impl Tables {
+    fn as_slice(&self) -> &[Table] {
+        &self.tables[..self.count]
+    }
+}
+
+static TABLES: RwLock<Tables> = RwLock::new(Tables {
+    tables: [Table::null(); MAX_TABLE_COUNT],
+    count: 0,
+});
+
+/// Records the table at `addr`, leaving out one that fails its checksum
+pub fn add(addr: usize) {
+    if addr == 0 {
+        return;
+    }
+    let header = unsafe { &*(addr as *const Header) };
+    let signature = header.signature;
+    let table = if &signature == FACS_SIGNATURE {
+        // Signature and length only, with no checksum
+        Table {
+            signature,
+            len: header.length as usize,
+            addr,
+            ..Table::null()
+        }
+    } else {
+        if let Err(err) = header.init(signature) {
+            warn!("ACPI", err);
+            return;
+        }
+        Table {
+            signature,
+            oem_id: header.oem_id,
+            revision: header.revision,
+            addr,
+            len: header.length as usize,
+        }
+    };
+
+    let mut tables = TABLES.write();
+    if tables.as_slice().iter().any(|table| table.addr == addr) {
+        return;
+    }
+    if tables.count == MAX_TABLE_COUNT {
+        warn!("ACPI", table, " dropped, registry full");
+        return;
+    }
+    let count = tables.count;
+    tables.tables[count] = table;
+    tables.count += 1;
+}
+
+/// Table `instance` of those with `signature`, in the order they were found
+pub fn find(signature: &[u8; 4], instance: usize) -> Option<Table> {
+    TABLES
+        .read()
+        .as_slice()
+        .iter()
+        .filter(|table| &table.signature == signature)
+        .nth(instance)
+        .copied()
+}
+
+pub fn count() -> usize {
+    TABLES.read().count
+}
+
+pub fn get(index: usize) -> Option<Table> {
+    TABLES.read().as_slice().get(index).copied()
+}
+
+/// Prints every table in the style of `acpidump`, for attaching to bug reports
+///
+/// ```text
+/// DSDT @ 0x7FFE0040 Length 0x1F0B Revision 1 OEM BOCHS
+///   0000: 44 53 44 54 0B 1F 00 00 01 A5 42 4F 43 48 53 20  DSDT......BOCHS
+/// ```
+pub fn dump() {
+    for table in TABLES.read().as_slice() {
+        table.out();
+        '\n'.out();
+        let digits = if table.len > 0x10000 { 8 } else { 4 };
+        for (line, bytes) in table.as_slice().chunks(DUMP_WIDTH).enumerate() {
+            let offset = line * DUMP_WIDTH;
+            "  ".out();
+            for shift in (0..digits).rev() {
+                u8::nibble_to_hex_char((offset >> (shift * 4)) as u8 & 0xF).out();
+            }
+            ": ".out();
+            for i in 0..DUMP_WIDTH {
+                match bytes.get(i) {
+                    Some(&byte) => u8::byte_to_hex_str(byte),
+                    None => "  ".out(),
+                }
+                ' '.out();
+            }
+            ' '.out();
+            for &byte in bytes {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                c.out();
+            }
+            '\n'.out();
+        }
+        '\n'.out();
+    }
+}
@@ -4,16 +4,10 @@ use core::ptr::{addr_of, read_unaligned};
 
 use crate::mem::Memory;
 
-use super::{Error, Header, fadt, madt, mcfg};
+use super::{Error, Header, table};
 
 const SIGNATURE: &[u8; 4] = b"XSDT";
 
-static mut ADDR: usize = 0;
-
-pub fn set_config(addr: usize) {
-    unsafe { ADDR = addr }
-}
-
 #[repr(C, packed)]
 struct XSDT {
     header: Header,
@@ -27,13 +21,7 @@ impl XSDT {
         let count = (self.header.length as usize - size_of::<Self>()) / size_of::<u64>();
         let entries = addr_of!(self.entries) as *const u64;
         for i in 0..count {
-            let entry = unsafe { read_unaligned(entries.add(i)) } as usize;
-            match &unsafe { &*(entry as *const Header) }.signature {
-                fadt::SIGNATURE => fadt::set_config(entry),
-                madt::SIGNATURE => madt::set_config(entry),
-                mcfg::SIGNATURE => mcfg::set_config(entry),
-                _ => {}
-            };
+            table::add(unsafe { read_unaligned(entries.add(i)) } as usize);
         }
         Ok(())
     }
@@ -43,6 +31,6 @@ pub fn init(addr: usize) -> Result<(), Error> {
     if addr == 0 {
         return Err(Error::InvalidAddress(*SIGNATURE));
     }
-    unsafe { ADDR = addr };
+    table::add(addr);
     XSDT::get_ref(addr).init()
 }
@@ -1,6 +1,7 @@
 //! Error
 
 pub enum Error {
+    InvalidLINT(u8),
     IOAPIC(super::ioapic::Error),
 }
 impl From<Error> for super::super::Error {
@@ -17,6 +18,10 @@ impl crate::Output for Error {
     fn out(&self) {
         "/APIC".out();
         match self {
+            Error::InvalidLINT(lint) => {
+                " LINT".out();
+                (*lint as usize).out();
+            }
             Error::IOAPIC(e) => e.out(),
         }
     }
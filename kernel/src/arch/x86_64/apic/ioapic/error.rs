@@ -4,6 +4,8 @@ pub enum Error {
     InvalidGSIIndex,
 
     InvalidCount,
+
+    InvalidIRQ(u8),
 }
 impl From<Error> for super::super::Error {
     fn from(err: Error) -> Self {
@@ -24,9 +26,12 @@ impl crate::Output for Error {
     fn out(&self) {
         "/IOAPIC ".out();
         match self {
-            Error::InvalidGSIIndex => "GSI Index",
-            Error::InvalidCount => "Count Overflow",
+            Error::InvalidGSIIndex => "GSI Index".out(),
+            Error::InvalidCount => "Count Overflow".out(),
+            Error::InvalidIRQ(irq) => {
+                "IRQ ".out();
+                (*irq as usize).out();
+            }
         }
-        .out();
     }
 }
@@ -2,15 +2,67 @@
 
 use core::ptr::{read_volatile, write_volatile};
 
-use super::{super::idt::Interrupt, lapic};
+use crate::sync::RwLock;
+
+use super::{Polarity, TriggerMode, lapic};
 
 mod error;
 
 pub use error::Error;
 
 const MAX_IO_APIC_COUNT: usize = 9;
-static mut IOAPICS: [Config; MAX_IO_APIC_COUNT] = [Config::null(); MAX_IO_APIC_COUNT];
-static mut COUNT: usize = 0;
+static IOAPICS: RwLock<IOAPICs> = RwLock::new(IOAPICs {
+    configs: [Config::null(); MAX_IO_APIC_COUNT],
+    count: 0,
+});
+
+/// Legacy IRQs an interrupt source override can redirect
+const ISA_IRQ_COUNT: usize = 16;
+static OVERRIDES: RwLock<[Source; ISA_IRQ_COUNT]> = RwLock::new(Source::identity());
+
+const MAX_NMI_SOURCE_COUNT: usize = 4;
+static NMI_SOURCES: RwLock<[Option<Source>; MAX_NMI_SOURCE_COUNT]> =
+    RwLock::new([None; MAX_NMI_SOURCE_COUNT]);
+
+#[repr(u32)]
+enum DeliveryMode {
+    Fixed,
+    NMI = 0b100,
+}
+
+/// Where an input is wired and how it signals
+#[derive(Clone, Copy, PartialEq)]
+struct Source {
+    gsi: u32,
+    polarity: Polarity,
+    trigger_mode: TriggerMode,
+}
+impl Source {
+    /// ISA IRQs map to the same GSI, active high and edge-triggered, unless the MADT says otherwise
+    const fn identity() -> [Self; ISA_IRQ_COUNT] {
+        let mut overrides = [Self {
+            gsi: 0,
+            polarity: Polarity::High,
+            trigger_mode: TriggerMode::Edge,
+        }; ISA_IRQ_COUNT];
+        let mut irq = 0;
+        while irq < ISA_IRQ_COUNT {
+            overrides[irq].gsi = irq as u32;
+            irq += 1;
+        }
+        overrides
+    }
+}
+
+struct IOAPICs {
+    configs: [Config; MAX_IO_APIC_COUNT],
+    count: usize,
+}
+impl IOAPICs {
+    fn as_slice(&self) -> &[Config] {
+        &self.configs[..self.count]
+    }
+}
 
 #[repr(u8)]
 enum MemoryMappedRegister {
@@ -95,10 +147,33 @@ impl Config {
         Self { addr: 0, base: 0 }
     }
 
-    fn init(&self, index: u32, vector: u32) {
+    fn count(&self) -> u32 {
+        ((self.read(Register::Version as u32) >> 16) & 0xFF) + 1
+    }
+
+    fn mask(&self, index: u32) {
+        let addr = Register::RedirectionTableEntry as u32 + index * 2;
+        self.write(addr, self.read(addr) | 1 << 16);
+    }
+
+    /// Programs and unmasks a redirection entry aimed at this processor
+    fn init(
+        &self,
+        index: u32,
+        vector: u8,
+        delivery_mode: DeliveryMode,
+        polarity: Polarity,
+        trigger_mode: TriggerMode,
+    ) {
         let addr = Register::RedirectionTableEntry as u32 + index * 2;
-        self.write(addr, ((self.read(addr) & !0xFF) | vector) & !(1 << 16));
         self.write(addr + 1, lapic::id());
+        self.write(
+            addr,
+            vector as u32
+                | (delivery_mode as u32) << 8
+                | ((polarity == Polarity::Low) as u32) << 13
+                | ((trigger_mode == TriggerMode::Level) as u32) << 15,
+        );
     }
 
     fn read(&self, index: u32) -> u32 {
@@ -126,42 +201,131 @@ impl Config {
 }
 
 pub fn append(addr: u32, base: u32) -> Result<(), Error> {
-    unsafe {
-        if COUNT == MAX_IO_APIC_COUNT {
-            return Err(Error::InvalidCount);
-        }
-        IOAPICS[COUNT] = Config { addr, base };
-        COUNT += 1;
+    let mut ioapics = IOAPICS.write();
+    if ioapics.count == MAX_IO_APIC_COUNT {
+        return Err(Error::InvalidCount);
     }
+    let count = ioapics.count;
+    ioapics.configs[count] = Config { addr, base };
+    ioapics.count += 1;
     Ok(())
 }
 
-pub fn handle_override(src: u8, dst: u32, polarity: u8, trigger_mode: u8) -> Result<(), Error> {
-    for i in (0..unsafe { COUNT }).rev() {
-        let ioapic = &mut unsafe { IOAPICS }[i];
-        if dst < ioapic.base {
-            continue;
-        }
-        if dst >= ioapic.base + (ioapic.read(Register::Version as u32) >> 16) & 0xFF {
-            return Err(Error::InvalidGSIIndex);
-        }
+/// Records a MADT interrupt source override of ISA `irq`
+pub fn handle_override(irq: u8, gsi: u32, flags: u16) -> Result<(), Error> {
+    let (polarity, trigger_mode) = super::decode_inti(flags);
+    *OVERRIDES
+        .write()
+        .get_mut(irq as usize)
+        .ok_or(Error::InvalidIRQ(irq))? = Source {
+        gsi,
+        polarity,
+        trigger_mode,
+    };
+    Ok(())
+}
+
+/// Records a MADT NMI source, programmed once every IOAPIC is known
+pub fn handle_nmi(gsi: u32, flags: u16) -> Result<(), Error> {
+    let (polarity, trigger_mode) = super::decode_inti(flags);
+    let mut sources = NMI_SOURCES.write();
+    let source = sources
+        .iter_mut()
+        .find(|source| source.is_none())
+        .ok_or(Error::InvalidCount)?;
+    *source = Some(Source {
+        gsi,
+        polarity,
+        trigger_mode,
+    });
+    Ok(())
+}
+
+fn program(
+    gsi: u32,
+    vector: u8,
+    delivery_mode: DeliveryMode,
+    polarity: Polarity,
+    trigger_mode: TriggerMode,
+) -> Result<(), Error> {
+    let ioapics = IOAPICS.read();
+    let ioapic = ioapics
+        .as_slice()
+        .iter()
+        .find(|ioapic| (ioapic.base..ioapic.base + ioapic.count()).contains(&gsi))
+        .ok_or(Error::InvalidGSIIndex)?;
+    ioapic.init(
+        gsi - ioapic.base,
+        vector,
+        delivery_mode,
+        polarity,
+        trigger_mode,
+    );
+    Ok(())
+}
 
-        let index_reg = (ioapic.addr + MemoryMappedRegister::RegisterSelect as u32) as *mut u32;
-        let data_reg = (ioapic.addr + MemoryMappedRegister::Window as u32) as *mut u32;
-        let addr = Register::RedirectionTableEntry as u32 + (dst - ioapic.base) * 2;
-        return Ok(());
+/// Delivers `gsi` to this processor on `vector`
+pub fn route(
+    gsi: u32,
+    vector: u8,
+    polarity: Polarity,
+    trigger_mode: TriggerMode,
+) -> Result<(), Error> {
+    program(gsi, vector, DeliveryMode::Fixed, polarity, trigger_mode)
+}
+
+/// GSI ISA `irq` is wired to, moved by any MADT interrupt source override
+pub fn isa_gsi(irq: u8) -> Result<u32, Error> {
+    OVERRIDES
+        .read()
+        .get(irq as usize)
+        .map(|source| source.gsi)
+        .ok_or(Error::InvalidIRQ(irq))
+}
+
+/// Delivers ISA `irq` on `vector` through whatever GSI the firmware wired it to
+pub fn route_isa(irq: u8, vector: u8) -> Result<(), Error> {
+    let Source {
+        gsi,
+        polarity,
+        trigger_mode,
+    } = *OVERRIDES
+        .read()
+        .get(irq as usize)
+        .ok_or(Error::InvalidIRQ(irq))?;
+    route(gsi, vector, polarity, trigger_mode)
+}
+
+/// Delivers the ACPI SCI on `vector`
+///
+/// The SCI is shareable, level-triggered and active low, unless the MADT overrides the ISA line it
+/// sits on.
+pub fn route_sci(gsi: u32, vector: u8) -> Result<(), Error> {
+    let identity = Source::identity();
+    let overrides = OVERRIDES.read();
+    match overrides.get(gsi as usize) {
+        Some(source) if *source != identity[gsi as usize] => {
+            route(source.gsi, vector, source.polarity, source.trigger_mode)
+        }
+        _ => route(gsi, vector, Polarity::Low, TriggerMode::Level),
     }
-    Err(Error::InvalidGSIIndex)
 }
 
-pub fn init() {
-    for i in 0..unsafe { COUNT } {
-        let ioapic = &mut unsafe { IOAPICS }[i];
-        for j in 0..((ioapic.read(Register::Version as u32) >> 16) & 0xFF) {
-            match ioapic.base + j {
-                1 => ioapic.init(j, Interrupt::Keyboard as u32),
-                _ => {}
-            }
+/// Masks every input until a driver routes it, except NMI sources
+pub fn init() -> Result<(), Error> {
+    for ioapic in IOAPICS.read().as_slice() {
+        for index in 0..ioapic.count() {
+            ioapic.mask(index);
         }
     }
+    for source in NMI_SOURCES.read().iter().flatten() {
+        program(
+            source.gsi,
+            0,
+            DeliveryMode::NMI,
+            source.polarity,
+            source.trigger_mode,
+        )?;
+    }
+    Ok(())
 }
@@ -2,9 +2,16 @@
 
 use core::ptr::{read_volatile, write_volatile};
 
+use crate::sync::{Once, Spinlock};
+
+use super::{super::idt::vector, Error, Polarity};
+
 mod timer;
 
-static mut ADDR: u32 = 0;
+static ADDR: Once<usize> = Once::new();
+
+/// Polarity of LINT0 and LINT1 when the MADT wires them to NMI
+static NMI_LINTS: Spinlock<[Option<Polarity>; 2]> = Spinlock::new([None; 2]);
 
 #[repr(u16)]
 enum Local {
@@ -272,23 +279,52 @@ enum Local {
     TDCR = 0x3E0,
 }
 impl Local {
+    fn addr(self) -> usize {
+        ADDR.get().copied().unwrap_or(0) + self as usize
+    }
+
     fn read(self) -> u32 {
-        unsafe { read_volatile((ADDR + self as u32) as *const u32) }
+        unsafe { read_volatile(self.addr() as *const u32) }
     }
 
     fn write(self, value: u32) {
-        unsafe { write_volatile((ADDR + self as u32) as *mut u32, value) }
+        unsafe { write_volatile(self.addr() as *mut u32, value) }
+    }
+}
+
+/// Records a MADT local APIC NMI entry for this processor
+pub fn handle_nmi(lint: u8, polarity: Polarity) -> Result<(), Error> {
+    *NMI_LINTS
+        .lock()
+        .get_mut(lint as usize)
+        .ok_or(Error::InvalidLINT(lint))? = Some(polarity);
+    Ok(())
+}
+
+pub fn init(addr: usize) -> Result<(), crate::Error> {
+    ADDR.call_once(|| addr);
+    Local::SIVR.write((Local::SIVR.read() & !0xFF) | 1 << 8 | vector::SPURIOUS as u32);
+
+    // NMI delivery is always edge-triggered, so only the polarity carries over
+    for (lint, polarity) in [Local::LINT0, Local::LINT1]
+        .into_iter()
+        .zip(*NMI_LINTS.lock())
+    {
+        if let Some(polarity) = polarity {
+            lint.write(0b100 << 8 | ((polarity == Polarity::Low) as u32) << 13);
+        }
     }
+    timer::init()
+}
+
+/// Raises corrected machine-check interrupts on `vector`
+pub fn set_cmci(vector: u8) {
+    Local::CMCIR.write(vector as u32);
 }
 
-pub fn init(addr: u32) {
-    unsafe { ADDR = addr };
-    let mut sivr = Local::SIVR.read();
-    if (sivr >> 8) & 1 == 0 {
-        sivr |= 1 << 8;
-        Local::SIVR.write(sivr);
-    };
-    timer::init();
+/// Delivers performance counter overflows as NMI, which also unmasks the entry after a PMI
+pub fn set_performance_counter_nmi() {
+    Local::PMCR.write(0b100 << 8);
 }
 
 pub fn id() -> u32 {
@@ -1,7 +1,69 @@
 //! Timer
 
-use super::{super::super::idt::Interrupt, Local};
+use core::hint::spin_loop;
 
-pub fn init() {
-    Local::Timer.write(((Local::Timer.read() & !0xFF) | Interrupt::Timer as u32) & !(1 << 16));
+use crate::{
+    io::{port, text::Cursor},
+    task::scheduler,
+    time,
+};
+
+use super::{super::super::idt::vector, Local};
+
+/// Input frequency of the Programmable Interval Timer in Hz
+const PIT_FREQUENCY: u64 = 1_193_182;
+
+/// Length of the calibration window in milliseconds
+const CALIBRATION_MS: u64 = 10;
+
+/// Divide by 16
+const DIVIDE_VALUE: u32 = 0b0011;
+
+/// Counts elapsed during `CALIBRATION_MS` measured by PIT channel 2 in one-shot mode
+fn calibrate() -> u32 {
+    let gate = port::in_byte(port::SYSTEM_CONTROL_B) & !0b10;
+    port::out_byte(port::SYSTEM_CONTROL_B, gate & !0b1);
+
+    // Channel 2, lobyte/hibyte, mode 1, binary
+    port::out_byte(port::PIT_COMMAND, 0b1011_0010);
+    let count = (PIT_FREQUENCY * CALIBRATION_MS / 1000) as u16;
+    port::out_byte(port::PIT_CHANNEL_2, count as u8);
+    port::out_byte(port::PIT_CHANNEL_2, (count >> 8) as u8);
+
+    Local::TDCR.write(DIVIDE_VALUE);
+    port::out_byte(port::SYSTEM_CONTROL_B, gate | 0b1);
+    Local::TICR.write(u32::MAX);
+    while port::in_byte(port::SYSTEM_CONTROL_B) & (1 << 5) == 0 {
+        spin_loop();
+    }
+    let elapsed = u32::MAX - Local::TCCR.read();
+    Local::TICR.write(0);
+    port::out_byte(port::SYSTEM_CONTROL_B, gate);
+    elapsed
+}
+
+fn handle(_: usize) {
+    time::tick();
+    match time::ticks() % time::HZ {
+        0 => Cursor::lock().read_cache(),
+        tick if tick == time::HZ / 2 => Cursor::lock().show(),
+        _ => {}
+    }
+    scheduler::tick();
+}
+
+pub fn init() -> Result<(), crate::Error> {
+    let vector = vector::allocate(1)?;
+    vector::register(vector, handle, 0)?;
+
+    Local::Timer.write(Local::Timer.read() | (1 << 16));
+    let count = calibrate() as u64 * 1000 / (CALIBRATION_MS * time::HZ);
+
+    Local::TDCR.write(DIVIDE_VALUE);
+    Local::Timer.write(
+        ((Local::Timer.read() & !(0xFF | (0b11 << 17))) | vector as u32 | (0b01 << 17))
+            & !(1 << 16),
+    );
+    Local::TICR.write(count.max(1) as u32);
+    Ok(())
 }
@@ -10,6 +10,36 @@ pub mod lapic;
 
 pub use error::Error;
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum Polarity {
+    High,
+    Low,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TriggerMode {
+    Edge,
+    Level,
+}
+
+/// Decodes MPS INTI flags, where bus default means ISA: active high and edge-triggered
+/// - Bits 0 ..= 1: Polarity
+/// - Bits 2 ..= 3: Trigger Mode
+pub fn decode_inti(flags: u16) -> (Polarity, TriggerMode) {
+    (
+        if flags & 0b11 == 0b11 {
+            Polarity::Low
+        } else {
+            Polarity::High
+        },
+        if (flags >> 2) & 0b11 == 0b11 {
+            TriggerMode::Level
+        } else {
+            TriggerMode::Edge
+        },
+    )
+}
+
 pub fn init() -> Result<(), crate::Error> {
     let ia32_apic_base = msr::read(msr::IA32_APIC_BASE);
     if (ia32_apic_base >> 11) & 1 == 0 {
@@ -18,7 +48,7 @@ pub fn init() -> Result<(), crate::Error> {
 
     let addr = madt::init()?;
 
-    lapic::init(addr);
-    ioapic::init();
+    lapic::init(addr)?;
+    ioapic::init()?;
     Ok(())
 }
@@ -0,0 +1,219 @@
+//! CPUID
+
+use core::arch::asm;
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Leaf {
+    /// - EAX: Maximum Input Value for Basic CPUID Information
+    /// - EBX: Genu
+    /// - ECX: ntel
+    /// - EDX: ineI
+    BasicCPUIDInformation0 = 0x0,
+
+    /// - EAX: Version Information
+    ///   - Bits 0 ..= 3: Stepping ID
+    ///   - Bits 4 ..= 7: Model
+    ///   - Bits 8 ..= 11: Family ID
+    ///   - Bits 12 ..= 13: Processor Type
+    ///   - Bits 16 ..= 19: Extended Model ID
+    ///   - Bits 20 ..= 27: Extended Family ID
+    /// - EBX:
+    ///   - Bits 0 ..= 7: Brand Index
+    ///   - Bits 8 ..= 15: CLFLUSH instruction cache line size
+    ///   - Bits 16 ..= 23: Maximum number of addressable IDs for logical processors
+    ///   - Bits 24 ..= 31: Initial APIC ID
+    /// - ECX:
+    ///   - Bit 0: SSE3
+    ///   - Bit 1: PCLMULQDQ
+    ///   - Bit 3: MONITOR
+    ///   - Bit 5: VMX
+    ///   - Bit 9: SSSE3
+    ///   - Bit 12: FMA
+    ///   - Bit 13: CMPXCHG16B
+    ///   - Bit 17: PCID
+    ///   - Bit 19: SSE4.1
+    ///   - Bit 20: SSE4.2
+    ///   - Bit 21: x2APIC
+    ///   - Bit 22: MOVBE
+    ///   - Bit 23: POPCNT
+    ///   - Bit 24: TSC-Deadline
+    ///   - Bit 25: AESNI
+    ///   - Bit 26: XSAVE
+    ///   - Bit 27: OSXSAVE
+    ///   - Bit 28: AVX
+    ///   - Bit 29: F16C
+    ///   - Bit 30: RDRAND
+    ///   - Bit 31: Hypervisor
+    /// - EDX:
+    ///   - Bit 0: FPU
+    ///   - Bit 4: TSC
+    ///   - Bit 5: MSR
+    ///   - Bit 6: PAE
+    ///   - Bit 7: MCE
+    ///   - Bit 8: CX8
+    ///   - Bit 9: APIC
+    ///   - Bit 11: SEP
+    ///   - Bit 12: MTRR
+    ///   - Bit 13: PGE
+    ///   - Bit 14: MCA
+    ///   - Bit 15: CMOV
+    ///   - Bit 16: PAT
+    ///   - Bit 19: CLFSH
+    ///   - Bit 23: MMX
+    ///   - Bit 24: FXSR
+    ///   - Bit 25: SSE
+    ///   - Bit 26: SSE2
+    ///   - Bit 28: HTT
+    BasicCPUIDInformation1 = 0x1,
+
+    /// Sub-leaf per cache until EAX.Type is 0
+    /// - EAX:
+    ///   - Bits 0 ..= 4: Cache Type
+    ///     - 0: Null
+    ///     - 1: Data
+    ///     - 2: Instruction
+    ///     - 3: Unified
+    ///   - Bits 5 ..= 7: Cache Level
+    ///   - Bits 14 ..= 25: Maximum number of logical processors sharing this cache - 1
+    /// - EBX:
+    ///   - Bits 0 ..= 11: System Coherency Line Size - 1
+    ///   - Bits 12 ..= 21: Physical Line partitions - 1
+    ///   - Bits 22 ..= 31: Ways of associativity - 1
+    /// - ECX: Number of Sets - 1
+    DeterministicCacheParameters = 0x4,
+
+    /// Sub-leaf 0
+    /// - EAX: Maximum Sub-leaf
+    /// - EBX:
+    ///   - Bit 0: FSGSBASE
+    ///   - Bit 3: BMI1
+    ///   - Bit 5: AVX2
+    ///   - Bit 7: SMEP
+    ///   - Bit 8: BMI2
+    ///   - Bit 9: Enhanced REP MOVSB/STOSB
+    ///   - Bit 10: INVPCID
+    ///   - Bit 16: AVX512F
+    ///   - Bit 17: AVX512DQ
+    ///   - Bit 18: RDSEED
+    ///   - Bit 19: ADX
+    ///   - Bit 20: SMAP
+    ///   - Bit 28: AVX512CD
+    ///   - Bit 30: AVX512BW
+    ///   - Bit 31: AVX512VL
+    /// - ECX:
+    ///   - Bit 2: UMIP
+    ///   - Bit 3: PKU
+    ///   - Bit 16: LA57
+    ///   - Bit 22: RDPID
+    /// - EDX:
+    ///   - Bit 4: FSRM
+    ///   - Bit 26: IBRS & IBPB
+    StructuredExtendedFeatureFlags = 0x7,
+
+    /// - EAX:
+    ///   - Bits 0 ..= 7: Version ID of architectural performance monitoring
+    ///   - Bits 8 ..= 15: Number of general-purpose counters per logical processor
+    ///   - Bits 16 ..= 23: Bit width of general-purpose counters
+    ///   - Bits 24 ..= 31: Length of the EBX bit vector
+    /// - EBX: Architectural events not available when set
+    ///   - Bit 0: Core cycles
+    ///   - Bit 1: Instructions retired
+    ///   - Bit 2: Reference cycles
+    ///   - Bit 3: LLC references
+    ///   - Bit 4: LLC misses
+    ///   - Bit 5: Branch instructions retired
+    ///   - Bit 6: Branch mispredicts retired
+    /// - EDX:
+    ///   - Bits 0 ..= 4: Number of fixed-function counters
+    ///   - Bits 5 ..= 12: Bit width of fixed-function counters
+    ArchitecturalPerformanceMonitoring = 0xA,
+
+    /// Sub-leaf per level until ECX.Level Type is 0
+    /// - EAX:
+    ///   - Bits 0 ..= 4: Shift of the x2APIC ID to get the next level ID
+    /// - EBX:
+    ///   - Bits 0 ..= 15: Number of logical processors at this level
+    /// - ECX:
+    ///   - Bits 0 ..= 7: Level Number
+    ///   - Bits 8 ..= 15: Level Type
+    ///     - 0: Invalid
+    ///     - 1: SMT
+    ///     - 2: Core
+    ///     - 3: Module
+    ///     - 4: Tile
+    ///     - 5: Die
+    /// - EDX: x2APIC ID
+    ExtendedTopologyEnumeration = 0xB,
+
+    /// - Sub-leaf 0
+    ///   - EAX: XCR0 bits 0 ..= 31 supported
+    ///   - EBX: Size of the XSAVE area for the features enabled in XCR0
+    ///   - ECX: Size of the XSAVE area for every supported feature
+    ///   - EDX: XCR0 bits 32 ..= 63 supported
+    /// - Sub-leaf 1
+    ///   - EAX:
+    ///     - Bit 0: XSAVEOPT
+    ///     - Bit 1: XSAVEC
+    ///     - Bit 2: XGETBV with ECX = 1
+    ///     - Bit 3: XSAVES & XRSTORS
+    /// - Sub-leaf n >= 2
+    ///   - EAX: Size of state component n
+    ///   - EBX: Offset of state component n in the standard format
+    ProcessorExtendedStateEnumeration = 0xD,
+
+    /// Same as `ExtendedTopologyEnumeration` with more level types
+    V2ExtendedTopologyEnumeration = 0x1F,
+
+    /// - EAX: Maximum Input Value for Extended Function CPUID Information
+    ExtendedFunctionCPUIDInformation0 = 0x8000_0000,
+
+    /// - ECX:
+    ///   - Bit 0: LAHF/SAHF in 64-bit mode
+    ///   - Bit 5: LZCNT
+    ///   - Bit 8: PREFETCHW
+    /// - EDX:
+    ///   - Bit 11: SYSCALL/SYSRET
+    ///   - Bit 20: Execute Disable Bit
+    ///   - Bit 26: 1-GByte pages
+    ///   - Bit 27: RDTSCP
+    ///   - Bit 29: Intel 64 Architecture
+    ExtendedFunctionCPUIDInformation1 = 0x8000_0001,
+
+    /// - EAX, EBX, ECX, EDX: Processor Brand String 0 ..= 15
+    ExtendedFunctionCPUIDInformation2 = 0x8000_0002,
+
+    /// - EAX, EBX, ECX, EDX: Processor Brand String 16 ..= 31
+    ExtendedFunctionCPUIDInformation3 = 0x8000_0003,
+
+    /// - EAX, EBX, ECX, EDX: Processor Brand String 32 ..= 47
+    ExtendedFunctionCPUIDInformation4 = 0x8000_0004,
+
+    /// - EDX:
+    ///   - Bit 8: Invariant TSC
+    ExtendedFunctionCPUIDInformation7 = 0x8000_0007,
+
+    /// AMD's equivalent of `DeterministicCacheParameters`
+    CacheTopologyInformation = 0x8000_001D,
+}
+
+pub fn cpuid(leaf: Leaf, subleaf: u32) -> (u32, u32, u32, u32) {
+    let eax: u32;
+    let ebx: u32;
+    let ecx: u32;
+    let edx: u32;
+    unsafe {
+        asm!(
+            // RBX is reserved by LLVM
+            "mov {0:r}, rbx",
+            "cpuid",
+            "xchg {0:r}, rbx",
+            out(reg) ebx,
+            inlateout("eax") leaf as u32 => eax,
+            inlateout("ecx") subleaf => ecx,
+            lateout("edx") edx,
+            options(nostack, preserves_flags),
+        )
+    };
+    (eax, ebx, ecx, edx)
+}
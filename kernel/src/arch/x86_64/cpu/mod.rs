@@ -0,0 +1,605 @@
+//! CPU
+
+use crate::{debug, info, io::text::Output, sync::Once};
+
+mod cpuid;
+
+use cpuid::{Leaf, cpuid};
+
+static FEATURES: Once<Features> = Once::new();
+
+const MAX_CACHE_COUNT: usize = 8;
+const MAX_TOPOLOGY_LEVEL_COUNT: usize = 8;
+
+/// Feature flag registers kept in `Features::registers`
+#[derive(Clone, Copy)]
+enum Register {
+    Leaf1ECX,
+    Leaf1EDX,
+    Leaf7EBX,
+    Leaf7ECX,
+    Leaf7EDX,
+    /// Sub-leaf 1
+    LeafDEAX,
+    Extended1ECX,
+    Extended1EDX,
+    Extended7EDX,
+}
+const REGISTER_COUNT: usize = Register::Extended7EDX as usize + 1;
+
+#[derive(Clone, Copy)]
+pub enum Feature {
+    FPU,
+    TSC,
+    MSR,
+    PAE,
+    MCE,
+    APIC,
+    MTRR,
+    PGE,
+    MCA,
+    PAT,
+    CLFSH,
+    MMX,
+    FXSR,
+    SSE,
+    SSE2,
+    HTT,
+
+    SSE3,
+    PCLMULQDQ,
+    MONITOR,
+    VMX,
+    SSSE3,
+    FMA,
+    CMPXCHG16B,
+    PDCM,
+    PCID,
+    SSE4_1,
+    SSE4_2,
+    X2APIC,
+    MOVBE,
+    POPCNT,
+    TSCDeadline,
+    AESNI,
+    XSAVE,
+    OSXSAVE,
+    AVX,
+    F16C,
+    RDRAND,
+    Hypervisor,
+
+    FSGSBASE,
+    BMI1,
+    AVX2,
+    SMEP,
+    BMI2,
+    ERMS,
+    INVPCID,
+    AVX512F,
+    AVX512DQ,
+    RDSEED,
+    ADX,
+    SMAP,
+    AVX512CD,
+    AVX512BW,
+    AVX512VL,
+    UMIP,
+    PKU,
+    LA57,
+    RDPID,
+    FSRM,
+
+    XSAVEOPT,
+    XSAVEC,
+    XGETBV1,
+    XSAVES,
+
+    LAHFSAHF,
+    LZCNT,
+    PREFETCHW,
+    SYSCALL,
+    NX,
+    Page1GB,
+    RDTSCP,
+    LongMode,
+
+    InvariantTSC,
+}
+impl Feature {
+    fn location(self) -> (Register, u32) {
+        use Register::*;
+
+        match self {
+            Feature::FPU => (Leaf1EDX, 0),
+            Feature::TSC => (Leaf1EDX, 4),
+            Feature::MSR => (Leaf1EDX, 5),
+            Feature::PAE => (Leaf1EDX, 6),
+            Feature::MCE => (Leaf1EDX, 7),
+            Feature::APIC => (Leaf1EDX, 9),
+            Feature::MTRR => (Leaf1EDX, 12),
+            Feature::PGE => (Leaf1EDX, 13),
+            Feature::MCA => (Leaf1EDX, 14),
+            Feature::PAT => (Leaf1EDX, 16),
+            Feature::CLFSH => (Leaf1EDX, 19),
+            Feature::MMX => (Leaf1EDX, 23),
+            Feature::FXSR => (Leaf1EDX, 24),
+            Feature::SSE => (Leaf1EDX, 25),
+            Feature::SSE2 => (Leaf1EDX, 26),
+            Feature::HTT => (Leaf1EDX, 28),
+
+            Feature::SSE3 => (Leaf1ECX, 0),
+            Feature::PCLMULQDQ => (Leaf1ECX, 1),
+            Feature::MONITOR => (Leaf1ECX, 3),
+            Feature::VMX => (Leaf1ECX, 5),
+            Feature::SSSE3 => (Leaf1ECX, 9),
+            Feature::FMA => (Leaf1ECX, 12),
+            Feature::CMPXCHG16B => (Leaf1ECX, 13),
+            Feature::PDCM => (Leaf1ECX, 15),
+            Feature::PCID => (Leaf1ECX, 17),
+            Feature::SSE4_1 => (Leaf1ECX, 19),
+            Feature::SSE4_2 => (Leaf1ECX, 20),
+            Feature::X2APIC => (Leaf1ECX, 21),
+            Feature::MOVBE => (Leaf1ECX, 22),
+            Feature::POPCNT => (Leaf1ECX, 23),
+            Feature::TSCDeadline => (Leaf1ECX, 24),
+            Feature::AESNI => (Leaf1ECX, 25),
+            Feature::XSAVE => (Leaf1ECX, 26),
+            Feature::OSXSAVE => (Leaf1ECX, 27),
+            Feature::AVX => (Leaf1ECX, 28),
+            Feature::F16C => (Leaf1ECX, 29),
+            Feature::RDRAND => (Leaf1ECX, 30),
+            Feature::Hypervisor => (Leaf1ECX, 31),
+
+            Feature::FSGSBASE => (Leaf7EBX, 0),
+            Feature::BMI1 => (Leaf7EBX, 3),
+            Feature::AVX2 => (Leaf7EBX, 5),
+            Feature::SMEP => (Leaf7EBX, 7),
+            Feature::BMI2 => (Leaf7EBX, 8),
+            Feature::ERMS => (Leaf7EBX, 9),
+            Feature::INVPCID => (Leaf7EBX, 10),
+            Feature::AVX512F => (Leaf7EBX, 16),
+            Feature::AVX512DQ => (Leaf7EBX, 17),
+            Feature::RDSEED => (Leaf7EBX, 18),
+            Feature::ADX => (Leaf7EBX, 19),
+            Feature::SMAP => (Leaf7EBX, 20),
+            Feature::AVX512CD => (Leaf7EBX, 28),
+            Feature::AVX512BW => (Leaf7EBX, 30),
+            Feature::AVX512VL => (Leaf7EBX, 31),
+            Feature::UMIP => (Leaf7ECX, 2),
+            Feature::PKU => (Leaf7ECX, 3),
+            Feature::LA57 => (Leaf7ECX, 16),
+            Feature::RDPID => (Leaf7ECX, 22),
+            Feature::FSRM => (Leaf7EDX, 4),
+
+            Feature::XSAVEOPT => (LeafDEAX, 0),
+            Feature::XSAVEC => (LeafDEAX, 1),
+            Feature::XGETBV1 => (LeafDEAX, 2),
+            Feature::XSAVES => (LeafDEAX, 3),
+
+            Feature::LAHFSAHF => (Extended1ECX, 0),
+            Feature::LZCNT => (Extended1ECX, 5),
+            Feature::PREFETCHW => (Extended1ECX, 8),
+            Feature::SYSCALL => (Extended1EDX, 11),
+            Feature::NX => (Extended1EDX, 20),
+            Feature::Page1GB => (Extended1EDX, 26),
+            Feature::RDTSCP => (Extended1EDX, 27),
+            Feature::LongMode => (Extended1EDX, 29),
+
+            Feature::InvariantTSC => (Extended7EDX, 8),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CacheType {
+    Data,
+    Instruction,
+    Unified,
+}
+
+#[derive(Clone, Copy)]
+pub struct Cache {
+    pub level: u8,
+    pub type_: CacheType,
+
+    /// In bytes
+    pub size: usize,
+    pub line_size: usize,
+    pub ways: usize,
+    pub sets: usize,
+
+    /// Logical processors sharing the cache
+    pub shared_by: usize,
+}
+impl Output for Cache {
+    /// `L1 Data 48 KiB, 12-way, 64-byte lines, shared by 2`
+    fn out(&self) {
+        'L'.out();
+        (self.level as usize).out();
+        match self.type_ {
+            CacheType::Data => " Data ",
+            CacheType::Instruction => " Instruction ",
+            CacheType::Unified => " Unified ",
+        }
+        .out();
+        (self.size >> 10).out();
+        " KiB, ".out();
+        self.ways.out();
+        "-way, ".out();
+        self.line_size.out();
+        "-byte lines, shared by ".out();
+        self.shared_by.out();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TopologyLevelType {
+    SMT,
+    Core,
+    Module,
+    Tile,
+    Die,
+    Unknown(u8),
+}
+
+#[derive(Clone, Copy)]
+pub struct TopologyLevel {
+    pub type_: TopologyLevelType,
+
+    /// Right shift of the x2APIC ID giving the ID at the next level
+    pub shift: u8,
+
+    /// Logical processors at this level
+    pub count: u16,
+}
+impl Output for TopologyLevel {
+    /// `Core: 8 logical processors, x2APIC ID shift 4`
+    fn out(&self) {
+        match self.type_ {
+            TopologyLevelType::SMT => "SMT",
+            TopologyLevelType::Core => "Core",
+            TopologyLevelType::Module => "Module",
+            TopologyLevelType::Tile => "Tile",
+            TopologyLevelType::Die => "Die",
+            TopologyLevelType::Unknown(_) => "Unknown",
+        }
+        .out();
+        ": ".out();
+        (self.count as usize).out();
+        " logical processors, x2APIC ID shift ".out();
+        (self.shift as usize).out();
+    }
+}
+
+/// Architectural performance monitoring
+#[derive(Clone, Copy)]
+pub struct PerformanceMonitoring {
+    pub version: u8,
+
+    pub counter_count: usize,
+    pub counter_width: u8,
+
+    pub fixed_counter_count: usize,
+    pub fixed_counter_width: u8,
+
+    /// Architectural events missing when their bit is set
+    pub unavailable: u32,
+}
+impl Output for PerformanceMonitoring {
+    /// `PMU version 2, 4 48-bit counters, 3 fixed 48-bit counters`
+    fn out(&self) {
+        "PMU version ".out();
+        (self.version as usize).out();
+        ", ".out();
+        self.counter_count.out();
+        ' '.out();
+        (self.counter_width as usize).out();
+        "-bit counters, ".out();
+        self.fixed_counter_count.out();
+        " fixed ".out();
+        (self.fixed_counter_width as usize).out();
+        "-bit counters".out();
+    }
+}
+
+/// Snapshot of CPUID taken once at boot
+pub struct Features {
+    vendor: [u8; 12],
+    brand: [u8; 48],
+
+    family: u32,
+    model: u32,
+    stepping: u32,
+
+    max_leaf: u32,
+    max_extended_leaf: u32,
+
+    registers: [u32; REGISTER_COUNT],
+
+    /// XCR0 bits the processor supports
+    xcr0_supported: u64,
+
+    /// Size of the XSAVE area covering every supported state component
+    xsave_size: usize,
+
+    caches: [Option<Cache>; MAX_CACHE_COUNT],
+
+    /// From the lowest level up
+    topology: [Option<TopologyLevel>; MAX_TOPOLOGY_LEVEL_COUNT],
+
+    performance_monitoring: Option<PerformanceMonitoring>,
+
+    apic_id: u32,
+}
+impl Features {
+    fn detect() -> Self {
+        let mut features = Self {
+            vendor: [0; 12],
+            brand: [0; 48],
+            family: 0,
+            model: 0,
+            stepping: 0,
+            max_leaf: 0,
+            max_extended_leaf: 0,
+            registers: [0; REGISTER_COUNT],
+            xcr0_supported: 0,
+            xsave_size: 0,
+            caches: [None; MAX_CACHE_COUNT],
+            topology: [None; MAX_TOPOLOGY_LEVEL_COUNT],
+            performance_monitoring: None,
+            apic_id: 0,
+        };
+
+        let (eax, ebx, ecx, edx) = cpuid(Leaf::BasicCPUIDInformation0, 0);
+        features.max_leaf = eax;
+        features.vendor[0..4].copy_from_slice(&ebx.to_le_bytes());
+        features.vendor[4..8].copy_from_slice(&edx.to_le_bytes());
+        features.vendor[8..12].copy_from_slice(&ecx.to_le_bytes());
+
+        let (eax, ebx, ecx, edx) = cpuid(Leaf::BasicCPUIDInformation1, 0);
+        let family = (eax >> 8) & 0xF;
+        let model = (eax >> 4) & 0xF;
+        features.stepping = eax & 0xF;
+        features.family = match family {
+            0xF => family + ((eax >> 20) & 0xFF),
+            _ => family,
+        };
+        features.model = match family {
+            0x6 | 0xF => model | ((eax >> 16) & 0xF) << 4,
+            _ => model,
+        };
+        features.apic_id = ebx >> 24;
+        features.registers[Register::Leaf1ECX as usize] = ecx;
+        features.registers[Register::Leaf1EDX as usize] = edx;
+
+        if features.max_leaf >= Leaf::StructuredExtendedFeatureFlags as u32 {
+            let (_, ebx, ecx, edx) = cpuid(Leaf::StructuredExtendedFeatureFlags, 0);
+            features.registers[Register::Leaf7EBX as usize] = ebx;
+            features.registers[Register::Leaf7ECX as usize] = ecx;
+            features.registers[Register::Leaf7EDX as usize] = edx;
+        }
+
+        if features.max_leaf >= Leaf::ArchitecturalPerformanceMonitoring as u32 {
+            let (eax, ebx, _, edx) = cpuid(Leaf::ArchitecturalPerformanceMonitoring, 0);
+            let version = eax as u8;
+            let length = (eax >> 24) & 0xFF;
+            features.performance_monitoring = (version != 0).then_some(PerformanceMonitoring {
+                version,
+                counter_count: ((eax >> 8) & 0xFF) as usize,
+                counter_width: (eax >> 16) as u8,
+                // Fixed counters arrived with version 2
+                fixed_counter_count: if version >= 2 {
+                    (edx & 0x1F) as usize
+                } else {
+                    0
+                },
+                fixed_counter_width: ((edx >> 5) & 0xFF) as u8,
+                // Events past the vector length are missing too
+                unavailable: ebx | u32::MAX.checked_shl(length).unwrap_or(0),
+            });
+        }
+
+        if features.max_leaf >= Leaf::ProcessorExtendedStateEnumeration as u32
+            && features.has(Feature::XSAVE)
+        {
+            let (eax, _, ecx, edx) = cpuid(Leaf::ProcessorExtendedStateEnumeration, 0);
+            features.xcr0_supported = (edx as u64) << 32 | eax as u64;
+            features.xsave_size = ecx as usize;
+            let (eax, _, _, _) = cpuid(Leaf::ProcessorExtendedStateEnumeration, 1);
+            features.registers[Register::LeafDEAX as usize] = eax;
+        }
+
+        features.max_extended_leaf = cpuid(Leaf::ExtendedFunctionCPUIDInformation0, 0).0;
+        if features.max_extended_leaf >= Leaf::ExtendedFunctionCPUIDInformation1 as u32 {
+            let (_, _, ecx, edx) = cpuid(Leaf::ExtendedFunctionCPUIDInformation1, 0);
+            features.registers[Register::Extended1ECX as usize] = ecx;
+            features.registers[Register::Extended1EDX as usize] = edx;
+        }
+        if features.max_extended_leaf >= Leaf::ExtendedFunctionCPUIDInformation4 as u32 {
+            for (i, leaf) in [
+                Leaf::ExtendedFunctionCPUIDInformation2,
+                Leaf::ExtendedFunctionCPUIDInformation3,
+                Leaf::ExtendedFunctionCPUIDInformation4,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let (eax, ebx, ecx, edx) = cpuid(leaf, 0);
+                for (j, register) in [eax, ebx, ecx, edx].into_iter().enumerate() {
+                    let offset = i * 16 + j * 4;
+                    features.brand[offset..offset + 4].copy_from_slice(&register.to_le_bytes());
+                }
+            }
+        }
+        if features.max_extended_leaf >= Leaf::ExtendedFunctionCPUIDInformation7 as u32 {
+            features.registers[Register::Extended7EDX as usize] =
+                cpuid(Leaf::ExtendedFunctionCPUIDInformation7, 0).3;
+        }
+
+        features.detect_caches();
+        features.detect_topology();
+        features
+    }
+
+    fn detect_caches(&mut self) {
+        let leaf = if self.max_leaf >= Leaf::DeterministicCacheParameters as u32
+            && self.vendor == *b"GenuineIntel"
+        {
+            Leaf::DeterministicCacheParameters
+        } else if self.max_extended_leaf >= Leaf::CacheTopologyInformation as u32 {
+            Leaf::CacheTopologyInformation
+        } else {
+            return;
+        };
+        for i in 0..MAX_CACHE_COUNT {
+            let (eax, ebx, ecx, _) = cpuid(leaf, i as u32);
+            let type_ = match eax & 0x1F {
+                1 => CacheType::Data,
+                2 => CacheType::Instruction,
+                3 => CacheType::Unified,
+                _ => break,
+            };
+            let line_size = (ebx & 0xFFF) as usize + 1;
+            let partitions = ((ebx >> 12) & 0x3FF) as usize + 1;
+            let ways = (ebx >> 22) as usize + 1;
+            let sets = ecx as usize + 1;
+            self.caches[i] = Some(Cache {
+                level: ((eax >> 5) & 0b111) as u8,
+                type_,
+                size: ways * partitions * line_size * sets,
+                line_size,
+                ways,
+                sets,
+                shared_by: ((eax >> 14) & 0xFFF) as usize + 1,
+            });
+        }
+    }
+
+    fn detect_topology(&mut self) {
+        let leaf = if self.max_leaf >= Leaf::V2ExtendedTopologyEnumeration as u32
+            && cpuid(Leaf::V2ExtendedTopologyEnumeration, 0).1 != 0
+        {
+            Leaf::V2ExtendedTopologyEnumeration
+        } else if self.max_leaf >= Leaf::ExtendedTopologyEnumeration as u32
+            && cpuid(Leaf::ExtendedTopologyEnumeration, 0).1 != 0
+        {
+            Leaf::ExtendedTopologyEnumeration
+        } else {
+            return;
+        };
+        for i in 0..MAX_TOPOLOGY_LEVEL_COUNT {
+            let (eax, ebx, ecx, edx) = cpuid(leaf, i as u32);
+            let type_ = match (ecx >> 8) as u8 {
+                0 => break,
+                1 => TopologyLevelType::SMT,
+                2 => TopologyLevelType::Core,
+                3 => TopologyLevelType::Module,
+                4 => TopologyLevelType::Tile,
+                5 => TopologyLevelType::Die,
+                type_ => TopologyLevelType::Unknown(type_),
+            };
+            self.topology[i] = Some(TopologyLevel {
+                type_,
+                shift: (eax & 0x1F) as u8,
+                count: ebx as u16,
+            });
+            self.apic_id = edx;
+        }
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        let (register, bit) = feature.location();
+        self.registers[register as usize] & (1 << bit) != 0
+    }
+
+    pub fn vendor(&self) -> &str {
+        core::str::from_utf8(&self.vendor).unwrap_or("")
+    }
+
+    pub fn brand(&self) -> &str {
+        let len = self
+            .brand
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.brand.len());
+        core::str::from_utf8(&self.brand[..len])
+            .unwrap_or("")
+            .trim()
+    }
+
+    pub fn family(&self) -> u32 {
+        self.family
+    }
+
+    pub fn model(&self) -> u32 {
+        self.model
+    }
+
+    pub fn stepping(&self) -> u32 {
+        self.stepping
+    }
+
+    pub fn max_leaf(&self) -> u32 {
+        self.max_leaf
+    }
+
+    pub fn xcr0_supported(&self) -> u64 {
+        self.xcr0_supported
+    }
+
+    pub fn xsave_size(&self) -> usize {
+        self.xsave_size
+    }
+
+    pub fn caches(&self) -> impl Iterator<Item = &Cache> {
+        self.caches.iter().flatten()
+    }
+
+    pub fn topology(&self) -> impl Iterator<Item = &TopologyLevel> {
+        self.topology.iter().flatten()
+    }
+
+    /// Architectural PMU from leaf 0xA, `None` if the processor has none
+    pub fn performance_monitoring(&self) -> Option<&PerformanceMonitoring> {
+        self.performance_monitoring.as_ref()
+    }
+
+    /// x2APIC ID when topology leaves exist, otherwise the initial APIC ID
+    pub fn apic_id(&self) -> u32 {
+        self.apic_id
+    }
+}
+
+pub fn init() {
+    let features = FEATURES.call_once(Features::detect);
+    info!(
+        "CPU",
+        features.brand(),
+        ", family ",
+        features.family() as usize,
+        " model ",
+        features.model() as usize,
+        " stepping ",
+        features.stepping() as usize,
+        ", max leaf ",
+        features.max_leaf() as u64,
+    );
+    for cache in features.caches() {
+        debug!("CPU", *cache);
+    }
+    for level in features.topology() {
+        debug!("CPU", *level);
+    }
+    if let Some(pm) = features.performance_monitoring() {
+        debug!("CPU", *pm);
+    }
+}
+
+pub fn features() -> &'static Features {
+    FEATURES.get().expect("CPU features undetected")
+}
+
+pub fn has(feature: Feature) -> bool {
+    features().has(feature)
+}
@@ -0,0 +1,125 @@
+//! Control Registers
+
+use core::arch::asm;
+
+/// - Bit 0: PE for Protection Enable
+/// - Bit 1: MP for Monitor Coprocessor
+/// - Bit 2: EM for Emulation
+/// - Bit 3: TS for Task Switched
+/// - Bit 4: ET for Extension Type
+/// - Bit 5: NE for Numeric Error
+/// - Bit 16: WP for Write Protect
+/// - Bit 18: AM for Alignment Mask
+/// - Bit 29: NW for Not Write-through
+/// - Bit 30: CD for Cache Disable
+/// - Bit 31: PG for Paging
+#[inline(always)]
+pub fn read_cr0() -> u64 {
+    let cr0: u64;
+    unsafe { asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack, preserves_flags)) };
+    cr0
+}
+
+#[inline(always)]
+pub fn write_cr0(value: u64) {
+    unsafe { asm!("mov cr0, {}", in(reg) value, options(nostack, preserves_flags)) };
+}
+
+/// Clears CR0.TS
+#[inline(always)]
+pub fn clts() {
+    unsafe { asm!("clts", options(nomem, nostack, preserves_flags)) };
+}
+
+/// - Bits 0 ..= 2: Reserved
+/// - Bit 3: PWT for Page-level Write-Through
+/// - Bit 4: PCD for Page-level Cache Disable
+/// - Bits 5 ..= 11: Reserved
+/// - Bits 12 ..= (MAXPHYADDR - 1): Address of the PML4 table
+/// - Bits MAXPHYADDR ..= 63: Reserved
+#[inline(always)]
+pub fn read_cr3() -> u64 {
+    let cr3: u64;
+    unsafe { asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack, preserves_flags)) };
+    cr3
+}
+
+#[inline(always)]
+pub fn write_cr3(value: u64) {
+    unsafe { asm!("mov cr3, {}", in(reg) value, options(nostack, preserves_flags)) };
+}
+
+/// Invalidates the TLB entry for the page containing `addr`
+#[inline(always)]
+pub fn invlpg(addr: usize) {
+    unsafe { asm!("invlpg [{}]", in(reg) addr, options(nostack, preserves_flags)) };
+}
+
+/// - Bit 0: VME for Virtual-8086 Mode Extensions
+/// - Bit 1: PVI for Protected-Mode Virtual Interrupts
+/// - Bit 2: TSD for Time Stamp Disable
+/// - Bit 3: DE for Debugging Extensions
+/// - Bit 4: PSE for Page Size Extensions
+/// - Bit 5: PAE for Physical Address Extension
+/// - Bit 6: MCE for Machine-Check Enable
+/// - Bit 7: PGE for Page Global Enable
+/// - Bit 8: PCE for Performance-Monitoring Counter Enable
+/// - Bit 9: OSFXSR for OS support for FXSAVE and FXRSTOR
+/// - Bit 10: OSXMMEXCPT for OS support for unmasked SIMD floating-point exceptions
+/// - Bit 11: UMIP for User-Mode Instruction Prevention
+/// - Bit 12: LA57 for 57-bit linear addresses
+/// - Bit 13: VMXE for VMX-Enable
+/// - Bit 16: FSGSBASE
+/// - Bit 17: PCIDE for PCID-Enable
+/// - Bit 18: OSXSAVE for XSAVE and Processor Extended States-Enable
+/// - Bit 20: SMEP for Supervisor Mode Execution Prevention
+/// - Bit 21: SMAP for Supervisor Mode Access Prevention
+/// - Bit 22: PKE for Protection Key Enable
+#[inline(always)]
+pub fn read_cr4() -> u64 {
+    let cr4: u64;
+    unsafe { asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack, preserves_flags)) };
+    cr4
+}
+
+#[inline(always)]
+pub fn write_cr4(value: u64) {
+    unsafe { asm!("mov cr4, {}", in(reg) value, options(nostack, preserves_flags)) };
+}
+
+/// - Bit 0: x87
+/// - Bit 1: SSE
+/// - Bit 2: AVX
+/// - Bits 3 ..= 4: MPX
+/// - Bit 5: AVX-512 opmask
+/// - Bit 6: AVX-512 ZMM0 ..= 15 upper halves
+/// - Bit 7: AVX-512 ZMM16 ..= 31
+/// - Bit 9: PKRU
+#[inline(always)]
+pub fn read_xcr0() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe {
+        asm!(
+            "xgetbv",
+            in("ecx") 0,
+            lateout("eax") low,
+            lateout("edx") high,
+            options(nomem, nostack, preserves_flags),
+        )
+    };
+    (high as u64) << 32 | low as u64
+}
+
+#[inline(always)]
+pub fn write_xcr0(value: u64) {
+    unsafe {
+        asm!(
+            "xsetbv",
+            in("ecx") 0,
+            in("eax") value as u32,
+            in("edx") (value >> 32) as u32,
+            options(nostack, preserves_flags),
+        )
+    };
+}
@@ -4,14 +4,18 @@ use core::{arch::asm, ptr::addr_of};
 
 use super::Descriptor;
 
-mod tss;
+pub mod tss;
 
 static mut GDT: Table = Table::new();
 
+/// User Data has to directly precede User Code for SYSRET
 #[repr(u8)]
 pub enum SegmentSelector {
     KernelCode = 0x08,
     KernelData = 0x10,
+    UserData = 0x18 | 3,
+    UserCode = 0x20 | 3,
+    TaskState = 0x28,
 }
 
 #[repr(C)]
@@ -133,8 +137,8 @@ struct Table {
     null: SegmentDescriptor,
     kernel_code: SegmentDescriptor,
     kernel_data: SegmentDescriptor,
-    user_code: SegmentDescriptor,
     user_data: SegmentDescriptor,
+    user_code: SegmentDescriptor,
     tss: ExtendedSegmentDescriptor,
 }
 impl Table {
@@ -143,8 +147,8 @@ impl Table {
             null: SegmentDescriptor::null(),
             kernel_code: SegmentDescriptor::kernel_code(),
             kernel_data: SegmentDescriptor::kernel_data(),
-            user_code: SegmentDescriptor::user_code(),
             user_data: SegmentDescriptor::user_data(),
+            user_code: SegmentDescriptor::user_code(),
             tss: ExtendedSegmentDescriptor::task_state(),
         }
     }
@@ -164,6 +168,10 @@ pub fn init() {
         asm!(
             "lgdt [{}]",
             in(reg) &Descriptor::new::<Table>(addr_of!(GDT) as usize),
+        );
+        asm!(
+            "ltr {0:x}",
+            in(reg) SegmentSelector::TaskState as u16,
         )
     };
 }
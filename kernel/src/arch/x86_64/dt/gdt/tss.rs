@@ -2,12 +2,17 @@
 
 use core::ptr::addr_of;
 
-static mut TASK_STATE_SEGMENT: TSS = TSS::null();
+pub static mut TASK_STATE_SEGMENT: TSS = TSS::null();
 
 pub fn get_addr() -> usize {
     addr_of!(TASK_STATE_SEGMENT) as *const TSS as usize
 }
 
+/// Stack loaded on a transition from ring 3 to ring 0
+pub fn set_rsp0(rsp: u64) {
+    unsafe { TASK_STATE_SEGMENT.rsp[0] = rsp };
+}
+
 #[repr(C, packed)]
 pub struct TSS {
     reserved0: u32,
@@ -30,7 +35,8 @@ impl TSS {
             reserved1: 0,
             ist: [0; 7],
             reserved2: [0; 5],
-            io_map_base_address: 0,
+            // Beyond the limit so that there is no I/O permission bit map
+            io_map_base_address: size_of::<TSS>() as u16,
         }
     }
 }
@@ -0,0 +1,32 @@
+//! Error
+
+pub enum Error {
+    InvalidCount,
+
+    InvalidVector(u8),
+
+    OutOfVectors,
+}
+impl From<Error> for super::super::super::Error {
+    fn from(err: Error) -> Self {
+        super::super::super::Error::IDT(err)
+    }
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::X86_64(err.into())
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "/IDT ".out();
+        match self {
+            Error::InvalidCount => "Count Overflow".out(),
+            Error::InvalidVector(vector) => {
+                "Vector ".out();
+                (*vector as usize).out();
+            }
+            Error::OutOfVectors => "Vectors Exhausted".out(),
+        }
+    }
+}
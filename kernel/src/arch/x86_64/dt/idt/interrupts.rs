@@ -1,14 +1,8 @@
 //! Interrupts
 
-use crate::{
-    Output,
-    io::{
-        port,
-        text::{Cursor, keyboard},
-    },
-};
+use crate::{Output, task};
 
-use super::super::super::apic::lapic::eoi;
+use super::super::super::{gdb, mca, pmu};
 
 #[repr(u8)]
 pub enum Interrupt {
@@ -79,19 +73,36 @@ pub enum Interrupt {
 
     /// #CP
     ControlProtectionException,
-
-    Timer = 32,
-    Keyboard,
-    NVMe,
 }
 
 #[repr(C)]
 pub struct InterruptFrame {
-    rip: u64,
-    cs: u64,
-    eflags: u64,
-    rsp: u64,
-    ss: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+}
+
+/// Everything `trap!` saves, in push order reversed
+#[repr(C)]
+pub struct Registers {
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rbp: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub frame: InterruptFrame,
 }
 
 pub fn divide_error() {
@@ -100,19 +111,28 @@ pub fn divide_error() {
     loop {}
 }
 
-pub fn debug_exception() {
+pub fn debug_exception(registers: &mut Registers) {
+    if gdb::enabled() {
+        return gdb::handle(registers);
+    }
     "#DB".out();
 
     loop {}
 }
 
-pub fn nmi_interrupt() {
+pub fn nmi_interrupt(frame: &InterruptFrame) {
+    if pmu::nmi(frame) {
+        return;
+    }
     "NMI".out();
 
     loop {}
 }
 
-pub fn breakpoint() {
+pub fn breakpoint(registers: &mut Registers) {
+    if gdb::enabled() {
+        return gdb::handle(registers);
+    }
     "#BP".out();
 
     loop {}
@@ -137,9 +157,12 @@ pub fn invalid_opcode() {
 }
 
 pub fn device_not_available() {
-    "#NM".out();
+    if let Err(e) = task::device_not_available() {
+        "#NM ".out();
+        e.out();
 
-    loop {}
+        loop {}
+    }
 }
 
 pub fn double_fault() {
@@ -188,10 +211,10 @@ pub fn page_fault() {
     loop {}
 }
 
-pub fn x87_fpu_floating_point_error() {
+pub fn x87_fpu_floating_point_error(frame: &InterruptFrame) {
     "#MF".out();
 
-    loop {}
+    terminate_user(frame)
 }
 
 pub fn alignment_check() {
@@ -200,15 +223,31 @@ pub fn alignment_check() {
     loop {}
 }
 
-pub fn machine_check() {
+pub fn machine_check(frame: &InterruptFrame) {
     "#MC".out();
+    if mca::machine_check() {
+        ".\n".out();
+        return;
+    }
 
-    loop {}
+    terminate_user(frame)
 }
 
-pub fn simd_floating_point_exception() {
+pub fn simd_floating_point_exception(frame: &InterruptFrame) {
     "#XM".out();
 
+    terminate_user(frame)
+}
+
+/// Ends the task if the fault came from ring 3, otherwise hangs
+fn terminate_user(frame: &InterruptFrame) -> ! {
+    if frame.cs & 0b11 == 3 {
+        " in task ".out();
+        task::id().out();
+        ".\n".out();
+        task::exit(usize::MAX)
+    }
+
     loop {}
 }
 
@@ -223,26 +262,3 @@ pub fn control_protection_exception() {
 
     loop {}
 }
-
-static mut TICK: usize = 0;
-pub fn timer() {
-    unsafe {
-        TICK += 1;
-        if TICK == 50 {
-            Cursor::show();
-        } else if TICK == 100 {
-            Cursor::read_cache();
-            TICK = 0;
-        }
-    }
-    eoi();
-}
-
-pub fn keyboard() {
-    keyboard::input(port::in_byte(port::PS2_DATA));
-    eoi();
-}
-
-pub fn nvme() {
-    eoi();
-}
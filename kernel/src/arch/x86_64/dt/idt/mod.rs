@@ -7,9 +7,12 @@ use core::{
 
 use super::{Descriptor, gdt};
 
+mod error;
 mod interrupts;
+pub mod vector;
 
-pub use interrupts::Interrupt;
+pub use error::Error;
+pub use interrupts::{Interrupt, InterruptFrame, Registers};
 
 macro_rules! interrupt {
     ($name:ident) => {{
@@ -57,6 +60,52 @@ macro_rules! interrupt {
     }}
 }
 
+/// Like `interrupt!`, but hands the handler every saved register, mutable
+macro_rules! trap {
+    ($name:ident) => {{
+        #[unsafe(naked)]
+        unsafe extern "C" fn wrapper() {
+            naked_asm!(
+                "push r15",
+                "push r14",
+                "push r13",
+                "push r12",
+                "push r11",
+                "push r10",
+                "push r9",
+                "push r8",
+                "push rbp",
+                "push rdi",
+                "push rsi",
+                "push rdx",
+                "push rcx",
+                "push rbx",
+                "push rax",
+                "mov rdi, rsp",
+                "call {}",
+                "pop rax",
+                "pop rbx",
+                "pop rcx",
+                "pop rdx",
+                "pop rsi",
+                "pop rdi",
+                "pop rbp",
+                "pop r8",
+                "pop r9",
+                "pop r10",
+                "pop r11",
+                "pop r12",
+                "pop r13",
+                "pop r14",
+                "pop r15",
+                "iretq",
+                sym interrupts::$name,
+            );
+        }
+        wrapper
+    }}
+}
+
 macro_rules! exception {
     ($name:ident) => {{
         #[unsafe(naked)]
@@ -177,11 +226,10 @@ pub fn init() {
         IDT[Interrupt::DivideError as usize] =
             GateDescriptor::interrupt(interrupt!(divide_error) as usize);
         IDT[Interrupt::DebugException as usize] =
-            GateDescriptor::interrupt(interrupt!(debug_exception) as usize);
+            GateDescriptor::interrupt(trap!(debug_exception) as usize);
         IDT[Interrupt::NMIInterrupt as usize] =
             GateDescriptor::interrupt(interrupt!(nmi_interrupt) as usize);
-        IDT[Interrupt::Breakpoint as usize] =
-            GateDescriptor::interrupt(interrupt!(breakpoint) as usize);
+        IDT[Interrupt::Breakpoint as usize] = GateDescriptor::interrupt(trap!(breakpoint) as usize);
         IDT[Interrupt::Overflow as usize] =
             GateDescriptor::interrupt(interrupt!(overflow) as usize);
         IDT[Interrupt::BOUNDRangeExceeded as usize] =
@@ -214,10 +262,9 @@ pub fn init() {
             GateDescriptor::interrupt(interrupt!(virtualization_exception) as usize);
         IDT[Interrupt::ControlProtectionException as usize] =
             GateDescriptor::interrupt(interrupt!(control_protection_exception) as usize);
-        IDT[Interrupt::Timer as usize] = GateDescriptor::interrupt(interrupt!(timer) as usize);
-        IDT[Interrupt::Keyboard as usize] =
-            GateDescriptor::interrupt(interrupt!(keyboard) as usize);
-        IDT[Interrupt::NVMe as usize] = GateDescriptor::interrupt(interrupt!(nvme) as usize);
+        for vector in vector::FIRST..=u8::MAX {
+            IDT[vector as usize] = GateDescriptor::interrupt(vector::stub(vector));
+        }
 
         asm!(
             "lidt [{}]",
@@ -0,0 +1,222 @@
+//! Interrupt Vector
+//!
+//! Every external vector enters through a 16-byte stub that pushes its number and jumps to a
+//! common dispatcher, which walks the handlers registered for that vector.
+
+use core::{
+    arch::{global_asm, naked_asm},
+    ptr::{addr_of, fn_addr_eq},
+};
+
+use crate::{random, sync::IrqSpinlock};
+
+use super::{super::super::apic::lapic, Error};
+
+/// First vector not reserved for exceptions
+pub const FIRST: u8 = 32;
+
+/// Spurious-interrupt vector of the local APIC, never allocated and never acknowledged
+pub const SPURIOUS: u8 = 0xFF;
+
+const COUNT: usize = 256 - FIRST as usize;
+
+const STUB_SIZE: usize = 16;
+
+/// Handlers chained on one vector for shared lines
+const MAX_HANDLER_COUNT: usize = 4;
+
+/// Largest block `allocate` hands out, the limit of multiple-message MSI
+const MAX_BLOCK_COUNT: usize = 32;
+
+/// Handlers on a shared vector are all called and must check their own device
+pub type Handler = fn(context: usize);
+
+static VECTORS: IrqSpinlock<[Slot; COUNT]> = IrqSpinlock::new([Slot::null(); COUNT]);
+
+unsafe extern "C" {
+    static VECTOR_STUBS: [[u8; STUB_SIZE]; COUNT];
+}
+
+global_asm!(
+    ".pushsection .text",
+    ".balign {size}",
+    ".global VECTOR_STUBS",
+    "VECTOR_STUBS:",
+    ".altmacro",
+    ".macro vector_stub number",
+    "    .balign {size}",
+    "    push \\number",
+    "    jmp {common}",
+    ".endm",
+    ".set vector, {first}",
+    ".rept {count}",
+    "    vector_stub %vector",
+    "    .set vector, vector + 1",
+    ".endr",
+    ".noaltmacro",
+    ".popsection",
+    size = const STUB_SIZE,
+    first = const FIRST,
+    count = const COUNT,
+    common = sym common,
+);
+
+#[derive(Clone, Copy)]
+struct Entry {
+    handler: Handler,
+    context: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    allocated: bool,
+    entries: [Option<Entry>; MAX_HANDLER_COUNT],
+}
+impl Slot {
+    const fn null() -> Self {
+        Self {
+            allocated: false,
+            entries: [None; MAX_HANDLER_COUNT],
+        }
+    }
+}
+
+fn index(vector: u8) -> Result<usize, Error> {
+    if vector < FIRST || vector == SPURIOUS {
+        return Err(Error::InvalidVector(vector));
+    }
+    Ok((vector - FIRST) as usize)
+}
+
+/// Entry point the IDT gate of `vector` points at
+pub fn stub(vector: u8) -> usize {
+    unsafe { addr_of!(VECTOR_STUBS[(vector - FIRST) as usize]) as usize }
+}
+
+/// Reserves `count` contiguous vectors aligned to `count` and returns the first
+///
+/// The alignment lets the block be programmed as a multiple-message MSI.
+pub fn allocate(count: usize) -> Result<u8, Error> {
+    if count == 0 || count > MAX_BLOCK_COUNT || !count.is_power_of_two() {
+        return Err(Error::InvalidCount);
+    }
+
+    let mut vectors = VECTORS.lock();
+    let mut vector = (FIRST as usize).next_multiple_of(count);
+    while vector + count <= SPURIOUS as usize {
+        let slots = &mut vectors[vector - FIRST as usize..][..count];
+        if slots.iter().all(|slot| !slot.allocated) {
+            for slot in slots {
+                *slot = Slot::null();
+                slot.allocated = true;
+            }
+            return Ok(vector as u8);
+        }
+        vector += count;
+    }
+    Err(Error::OutOfVectors)
+}
+
+/// Releases vectors from `allocate` along with their handlers
+pub fn free(vector: u8, count: usize) -> Result<(), Error> {
+    let start = index(vector)?;
+    if count == 0 || start + count > COUNT {
+        return Err(Error::InvalidCount);
+    }
+    for slot in &mut VECTORS.lock()[start..start + count] {
+        *slot = Slot::null();
+    }
+    Ok(())
+}
+
+/// Chains `handler` on an allocated vector, called with `context` on every interrupt
+pub fn register(vector: u8, handler: Handler, context: usize) -> Result<(), Error> {
+    let mut vectors = VECTORS.lock();
+    let slot = &mut vectors[index(vector)?];
+    if !slot.allocated {
+        return Err(Error::InvalidVector(vector));
+    }
+    let entry = slot
+        .entries
+        .iter_mut()
+        .find(|entry| entry.is_none())
+        .ok_or(Error::InvalidCount)?;
+    *entry = Some(Entry { handler, context });
+    Ok(())
+}
+
+pub fn unregister(vector: u8, handler: Handler, context: usize) -> Result<(), Error> {
+    let mut vectors = VECTORS.lock();
+    let entries = &mut vectors[index(vector)?].entries;
+    let position = entries
+        .iter()
+        .position(|entry| {
+            entry
+                .is_some_and(|entry| fn_addr_eq(entry.handler, handler) && entry.context == context)
+        })
+        .ok_or(Error::InvalidVector(vector))?;
+
+    // Keep the chain contiguous
+    entries.copy_within(position + 1.., position);
+    entries[MAX_HANDLER_COUNT - 1] = None;
+    Ok(())
+}
+
+#[unsafe(naked)]
+unsafe extern "C" fn common() {
+    naked_asm!(
+        "push r15",
+        "push r14",
+        "push r13",
+        "push r12",
+        "push r11",
+        "push r10",
+        "push r9",
+        "push r8",
+        "push rbp",
+        "push rdi",
+        "push rsi",
+        "push rdx",
+        "push rcx",
+        "push rbx",
+        "push rax",
+        "mov rdi, [rsp + 8 * 15]",
+        "sub rsp, 8",
+        "call {}",
+        "add rsp, 8",
+        "pop rax",
+        "pop rbx",
+        "pop rcx",
+        "pop rdx",
+        "pop rsi",
+        "pop rdi",
+        "pop rbp",
+        "pop r8",
+        "pop r9",
+        "pop r10",
+        "pop r11",
+        "pop r12",
+        "pop r13",
+        "pop r14",
+        "pop r15",
+        "add rsp, 8",
+        "iretq",
+        sym dispatch,
+    );
+}
+
+/// Acknowledges the interrupt first, so a handler that switches tasks does not hold the APIC
+extern "C" fn dispatch(vector: u64) {
+    let vector = vector as u8;
+    if vector == SPURIOUS {
+        return;
+    }
+    lapic::eoi();
+    random::add_jitter();
+
+    // Copied out so handlers run without the lock and may register or switch tasks
+    let entries = VECTORS.lock()[(vector - FIRST) as usize].entries;
+    for entry in entries.iter().map_while(|entry| *entry) {
+        (entry.handler)(entry.context);
+    }
+}
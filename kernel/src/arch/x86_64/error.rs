@@ -2,6 +2,8 @@
 
 pub enum Error {
     APIC(super::apic::Error),
+    IDT(super::idt::Error),
+    PMU(super::pmu::Error),
 }
 impl From<Error> for crate::Error {
     fn from(err: Error) -> Self {
@@ -13,6 +15,8 @@ impl crate::Output for Error {
         "x86-64".out();
         match self {
             Error::APIC(e) => e.out(),
+            Error::IDT(e) => e.out(),
+            Error::PMU(e) => e.out(),
         }
     }
 }
@@ -0,0 +1,140 @@
+//! Floating Point Unit
+//!
+//! x87, SSE and AVX state is switched lazily: CR0.TS is set whenever a task is
+//! resumed, and the first FPU instruction afterwards raises #NM, at which point
+//! the previous owner's state is saved and the current task's state restored.
+
+use core::{
+    arch::asm,
+    ptr::write_bytes,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::mem::physical;
+
+use super::{
+    cpu::{self, Feature},
+    cr,
+};
+
+/// Size of the legacy FXSAVE area
+const FXSAVE_SIZE: usize = 512;
+
+/// x87 | SSE
+const XCR0_BASE: u64 = 0b11;
+
+const XCR0_AVX: u64 = 1 << 2;
+
+/// Opmask | ZMM_Hi256 | Hi16_ZMM
+const XCR0_AVX512: u64 = 0b111 << 5;
+
+/// Default x87 control word with every exception masked
+const FCW_DEFAULT: u16 = 0x037F;
+
+/// Default MXCSR with every exception masked
+const MXCSR_DEFAULT: u32 = 0x1F80;
+
+static XSAVE: AtomicBool = AtomicBool::new(false);
+
+/// State components enabled in XCR0
+static XCR0: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes of a save area
+static SIZE: AtomicUsize = AtomicUsize::new(FXSAVE_SIZE);
+
+pub fn init() {
+    let features = cpu::features();
+
+    // MP and NE set, EM cleared
+    cr::write_cr0(cr::read_cr0() & !(1 << 2) | (1 << 1) | (1 << 5));
+    // OSFXSR and OSXMMEXCPT
+    let mut cr4 = cr::read_cr4() | (1 << 9) | (1 << 10);
+
+    if features.has(Feature::XSAVE) {
+        cr4 |= 1 << 18;
+        cr::write_cr4(cr4);
+
+        let supported = features.xcr0_supported();
+        let mut xcr0 = XCR0_BASE;
+        if features.has(Feature::AVX) && supported & XCR0_AVX != 0 {
+            xcr0 |= XCR0_AVX;
+            if features.has(Feature::AVX512F) && supported & XCR0_AVX512 == XCR0_AVX512 {
+                xcr0 |= XCR0_AVX512;
+            }
+        }
+        cr::write_xcr0(xcr0);
+
+        XCR0.store(xcr0, Ordering::Relaxed);
+        SIZE.store(
+            features.xsave_size().max(FXSAVE_SIZE + 64),
+            Ordering::Relaxed,
+        );
+        XSAVE.store(true, Ordering::Relaxed);
+    } else {
+        cr::write_cr4(cr4);
+    }
+
+    unsafe { asm!("fninit", options(nomem, nostack)) };
+    set_task_switched();
+}
+
+/// Makes the next FPU instruction raise #NM
+pub fn set_task_switched() {
+    cr::write_cr0(cr::read_cr0() | (1 << 3));
+}
+
+pub fn clear_task_switched() {
+    cr::clts();
+}
+
+/// Allocates a save area holding the initial state
+pub fn allocate() -> Result<usize, crate::mem::Error> {
+    let size = SIZE.load(Ordering::Relaxed);
+    let area = physical::allocate(size)?;
+    unsafe {
+        write_bytes(area as *mut u8, 0, size);
+        (area as *mut u16).write(FCW_DEFAULT);
+        ((area + 24) as *mut u32).write(MXCSR_DEFAULT);
+    }
+    Ok(area)
+}
+
+pub fn deallocate(area: usize) -> Result<(), crate::mem::Error> {
+    physical::deallocate(area)
+}
+
+/// Stores the register state into the 64-byte aligned `area`
+pub fn save(area: usize) {
+    if XSAVE.load(Ordering::Relaxed) {
+        let xcr0 = XCR0.load(Ordering::Relaxed);
+        unsafe {
+            asm!(
+                "xsave64 [{}]",
+                in(reg) area,
+                in("eax") xcr0 as u32,
+                in("edx") (xcr0 >> 32) as u32,
+                options(nostack),
+            )
+        };
+    } else {
+        unsafe { asm!("fxsave64 [{}]", in(reg) area, options(nostack)) };
+    }
+}
+
+/// Loads the register state from the 64-byte aligned `area`
+pub fn restore(area: usize) {
+    if XSAVE.load(Ordering::Relaxed) {
+        let xcr0 = XCR0.load(Ordering::Relaxed);
+        unsafe {
+            asm!(
+                "xrstor64 [{}]",
+                in(reg) area,
+                in("eax") xcr0 as u32,
+                in("edx") (xcr0 >> 32) as u32,
+                options(nostack),
+            )
+        };
+    } else {
+        unsafe { asm!("fxrstor64 [{}]", in(reg) area, options(nostack)) };
+    }
+}
@@ -0,0 +1,478 @@
+//! GDB Remote Serial Protocol
+//!
+//! A stub on COM2 entered from #BP and #DB. GDB inserts its breakpoints on resume and removes
+//! them on stop, so stepping over one is left to GDB.
+
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    io::{port, serial::Uart},
+    mem::{PAGE_SIZE, paging},
+    sync::Spinlock,
+};
+
+use super::{
+    apic::ioapic,
+    cr,
+    idt::{Registers, vector},
+    rflags,
+};
+
+/// ISA IRQ of COM2
+const IRQ: u8 = 3;
+
+/// Largest packet body, advertised to GDB in hex
+const PACKET_SIZE: usize = 0x400;
+
+const MAX_BREAKPOINT_COUNT: usize = 32;
+
+/// rax to r15, rip and eflags, then cs, ss, ds, es, fs and gs
+const REGISTER_COUNT: usize = 24;
+
+const INT3: u8 = 0xCC;
+
+/// Write Protect bit of CR0
+const WP: u64 = 1 << 16;
+
+static UART: Uart = Uart::new(port::COM2);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Address and original byte of every inserted software breakpoint
+static BREAKPOINTS: Spinlock<[Option<(usize, u8)>; MAX_BREAKPOINT_COUNT]> =
+    Spinlock::new([None; MAX_BREAKPOINT_COUNT]);
+
+fn hex_digit(nibble: u8) -> u8 {
+    b"0123456789abcdef"[(nibble & 0xF) as usize]
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Big-endian number as in addresses and lengths
+fn parse_number(digits: &[u8]) -> Option<usize> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0usize, |n, &digit| {
+        n.checked_mul(16)?.checked_add(hex_value(digit)? as usize)
+    })
+}
+
+/// Little-endian value of `size` bytes as in register contents
+fn parse_value(digits: &[u8], size: usize) -> Option<u64> {
+    if digits.len() < size * 2 {
+        return None;
+    }
+    let mut value = 0u64;
+    for (i, pair) in digits[..size * 2].chunks(2).enumerate() {
+        let byte = hex_value(pair[0])? << 4 | hex_value(pair[1])?;
+        value |= (byte as u64) << (i * 8);
+    }
+    Some(value)
+}
+
+fn split(data: &[u8], separator: u8) -> Option<(&[u8], &[u8])> {
+    let position = data.iter().position(|&byte| byte == separator)?;
+    Some((&data[..position], &data[position + 1..]))
+}
+
+/// Reply being built, hex-encoded where the protocol wants it
+struct Packet {
+    buffer: [u8; PACKET_SIZE],
+    len: usize,
+}
+impl Packet {
+    fn new() -> Self {
+        Self {
+            buffer: [0; PACKET_SIZE],
+            len: 0,
+        }
+    }
+
+    fn str(&mut self, s: &str) -> &mut Self {
+        for &byte in s.as_bytes() {
+            if self.len < PACKET_SIZE {
+                self.buffer[self.len] = byte;
+                self.len += 1;
+            }
+        }
+        self
+    }
+
+    fn byte(&mut self, byte: u8) -> &mut Self {
+        if self.len + 2 <= PACKET_SIZE {
+            self.buffer[self.len] = hex_digit(byte >> 4);
+            self.buffer[self.len + 1] = hex_digit(byte);
+            self.len += 2;
+        }
+        self
+    }
+
+    fn value(&mut self, value: u64, size: usize) -> &mut Self {
+        for i in 0..size {
+            self.byte((value >> (i * 8)) as u8);
+        }
+        self
+    }
+
+    fn send(&self) {
+        loop {
+            UART.out_byte(b'$');
+            let mut checksum = 0u8;
+            for &byte in &self.buffer[..self.len] {
+                UART.out_byte(byte);
+                checksum = checksum.wrapping_add(byte);
+            }
+            UART.out_byte(b'#');
+            UART.out_byte(hex_digit(checksum >> 4));
+            UART.out_byte(hex_digit(checksum));
+            if UART.in_byte() == b'+' {
+                return;
+            }
+        }
+    }
+}
+
+/// Waits for a packet with a valid checksum and returns its body length
+fn receive(buffer: &mut [u8; PACKET_SIZE]) -> usize {
+    loop {
+        while UART.in_byte() != b'$' {}
+
+        let mut len = 0;
+        let mut checksum = 0u8;
+        loop {
+            let byte = UART.in_byte();
+            if byte == b'#' {
+                break;
+            }
+            if len < PACKET_SIZE {
+                buffer[len] = byte;
+                len += 1;
+            }
+            checksum = checksum.wrapping_add(byte);
+        }
+        let expected = hex_value(UART.in_byte()).zip(hex_value(UART.in_byte()));
+        if expected.is_some_and(|(high, low)| high << 4 | low == checksum) {
+            UART.out_byte(b'+');
+            return len;
+        }
+        UART.out_byte(b'-');
+    }
+}
+
+fn segment(index: usize) -> u64 {
+    let selector: u16;
+    unsafe {
+        match index {
+            20 => asm!("mov {:x}, ds", out(reg) selector),
+            21 => asm!("mov {:x}, es", out(reg) selector),
+            22 => asm!("mov {:x}, fs", out(reg) selector),
+            _ => asm!("mov {:x}, gs", out(reg) selector),
+        }
+    }
+    selector as u64
+}
+
+/// Value and size in bytes of register `index` in GDB's amd64 numbering
+fn register(registers: &Registers, index: usize) -> Option<(u64, usize)> {
+    let value = match index {
+        0 => registers.rax,
+        1 => registers.rbx,
+        2 => registers.rcx,
+        3 => registers.rdx,
+        4 => registers.rsi,
+        5 => registers.rdi,
+        6 => registers.rbp,
+        7 => registers.frame.rsp,
+        8 => registers.r8,
+        9 => registers.r9,
+        10 => registers.r10,
+        11 => registers.r11,
+        12 => registers.r12,
+        13 => registers.r13,
+        14 => registers.r14,
+        15 => registers.r15,
+        16 => registers.frame.rip,
+        17 => return Some((registers.frame.eflags, 4)),
+        18 => return Some((registers.frame.cs, 4)),
+        19 => return Some((registers.frame.ss, 4)),
+        20..REGISTER_COUNT => return Some((segment(index), 4)),
+        _ => return None,
+    };
+    Some((value, 8))
+}
+
+/// Segment selectors are reported but kept, the kernel owns them
+fn set_register(registers: &mut Registers, index: usize, value: u64) {
+    let register = match index {
+        0 => &mut registers.rax,
+        1 => &mut registers.rbx,
+        2 => &mut registers.rcx,
+        3 => &mut registers.rdx,
+        4 => &mut registers.rsi,
+        5 => &mut registers.rdi,
+        6 => &mut registers.rbp,
+        7 => &mut registers.frame.rsp,
+        8 => &mut registers.r8,
+        9 => &mut registers.r9,
+        10 => &mut registers.r10,
+        11 => &mut registers.r11,
+        12 => &mut registers.r12,
+        13 => &mut registers.r13,
+        14 => &mut registers.r14,
+        15 => &mut registers.r15,
+        16 => &mut registers.frame.rip,
+        17 => &mut registers.frame.eflags,
+        _ => return,
+    };
+    *register = value;
+}
+
+fn readable(addr: usize, len: usize) -> bool {
+    let Some(end) = addr.checked_add(len) else {
+        return false;
+    };
+    (addr & !(PAGE_SIZE - 1)..end)
+        .step_by(PAGE_SIZE)
+        .all(paging::mapped)
+}
+
+/// Writes through read-only kernel text by lifting CR0.WP for the duration
+fn write_memory(addr: usize, bytes: impl Iterator<Item = u8>) {
+    let cr0 = cr::read_cr0();
+    cr::write_cr0(cr0 & !WP);
+    for (i, byte) in bytes.enumerate() {
+        unsafe { ((addr + i) as *mut u8).write_volatile(byte) };
+    }
+    cr::write_cr0(cr0);
+}
+
+fn insert_breakpoint(addr: usize) -> bool {
+    let mut breakpoints = BREAKPOINTS.lock();
+    if breakpoints.iter().flatten().any(|&(a, _)| a == addr) {
+        return true;
+    }
+    let Some(slot) = breakpoints.iter_mut().find(|slot| slot.is_none()) else {
+        return false;
+    };
+    if !readable(addr, 1) {
+        return false;
+    }
+    *slot = Some((addr, unsafe { (addr as *const u8).read_volatile() }));
+    write_memory(addr, [INT3].into_iter());
+    true
+}
+
+fn remove_breakpoint(addr: usize) -> bool {
+    let mut breakpoints = BREAKPOINTS.lock();
+    let Some(slot) = breakpoints
+        .iter_mut()
+        .find(|slot| slot.is_some_and(|(a, _)| a == addr))
+    else {
+        return false;
+    };
+    if let Some((addr, original)) = slot.take() {
+        write_memory(addr, [original].into_iter());
+    }
+    true
+}
+
+fn remove_breakpoints() {
+    for slot in BREAKPOINTS.lock().iter_mut() {
+        if let Some((addr, original)) = slot.take() {
+            write_memory(addr, [original].into_iter());
+        }
+    }
+}
+
+/// `addr,len` of `m`, `M`, `Z` and `z`
+fn parse_range(data: &[u8]) -> Option<(usize, usize)> {
+    let (addr, len) = split(data, b',')?;
+    Some((parse_number(addr)?, parse_number(len)?))
+}
+
+/// Resumes at `addr` if the packet names one
+fn resume_at(registers: &mut Registers, data: &[u8]) {
+    if let Some(addr) = parse_number(data) {
+        registers.frame.rip = addr as u64;
+    }
+}
+
+/// Talks to GDB until it resumes the interrupted code
+pub fn handle(registers: &mut Registers) {
+    // Report the address of an inserted breakpoint rather than the byte after it
+    let rip = (registers.frame.rip as usize).wrapping_sub(1);
+    if BREAKPOINTS.lock().iter().flatten().any(|&(a, _)| a == rip) {
+        registers.frame.rip = rip as u64;
+    }
+    registers.frame.eflags &= !rflags::TF;
+
+    // SIGTRAP
+    Packet::new().str("S05").send();
+
+    let mut buffer = [0u8; PACKET_SIZE];
+    loop {
+        let len = receive(&mut buffer);
+        let Some((&command, data)) = buffer[..len].split_first() else {
+            continue;
+        };
+        let mut reply = Packet::new();
+        match command {
+            b'?' => {
+                reply.str("S05");
+            }
+            b'g' => {
+                for index in 0..REGISTER_COUNT {
+                    if let Some((value, size)) = register(registers, index) {
+                        reply.value(value, size);
+                    }
+                }
+            }
+            b'G' => {
+                let mut offset = 0;
+                for index in 0..REGISTER_COUNT {
+                    let size = if index < 17 { 8 } else { 4 };
+                    let Some(value) = data.get(offset..).and_then(|d| parse_value(d, size)) else {
+                        break;
+                    };
+                    set_register(registers, index, value);
+                    offset += size * 2;
+                }
+                reply.str("OK");
+            }
+            b'p' => match parse_number(data).and_then(|index| register(registers, index)) {
+                Some((value, size)) => {
+                    reply.value(value, size);
+                }
+                None => {
+                    reply.str("E00");
+                }
+            },
+            b'P' => {
+                let parsed = split(data, b'=').and_then(|(index, value)| {
+                    let index = parse_number(index)?;
+                    let (_, size) = register(registers, index)?;
+                    Some((index, parse_value(value, size)?))
+                });
+                match parsed {
+                    Some((index, value)) => {
+                        set_register(registers, index, value);
+                        reply.str("OK");
+                    }
+                    None => {
+                        reply.str("E00");
+                    }
+                }
+            }
+            b'm' => match parse_range(data) {
+                Some((addr, len)) if len <= PACKET_SIZE / 2 && readable(addr, len) => {
+                    for i in 0..len {
+                        reply.byte(unsafe { ((addr + i) as *const u8).read_volatile() });
+                    }
+                }
+                _ => {
+                    reply.str("E14");
+                }
+            },
+            b'M' => {
+                let parsed = split(data, b':').and_then(|(range, bytes)| {
+                    let (addr, len) = parse_range(range)?;
+                    (bytes.len() >= len * 2 && readable(addr, len)).then_some((addr, len, bytes))
+                });
+                match parsed {
+                    Some((addr, len, bytes)) => {
+                        write_memory(
+                            addr,
+                            bytes[..len * 2]
+                                .chunks(2)
+                                .map(|pair| parse_value(pair, 1).unwrap_or(0) as u8),
+                        );
+                        reply.str("OK");
+                    }
+                    None => {
+                        reply.str("E14");
+                    }
+                }
+            }
+            b'Z' | b'z' => {
+                // Only software breakpoints, type 0
+                if let Some((addr, _)) = data.strip_prefix(b"0,").and_then(parse_range) {
+                    let done = if command == b'Z' {
+                        insert_breakpoint(addr)
+                    } else {
+                        remove_breakpoint(addr)
+                    };
+                    reply.str(if done { "OK" } else { "E22" });
+                }
+            }
+            b'c' => {
+                resume_at(registers, data);
+                return;
+            }
+            b's' => {
+                resume_at(registers, data);
+                registers.frame.eflags |= rflags::TF;
+                return;
+            }
+            b'D' => {
+                remove_breakpoints();
+                reply.str("OK").send();
+                return;
+            }
+            b'k' => {
+                remove_breakpoints();
+                return;
+            }
+            b'H' => {
+                reply.str("OK");
+            }
+            b'q' if data.starts_with(b"Supported") => {
+                reply.str("PacketSize=400");
+            }
+            b'q' if data.starts_with(b"Attached") => {
+                reply.str("1");
+            }
+            _ => {}
+        }
+        reply.send();
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Stops in the stub as if a breakpoint had been hit here
+pub fn breakpoint() {
+    unsafe { asm!("int3") };
+}
+
+/// Ctrl-C from GDB while the kernel runs
+fn interrupt(_: usize) {
+    while let Some(byte) = UART.try_in_byte() {
+        if byte == 0x03 {
+            breakpoint();
+        }
+    }
+}
+
+pub fn init() -> Result<(), crate::Error> {
+    if !UART.init() {
+        return Ok(());
+    }
+    let vector = vector::allocate(1)?;
+    vector::register(vector, interrupt, 0)?;
+    ioapic::route_isa(IRQ, vector)?;
+    UART.enable_receive();
+    ENABLED.store(true, Ordering::Release);
+    Ok(())
+}
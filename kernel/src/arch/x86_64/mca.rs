@@ -0,0 +1,225 @@
+//! Machine Check Architecture
+//!
+//! Uncorrected errors arrive as #MC. Corrected ones are only logged by the banks, so they are
+//! collected by a polling task, which CMCI wakes as soon as they happen where supported.
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::{io::text::Output, task, warn};
+
+use super::{
+    apic::lapic,
+    cpu::{self, Feature},
+    cr,
+    idt::vector,
+    msr,
+};
+
+/// Interval between two scans for corrected errors
+const POLL_MS: u64 = 1000;
+
+/// Machine-Check Enable bit of CR4
+const CR4_MCE: u64 = 1 << 6;
+
+const MCG_CTL_P: u64 = 1 << 8;
+const MCG_CMCI_P: u64 = 1 << 10;
+
+const RIPV: u64 = 1 << 0;
+const MCIP: u64 = 1 << 2;
+
+const VAL: u64 = 1 << 63;
+const OVER: u64 = 1 << 62;
+const UC: u64 = 1 << 61;
+const MISCV: u64 = 1 << 59;
+const ADDRV: u64 = 1 << 58;
+const PCC: u64 = 1 << 57;
+
+const CMCI_EN: u64 = 1 << 30;
+
+static BANK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Task ID of the poller once it runs
+static POLLER: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// What one bank logged
+pub struct Event {
+    bank: usize,
+    status: u64,
+    addr: Option<u64>,
+    misc: Option<u64>,
+}
+impl Event {
+    fn read(bank: usize) -> Option<Self> {
+        let offset = 4 * bank as u32;
+        let status = msr::read(msr::IA32_MC0_STATUS + offset);
+        if status & VAL == 0 {
+            return None;
+        }
+        Some(Self {
+            bank,
+            status,
+            addr: (status & ADDRV != 0).then(|| msr::read(msr::IA32_MC0_ADDR + offset)),
+            misc: (status & MISCV != 0).then(|| msr::read(msr::IA32_MC0_MISC + offset)),
+        })
+    }
+
+    /// Lets the bank log the next error
+    fn clear(&self) {
+        msr::write(msr::IA32_MC0_STATUS + 4 * self.bank as u32, 0);
+    }
+
+    pub fn corrected(&self) -> bool {
+        self.status & UC == 0
+    }
+
+    /// Simple and compound MCA error codes, with the filter bit masked off
+    fn kind(&self) -> &'static str {
+        let code = self.status as u16 & !(1 << 12);
+        match code {
+            0x0000 => "No Error",
+            0x0001 => "Unclassified",
+            0x0002 => "Microcode ROM Parity",
+            0x0003 => "External",
+            0x0004 => "FRC",
+            0x0005 => "Internal Parity",
+            0x0006 => "SMM Handler Code Access Violation",
+            0x0400 => "Internal Timer",
+            _ if code & 0xFFFC == 0x000C => "Generic Cache Hierarchy",
+            _ if code & 0xFFF0 == 0x0010 => "TLB",
+            _ if code & 0xFF80 == 0x0080 => "Memory Controller",
+            _ if code & 0xFF00 == 0x0100 => "Cache Hierarchy",
+            _ if code & 0xF800 == 0x0800 => "Bus and Interconnect",
+            _ if code & 0xFC00 == 0x0400 => "Internal Unclassified",
+            _ => "Unknown",
+        }
+    }
+}
+impl Output for Event {
+    fn out(&self) {
+        if self.corrected() {
+            "Corrected"
+        } else {
+            "Uncorrected"
+        }
+        .out();
+        " error in bank ".out();
+        self.bank.out();
+        ": ".out();
+        self.kind().out();
+        " (status ".out();
+        self.status.out();
+        if let Some(addr) = self.addr {
+            ", address ".out();
+            addr.out();
+        }
+        if let Some(misc) = self.misc {
+            ", misc ".out();
+            misc.out();
+        }
+        ")".out();
+        if self.status & PCC != 0 {
+            ", processor context corrupt".out();
+        }
+        if self.status & OVER != 0 {
+            ", overflowed".out();
+        }
+    }
+}
+
+fn events() -> impl Iterator<Item = Event> {
+    (0..BANK_COUNT.load(Ordering::Relaxed)).filter_map(Event::read)
+}
+
+/// Logs and clears every corrected error the banks hold, leaving uncorrected ones to #MC
+pub fn poll() {
+    for event in events().filter(Event::corrected) {
+        warn!("MCA", event);
+        event.clear();
+    }
+}
+
+/// Leaves the banks to the poller, which would race it and which logs outside the interrupt
+fn cmci(_: usize) {
+    task::wake(POLLER.load(Ordering::Relaxed));
+}
+
+fn poller(_: usize) -> usize {
+    loop {
+        task::sleep(POLL_MS);
+        poll();
+    }
+}
+
+/// Reports every bank and tells whether execution can resume where it was interrupted
+///
+/// Uses `out` directly since the #MC may have interrupted the log itself.
+pub fn machine_check() -> bool {
+    let mut recoverable = msr::read(msr::IA32_MCG_STATUS) & RIPV != 0;
+    for event in events() {
+        "\n    ".out();
+        event.out();
+        if !event.corrected() {
+            recoverable = false;
+        }
+        event.clear();
+    }
+    if recoverable {
+        msr::write(
+            msr::IA32_MCG_STATUS,
+            msr::read(msr::IA32_MCG_STATUS) & !MCIP,
+        );
+    }
+    recoverable
+}
+
+/// Enables every bank and #MC, and CMCI where the processor has it
+pub fn init() -> Result<(), crate::Error> {
+    let features = cpu::features();
+    if !features.has(Feature::MCE) || !features.has(Feature::MCA) {
+        return Ok(());
+    }
+
+    let cap = msr::read(msr::IA32_MCG_CAP);
+    let count = (cap & 0xFF) as usize;
+    BANK_COUNT.store(count, Ordering::Relaxed);
+    if cap & MCG_CTL_P != 0 {
+        msr::write(msr::IA32_MCG_CTL, u64::MAX);
+    }
+
+    // Bank 0 of older P6 processors belongs to the firmware
+    let first = (features.vendor() == "GenuineIntel"
+        && features.family() == 6
+        && features.model() < 0x1A) as usize;
+    for bank in first..count {
+        msr::write(msr::IA32_MC0_CTL + 4 * bank as u32, u64::MAX);
+    }
+
+    // Report what survived the reset, then start clean
+    poll();
+    for event in events() {
+        warn!("MCA", "Left over: ", event);
+        event.clear();
+    }
+    cr::write_cr4(cr::read_cr4() | CR4_MCE);
+
+    if cap & MCG_CMCI_P != 0 {
+        let vector = vector::allocate(1)?;
+        vector::register(vector, cmci, 0)?;
+        for bank in 0..count as u32 {
+            let ctl2 = msr::IA32_MC0_CTL2 + bank;
+            msr::write(ctl2, (msr::read(ctl2) & !0x7FFF) | CMCI_EN | 1);
+        }
+        lapic::set_cmci(vector);
+    }
+    ENABLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Starts the corrected-error poller, once tasks exist
+pub fn start_polling() -> Result<(), crate::Error> {
+    if ENABLED.load(Ordering::Relaxed) {
+        POLLER.store(task::spawn(poller, 0)?, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -1,16 +1,49 @@
 //! x86_64
 
+use core::arch::asm;
+
 pub mod apic;
-mod cpuid;
+pub mod cpu;
+pub mod cr;
 mod dt;
 mod error;
-mod msr;
+pub mod fpu;
+pub mod gdb;
+pub mod mca;
+pub mod msr;
+pub mod pmu;
+pub mod rflags;
+pub mod rng;
+pub mod syscall;
 
 pub use dt::gdt;
 pub use dt::idt;
 pub use error::Error;
 
 pub fn init() -> Result<(), crate::Error> {
+    cpu::init();
+    fpu::init();
     dt::init();
-    apic::init()
+    syscall::init();
+    apic::init()?;
+    mca::init()
+}
+
+/// Enables interrupts and halts until the next one arrives without losing a wakeup in between
+#[inline(always)]
+pub fn wait_for_interrupt() {
+    unsafe { asm!("sti", "hlt", options(nomem, nostack)) };
+}
+
+/// Stops this processor for good, NMIs aside
+pub fn halt() -> ! {
+    loop {
+        unsafe { asm!("cli", "hlt", options(nomem, nostack)) };
+    }
+}
+
+/// Loads an empty IDT and raises an exception, which shuts the processor down into a reset
+pub fn triple_fault() -> ! {
+    let descriptor = [0u64; 2];
+    unsafe { asm!("lidt [{}]", "int3", in(reg) &descriptor, options(noreturn)) }
 }
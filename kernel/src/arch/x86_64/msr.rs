@@ -11,9 +11,130 @@ use core::arch::asm;
 /// - Bits MAXPHYADDR ..= 63: Reserved
 pub const IA32_APIC_BASE: u32 = 0x1B;
 
+/// Counter `i` at `IA32_PMC0 + i`
+/// - Bits 0 ..= (width - 1): General-purpose performance counter
+pub const IA32_PMC0: u32 = 0xC1;
+
+/// Counter `i` at `IA32_PERFEVTSEL0 + i`
+/// - Bits 0 ..= 7: Event Select
+/// - Bits 8 ..= 15: Unit Mask
+/// - Bit 16: USR for counting at CPL > 0
+/// - Bit 17: OS for counting at CPL 0
+/// - Bit 18: E for Edge detect
+/// - Bit 19: PC for Pin control
+/// - Bit 20: INT for APIC interrupt on overflow
+/// - Bit 21: ANY for any thread
+/// - Bit 22: EN for Enable counter
+/// - Bit 23: INV for Invert counter mask
+/// - Bits 24 ..= 31: Counter Mask
+/// - Bits 32 ..= 63: Reserved
+pub const IA32_PERFEVTSEL0: u32 = 0x186;
+
+/// - Bits 0 ..= 7: Count of error-reporting banks
+/// - Bit 8: MCG_CTL_P for IA32_MCG_CTL present
+/// - Bit 9: MCG_EXT_P for Extended state registers present
+/// - Bit 10: MCG_CMCI_P for Corrected machine-check interrupt present
+/// - Bit 11: MCG_TES_P for Threshold-based error status present
+/// - Bits 12 ..= 15: Reserved
+/// - Bits 16 ..= 23: Count of extended state registers
+/// - Bit 24: MCG_SER_P for Software error recovery present
+/// - Bits 25 ..= 63: Reserved or model specific
+pub const IA32_MCG_CAP: u32 = 0x179;
+
+/// - Bit 0: RIPV for Restart IP Valid
+/// - Bit 1: EIPV for Error IP Valid
+/// - Bit 2: MCIP for Machine Check In Progress
+/// - Bits 3 ..= 63: Reserved
+pub const IA32_MCG_STATUS: u32 = 0x17A;
+
+/// - Bits 0 ..= 63: Enables for every bank, present if MCG_CTL_P
+pub const IA32_MCG_CTL: u32 = 0x17B;
+
+/// Bank `i` at `IA32_MC0_CTL2 + i`, present if MCG_CMCI_P
+/// - Bits 0 ..= 14: Corrected error count threshold
+/// - Bits 15 ..= 29: Reserved
+/// - Bit 30: CMCI_EN for CMCI Enable
+/// - Bits 31 ..= 63: Reserved
+pub const IA32_MC0_CTL2: u32 = 0x280;
+
+/// Counter `i` at `IA32_FIXED_CTR0 + i`
+/// - 0: Instructions retired
+/// - 1: Unhalted core cycles
+/// - 2: Unhalted reference cycles
+pub const IA32_FIXED_CTR0: u32 = 0x309;
+
+/// 4 bits per fixed counter
+/// - Bit 0: Count at CPL 0
+/// - Bit 1: Count at CPL > 0
+/// - Bit 2: AnyThread
+/// - Bit 3: PMI on overflow
+pub const IA32_FIXED_CTR_CTRL: u32 = 0x38D;
+
+/// - Bits 0 ..= 31: Overflow of general-purpose counter n
+/// - Bits 32 ..= 47: Overflow of fixed counter n - 32
+/// - Bits 48 ..= 63: Other status
+pub const IA32_PERF_GLOBAL_STATUS: u32 = 0x38E;
+
+/// - Bits 0 ..= 31: Enable general-purpose counter n
+/// - Bits 32 ..= 47: Enable fixed counter n - 32
+/// - Bits 48 ..= 63: Reserved
+pub const IA32_PERF_GLOBAL_CTRL: u32 = 0x38F;
+
+/// Writing 1 clears the matching bit of IA32_PERF_GLOBAL_STATUS
+pub const IA32_PERF_GLOBAL_OVF_CTRL: u32 = 0x390;
+
+/// Bank `i` at `IA32_MC0_CTL + 4 * i`
+/// - Bits 0 ..= 63: Error reporting enables
+pub const IA32_MC0_CTL: u32 = 0x400;
+
+/// Bank `i` at `IA32_MC0_STATUS + 4 * i`
+/// - Bits 0 ..= 15: MCA error code
+/// - Bits 16 ..= 31: Model-specific error code
+/// - Bits 32 ..= 52: Other information
+/// - Bits 53 ..= 56: Corrected error count and threshold status when MCG_TES_P
+/// - Bit 57: PCC for Processor Context Corrupt
+/// - Bit 58: ADDRV for IA32_MCi_ADDR valid
+/// - Bit 59: MISCV for IA32_MCi_MISC valid
+/// - Bit 60: EN for Error enabled
+/// - Bit 61: UC for Uncorrected error
+/// - Bit 62: OVER for Error overflow
+/// - Bit 63: VAL for Valid
+pub const IA32_MC0_STATUS: u32 = 0x401;
+
+/// Bank `i` at `IA32_MC0_ADDR + 4 * i`
+/// - Bits 0 ..= 63: Address of the code or data that caused the error
+pub const IA32_MC0_ADDR: u32 = 0x402;
+
+/// Bank `i` at `IA32_MC0_MISC + 4 * i`
+/// - Bits 0 ..= 5: Least significant valid bit of IA32_MCi_ADDR
+/// - Bits 6 ..= 8: Address mode
+/// - Bits 9 ..= 63: Model specific
+pub const IA32_MC0_MISC: u32 = 0x403;
+
 /// - Bits 0 ..= 63: TSC-deadline Value
 pub const IA32_TSC_DEADLINE: u32 = 0x6E0;
 
+/// - Bit 0: SCE for SYSCALL Enable
+/// - Bits 1 ..= 7: Reserved
+/// - Bit 8: LME for IA-32e Mode Enable
+/// - Bit 9: Reserved
+/// - Bit 10: LMA for IA-32e Mode Active
+/// - Bit 11: NXE for Execute Disable Bit Enable
+/// - Bits 12 ..= 63: Reserved
+pub const IA32_EFER: u32 = 0xC000_0080;
+
+/// - Bits 0 ..= 31: Reserved
+/// - Bits 32 ..= 47: SYSCALL CS and SS
+/// - Bits 48 ..= 63: SYSRET CS and SS
+pub const IA32_STAR: u32 = 0xC000_0081;
+
+/// - Bits 0 ..= 63: SYSCALL target RIP in 64-bit mode
+pub const IA32_LSTAR: u32 = 0xC000_0082;
+
+/// - Bits 0 ..= 31: SYSCALL RFLAGS mask
+/// - Bits 32 ..= 63: Reserved
+pub const IA32_FMASK: u32 = 0xC000_0084;
+
 #[inline(always)]
 pub fn read(msr: u32) -> u64 {
     let low: u32;
@@ -0,0 +1,41 @@
+//! Error
+
+use super::Event;
+
+pub enum Error {
+    /// No architectural performance monitoring
+    Unavailable,
+
+    Unsupported(Event),
+
+    OutOfCounters,
+
+    InvalidPeriod(u64),
+}
+impl From<Error> for super::super::Error {
+    fn from(err: Error) -> Self {
+        super::super::Error::PMU(err)
+    }
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::X86_64(err.into())
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "/PMU ".out();
+        match self {
+            Error::Unavailable => "Unavailable".out(),
+            Error::Unsupported(event) => {
+                "Unsupported Event ".out();
+                event.as_str().out();
+            }
+            Error::OutOfCounters => "Counters Exhausted".out(),
+            Error::InvalidPeriod(period) => {
+                "Period ".out();
+                (*period as usize).out();
+            }
+        }
+    }
+}
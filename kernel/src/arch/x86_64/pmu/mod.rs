@@ -0,0 +1,352 @@
+//! Performance Monitoring Unit
+//!
+//! Architectural performance monitoring, with cycles and instructions on the fixed counters
+//! when there are some and everything else on the general-purpose ones.
+//! Counting mode only reads the counters back. Sampling mode arms one counter to overflow every
+//! `period` events and raises an NMI, whose RIP lands in a buffer `dump` reports hottest first.
+//! The kernel keeps its symbols, so `make symbolize` turns the dumped addresses into functions.
+
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+use crate::{info, sync::Spinlock};
+
+use super::{
+    apic::lapic,
+    cpu::{self, PerformanceMonitoring},
+    idt::InterruptFrame,
+    msr,
+};
+
+mod error;
+
+pub use error::Error;
+
+/// Events one session counts at once
+const MAX_EVENT_COUNT: usize = 4;
+
+const SAMPLE_COUNT: usize = 4096;
+
+/// Distinct RIPs `dump` reports
+const TOP_COUNT: usize = 16;
+
+const USR: u64 = 1 << 16;
+const OS: u64 = 1 << 17;
+const INT: u64 = 1 << 20;
+const EN: u64 = 1 << 22;
+
+const FIXED_OS: u64 = 1 << 0;
+const FIXED_USR: u64 = 1 << 1;
+const FIXED_PMI: u64 = 1 << 3;
+
+static SESSION: Spinlock<[Option<(Event, Counter)>; MAX_EVENT_COUNT]> =
+    Spinlock::new([None; MAX_EVENT_COUNT]);
+
+/// Overflow bit of the sampled counter in the global MSRs, 0 when not sampling
+static SAMPLED_BIT: AtomicU64 = AtomicU64::new(0);
+static SAMPLED_MSR: AtomicU32 = AtomicU32::new(0);
+
+/// Written back after every overflow so the next one is `period` events away
+static RELOAD: AtomicU64 = AtomicU64::new(0);
+
+/// Filled by the NMI without locking, so `dump` only ever sees whole RIPs
+static SAMPLES: [AtomicU64; SAMPLE_COUNT] = [const { AtomicU64::new(0) }; SAMPLE_COUNT];
+static SAMPLE_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Scratch space of `dump`, too large for the stack
+static SORTED: Spinlock<[u64; SAMPLE_COUNT]> = Spinlock::new([0; SAMPLE_COUNT]);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Unhalted core cycles
+    Cycles,
+
+    /// Instructions retired
+    Instructions,
+
+    /// Last level cache misses
+    CacheMisses,
+
+    /// Branch instructions mispredicted at retirement
+    BranchMisses,
+}
+impl Event {
+    /// Numbered in the order above, as the syscalls pass them
+    pub fn new(number: u64) -> Option<Self> {
+        match number {
+            0 => Some(Event::Cycles),
+            1 => Some(Event::Instructions),
+            2 => Some(Event::CacheMisses),
+            3 => Some(Event::BranchMisses),
+            _ => None,
+        }
+    }
+
+    /// Event select, unit mask and the CPUID.0AH:EBX bit telling the event is missing
+    fn architectural(self) -> (u8, u8, u32) {
+        match self {
+            Event::Cycles => (0x3C, 0x00, 0),
+            Event::Instructions => (0xC0, 0x00, 1),
+            Event::CacheMisses => (0x2E, 0x41, 4),
+            Event::BranchMisses => (0xC5, 0x00, 6),
+        }
+    }
+
+    fn fixed(self) -> Option<usize> {
+        match self {
+            Event::Instructions => Some(0),
+            Event::Cycles => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::Cycles => "Cycles",
+            Event::Instructions => "Instructions",
+            Event::CacheMisses => "Cache Misses",
+            Event::BranchMisses => "Branch Misses",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Counter {
+    General(usize),
+    Fixed(usize),
+}
+impl Counter {
+    fn msr(self) -> u32 {
+        match self {
+            Counter::General(i) => msr::IA32_PMC0 + i as u32,
+            Counter::Fixed(i) => msr::IA32_FIXED_CTR0 + i as u32,
+        }
+    }
+
+    /// Bit of the counter in the global control, status and overflow MSRs
+    fn global_bit(self) -> u64 {
+        match self {
+            Counter::General(i) => 1 << i,
+            Counter::Fixed(i) => 1 << (32 + i),
+        }
+    }
+
+    fn mask(self, pm: &PerformanceMonitoring) -> u64 {
+        let width = match self {
+            Counter::General(_) => pm.counter_width,
+            Counter::Fixed(_) => pm.fixed_counter_width,
+        };
+        u64::MAX.checked_shr(64 - width as u32).unwrap_or(0)
+    }
+
+    fn read(self, pm: &PerformanceMonitoring) -> u64 {
+        msr::read(self.msr()) & self.mask(pm)
+    }
+
+    fn write(self, pm: &PerformanceMonitoring, value: u64) {
+        msr::write(self.msr(), value & self.mask(pm));
+    }
+
+    /// Counts `event` at every privilege level, raising a PMI on overflow if `interrupt`
+    fn enable(self, event: Event, interrupt: bool) {
+        match self {
+            Counter::General(i) => {
+                let (select, umask, _) = event.architectural();
+                let int = if interrupt { INT } else { 0 };
+                msr::write(
+                    msr::IA32_PERFEVTSEL0 + i as u32,
+                    EN | int | OS | USR | (umask as u64) << 8 | select as u64,
+                );
+            }
+            Counter::Fixed(i) => {
+                let pmi = if interrupt { FIXED_PMI } else { 0 };
+                let ctrl = msr::read(msr::IA32_FIXED_CTR_CTRL) & !(0xF << (4 * i));
+                msr::write(
+                    msr::IA32_FIXED_CTR_CTRL,
+                    ctrl | (pmi | FIXED_USR | FIXED_OS) << (4 * i),
+                );
+            }
+        }
+    }
+
+    fn disable(self) {
+        match self {
+            Counter::General(i) => msr::write(msr::IA32_PERFEVTSEL0 + i as u32, 0),
+            Counter::Fixed(i) => {
+                let ctrl = msr::read(msr::IA32_FIXED_CTR_CTRL);
+                msr::write(msr::IA32_FIXED_CTR_CTRL, ctrl & !(0xF << (4 * i)));
+            }
+        }
+    }
+}
+
+fn performance_monitoring() -> Result<&'static PerformanceMonitoring, Error> {
+    cpu::features()
+        .performance_monitoring()
+        .ok_or(Error::Unavailable)
+}
+
+/// Picks a free counter for each event, fixed ones first
+fn assign(
+    pm: &PerformanceMonitoring,
+    events: &[Event],
+) -> Result<[Option<(Event, Counter)>; MAX_EVENT_COUNT], Error> {
+    if events.len() > MAX_EVENT_COUNT {
+        return Err(Error::OutOfCounters);
+    }
+
+    let mut session = [None; MAX_EVENT_COUNT];
+    let mut general = 0;
+    for (slot, &event) in session.iter_mut().zip(events) {
+        let (_, _, bit) = event.architectural();
+        if pm.unavailable & (1 << bit) != 0 {
+            return Err(Error::Unsupported(event));
+        }
+        let counter = match event.fixed() {
+            Some(i) if i < pm.fixed_counter_count => Counter::Fixed(i),
+            _ if general < pm.counter_count => {
+                general += 1;
+                Counter::General(general - 1)
+            }
+            _ => return Err(Error::OutOfCounters),
+        };
+        *slot = Some((event, counter));
+    }
+    Ok(session)
+}
+
+/// Global control arrived with version 2, before it the enable bits alone decide
+fn set_global_ctrl(pm: &PerformanceMonitoring, value: u64) {
+    if pm.version >= 2 {
+        msr::write(msr::IA32_PERF_GLOBAL_CTRL, value);
+    }
+}
+
+/// Programs and starts a fresh session
+fn begin(
+    pm: &PerformanceMonitoring,
+    session: [Option<(Event, Counter)>; MAX_EVENT_COUNT],
+    sampling: bool,
+) {
+    let mut global = 0;
+    for (i, &(event, counter)) in session.iter().flatten().enumerate() {
+        // A sampling session holds its one event
+        let reload = if sampling && i == 0 {
+            RELOAD.load(Ordering::Relaxed)
+        } else {
+            0
+        };
+        counter.write(pm, reload);
+        counter.enable(event, sampling);
+        global |= counter.global_bit();
+    }
+    set_global_ctrl(pm, global);
+    *SESSION.lock() = session;
+}
+
+/// Disables every counter of the session, leaving their values readable
+pub fn stop() {
+    let Ok(pm) = performance_monitoring() else {
+        return;
+    };
+    set_global_ctrl(pm, 0);
+    SAMPLED_BIT.store(0, Ordering::Release);
+    for &(_, counter) in SESSION.lock().iter().flatten() {
+        counter.disable();
+    }
+}
+
+/// Counts `events` in kernel and user mode from zero, replacing the previous session
+pub fn start(events: &[Event]) -> Result<(), Error> {
+    let pm = performance_monitoring()?;
+    let session = assign(pm, events)?;
+    stop();
+    begin(pm, session, false);
+    Ok(())
+}
+
+/// Value of `event` in the current session
+pub fn read(event: Event) -> Option<u64> {
+    let pm = performance_monitoring().ok()?;
+    SESSION
+        .lock()
+        .iter()
+        .flatten()
+        .find(|&&(e, _)| e == event)
+        .map(|&(_, counter)| counter.read(pm))
+}
+
+/// Records the RIP every `period` occurrences of `event`, replacing the previous session
+///
+/// Counters only take 32-bit signed writes, which bounds `period`.
+pub fn start_sampling(event: Event, period: u64) -> Result<(), Error> {
+    let pm = performance_monitoring()?;
+    if pm.version < 2 {
+        return Err(Error::Unavailable);
+    }
+    if period == 0 || period > i32::MAX as u64 {
+        return Err(Error::InvalidPeriod(period));
+    }
+    let session = assign(pm, &[event])?;
+    let Some((_, counter)) = session[0] else {
+        return Err(Error::OutOfCounters);
+    };
+    stop();
+
+    SAMPLE_LEN.store(0, Ordering::Relaxed);
+    RELOAD.store(period.wrapping_neg() & counter.mask(pm), Ordering::Relaxed);
+    SAMPLED_MSR.store(counter.msr(), Ordering::Relaxed);
+    msr::write(msr::IA32_PERF_GLOBAL_OVF_CTRL, counter.global_bit());
+    SAMPLED_BIT.store(counter.global_bit(), Ordering::Release);
+    lapic::set_performance_counter_nmi();
+    begin(pm, session, true);
+    Ok(())
+}
+
+/// Takes a sample if the sampled counter overflowed, telling whether the NMI was ours
+pub fn nmi(frame: &InterruptFrame) -> bool {
+    let bit = SAMPLED_BIT.load(Ordering::Acquire);
+    if bit == 0 || msr::read(msr::IA32_PERF_GLOBAL_STATUS) & bit == 0 {
+        return false;
+    }
+
+    let index = SAMPLE_LEN.fetch_add(1, Ordering::Relaxed);
+    if let Some(sample) = SAMPLES.get(index) {
+        sample.store(frame.rip, Ordering::Relaxed);
+    }
+
+    msr::write(
+        SAMPLED_MSR.load(Ordering::Relaxed),
+        RELOAD.load(Ordering::Relaxed),
+    );
+    msr::write(msr::IA32_PERF_GLOBAL_OVF_CTRL, bit);
+    // Delivering the PMI masked the LVT entry
+    lapic::set_performance_counter_nmi();
+    true
+}
+
+/// Logs how many samples were taken and the hottest RIPs, for `make symbolize`
+pub fn dump() {
+    let taken = SAMPLE_LEN.load(Ordering::Relaxed);
+    let count = taken.min(SAMPLE_COUNT);
+    info!("PMU", count, " samples, ", taken - count, " dropped");
+
+    let mut sorted = SORTED.lock();
+    for (rip, sample) in sorted.iter_mut().zip(&SAMPLES[..count]) {
+        *rip = sample.load(Ordering::Relaxed);
+    }
+    let samples = &mut sorted[..count];
+    samples.sort_unstable();
+
+    // Hottest first
+    let mut top = [(0u64, 0usize); TOP_COUNT];
+    for run in samples.chunk_by(|a, b| a == b) {
+        let hits = run.len();
+        if let Some(position) = top.iter().position(|&(_, n)| hits > n) {
+            top.copy_within(position..TOP_COUNT - 1, position + 1);
+            top[position] = (run[0], hits);
+        }
+    }
+    for (rip, hits) in top.into_iter().take_while(|&(_, n)| n > 0) {
+        info!("PMU", rip, " ", hits);
+    }
+}
@@ -0,0 +1,44 @@
+//! RFLAGS Register
+
+use core::arch::asm;
+
+/// Trap Flag
+pub const TF: u64 = 1 << 8;
+
+/// Interrupt Enable Flag
+pub const IF: u64 = 1 << 9;
+
+/// Direction Flag
+pub const DF: u64 = 1 << 10;
+
+/// Alignment Check / Access Control Flag
+pub const AC: u64 = 1 << 18;
+
+#[inline(always)]
+pub fn read() -> u64 {
+    let rflags: u64;
+    unsafe {
+        asm!(
+            "pushfq",
+            "pop {}",
+            out(reg) rflags,
+            options(nomem, preserves_flags),
+        )
+    };
+    rflags
+}
+
+#[inline(always)]
+pub fn interrupts_enabled() -> bool {
+    read() & IF != 0
+}
+
+#[inline(always)]
+pub fn enable_interrupts() {
+    unsafe { asm!("sti", options(nomem, nostack)) };
+}
+
+#[inline(always)]
+pub fn disable_interrupts() {
+    unsafe { asm!("cli", options(nomem, nostack)) };
+}
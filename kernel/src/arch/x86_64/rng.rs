@@ -0,0 +1,80 @@
+//! Random Number Generator
+//!
+//! RDRAND reads the DRBG the processor reseeds on its own, RDSEED reads its entropy source
+//! directly. Both may run dry for a moment and report it through CF, so every read is retried.
+
+use core::{arch::asm, hint::spin_loop};
+
+use super::cpu::{self, Feature};
+
+/// Failing this many times in a row means the DRBG is broken rather than busy
+const RDRAND_RETRY_COUNT: usize = 10;
+
+/// The entropy source refills far slower than the DRBG
+const RDSEED_RETRY_COUNT: usize = 128;
+
+fn rdrand_once() -> Option<u64> {
+    let value: u64;
+    let ok: u8;
+    unsafe {
+        asm!(
+            "rdrand {}",
+            "setc {}",
+            out(reg) value,
+            out(reg_byte) ok,
+            options(nomem, nostack),
+        )
+    };
+    (ok != 0).then_some(value)
+}
+
+fn rdseed_once() -> Option<u64> {
+    let value: u64;
+    let ok: u8;
+    unsafe {
+        asm!(
+            "rdseed {}",
+            "setc {}",
+            out(reg) value,
+            out(reg_byte) ok,
+            options(nomem, nostack),
+        )
+    };
+    (ok != 0).then_some(value)
+}
+
+pub fn rdrand() -> Option<u64> {
+    if !cpu::features().has(Feature::RDRAND) {
+        return None;
+    }
+    (0..RDRAND_RETRY_COUNT).find_map(|_| rdrand_once())
+}
+
+pub fn rdseed() -> Option<u64> {
+    if !cpu::features().has(Feature::RDSEED) {
+        return None;
+    }
+    (0..RDSEED_RETRY_COUNT).find_map(|_| {
+        let value = rdseed_once();
+        if value.is_none() {
+            spin_loop();
+        }
+        value
+    })
+}
+
+/// Time Stamp Counter, whose low bits jitter between events
+#[inline(always)]
+pub fn rdtsc() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe {
+        asm!(
+            "rdtsc",
+            out("eax") low,
+            out("edx") high,
+            options(nomem, nostack, preserves_flags),
+        )
+    };
+    (high as u64) << 32 | low as u64
+}
@@ -0,0 +1,122 @@
+//! System Call
+
+use core::arch::naked_asm;
+
+use super::{
+    gdt::{self, SegmentSelector},
+    msr, rflags,
+};
+
+/// User RSP between `SYSCALL` and the switch onto the kernel stack
+static mut USER_RSP: u64 = 0;
+
+/// Registers saved by `entry`, in the order they are pushed from the bottom up
+#[repr(C)]
+pub struct Frame {
+    /// Number on entry and return value on exit
+    pub rax: u64,
+
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub r10: u64,
+    pub r8: u64,
+    pub r9: u64,
+
+    /// User RIP saved by `SYSCALL` in RCX
+    pub rip: u64,
+
+    /// User RFLAGS saved by `SYSCALL` in R11
+    pub rflags: u64,
+
+    pub rsp: u64,
+}
+
+pub fn init() {
+    msr::write(msr::IA32_EFER, msr::read(msr::IA32_EFER) | 1);
+    msr::write(
+        msr::IA32_STAR,
+        (SegmentSelector::KernelCode as u64) << 32 | (SegmentSelector::KernelData as u64) << 48,
+    );
+    msr::write(msr::IA32_LSTAR, entry as *const () as u64);
+    msr::write(
+        msr::IA32_FMASK,
+        rflags::TF | rflags::IF | rflags::DF | rflags::AC,
+    );
+}
+
+/// `SYSCALL` target
+///
+/// Switches to the ring 0 stack from the TSS before re-enabling interrupts.
+#[unsafe(naked)]
+unsafe extern "C" fn entry() {
+    naked_asm!(
+        "mov [rip + {user_rsp}], rsp",
+        "mov rsp, [rip + {tss} + 4]",
+        "push qword ptr [rip + {user_rsp}]",
+        "push r11",
+        "push rcx",
+        "push r9",
+        "push r8",
+        "push r10",
+        "push rdx",
+        "push rsi",
+        "push rdi",
+        "push rax",
+        "sti",
+        "mov rdi, rsp",
+        "call {handler}",
+        "cli",
+        "pop rax",
+        "pop rdi",
+        "pop rsi",
+        "pop rdx",
+        "pop r10",
+        "pop r8",
+        "pop r9",
+        "pop rcx",
+        "pop r11",
+        "pop rsp",
+        "sysretq",
+        user_rsp = sym USER_RSP,
+        tss = sym gdt::tss::TASK_STATE_SEGMENT,
+        handler = sym handler,
+    );
+}
+
+extern "C" fn handler(frame: &mut Frame) {
+    frame.rax = crate::syscall::dispatch(
+        frame.rax,
+        [
+            frame.rdi, frame.rsi, frame.rdx, frame.r10, frame.r8, frame.r9,
+        ],
+    ) as u64;
+}
+
+/// Drops to ring 3 at `rip` with `rsp` through `SYSRET`
+///
+/// The caller must already run on its own kernel stack in the target address space.
+#[unsafe(naked)]
+pub unsafe extern "C" fn enter(rip: usize, rsp: usize) -> ! {
+    naked_asm!(
+        "cli",
+        "mov rcx, rdi",
+        "mov rsp, rsi",
+        "mov r11, {rflags}",
+        "xor eax, eax",
+        "xor ebx, ebx",
+        "xor edx, edx",
+        "xor esi, esi",
+        "xor edi, edi",
+        "xor ebp, ebp",
+        "xor r8d, r8d",
+        "xor r9d, r9d",
+        "xor r10d, r10d",
+        "xor r12d, r12d",
+        "xor r13d, r13d",
+        "xor r14d, r14d",
+        "xor r15d, r15d",
+        "sysretq",
+        rflags = const rflags::IF | 0b10,
+    );
+}
@@ -1,6 +1,7 @@
 //! Error
 
 pub enum Error {
+    IOMMU(super::iommu::Error),
     Net(super::net::Error),
     PCIe(super::pcie::Error),
     Storage(super::storage::Error),
@@ -14,6 +15,7 @@ impl crate::Output for Error {
     fn out(&self) {
         "Drivers".out();
         match self {
+            Error::IOMMU(e) => e.out(),
             Error::Net(e) => e.out(),
             Error::PCIe(e) => e.out(),
             Error::Storage(e) => e.out(),
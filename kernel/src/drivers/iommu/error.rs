@@ -0,0 +1,37 @@
+//! Error
+
+pub enum Error {
+    InvalidCount(&'static str),
+    Timeout(&'static str),
+    Unsupported(&'static str),
+}
+impl From<Error> for super::super::Error {
+    fn from(err: Error) -> Self {
+        super::super::Error::IOMMU(err)
+    }
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::Drivers(err.into())
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "/IOMMU ".out();
+        match self {
+            Error::InvalidCount(entity) => {
+                entity.out();
+                " Count"
+            }
+            Error::Timeout(operation) => {
+                operation.out();
+                " Timeout"
+            }
+            Error::Unsupported(feature) => {
+                feature.out();
+                " Unsupported"
+            }
+        }
+        .out();
+    }
+}
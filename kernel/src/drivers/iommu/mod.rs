@@ -0,0 +1,378 @@
+//! Input-Output Memory Management Unit
+//!
+//! Intel VT-d DMA remapping from the units the DMAR reports. Each PCI function gets a domain of
+//! its own with second-level page tables that start empty, so it reaches only the buffers its
+//! driver maps and the reserved regions firmware still uses. Addresses are mapped to themselves,
+//! which keeps the I/O virtual address of a buffer equal to its physical one.
+//!
+//! Without a DMAR every function can reach all of memory, and mapping returns the address as is.
+//!
+//! A fault interrupt only wakes a task, which logs and clears the fault records.
+
+use core::{
+    ptr::{read_volatile, write_bytes, write_volatile},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    acpi::{dmar, mcfg, table},
+    info,
+    mem::{Memory, PAGE_SIZE, physical},
+    sync::{RwLock, Spinlock},
+    task,
+    x86_64::idt::vector,
+};
+
+use super::pcie;
+
+mod error;
+mod unit;
+
+pub use error::Error;
+use unit::Unit;
+
+const MAX_UNIT_COUNT: usize = 4;
+const MAX_SCOPE_COUNT: usize = 32;
+const MAX_RESERVED_COUNT: usize = 8;
+const MAX_DOMAIN_COUNT: usize = 16;
+
+/// Second-level entry
+/// - Bit 0: Read
+/// - Bit 1: Write
+/// - Bits 12 ..= 51: Address of the next table or the page
+const READ_WRITE: u64 = 0b11;
+const ADDRESS_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+/// Device scope of a unit: one function, or every bus below a bridge
+#[derive(Clone, Copy)]
+struct Scope {
+    unit: usize,
+    source: u16,
+    bridge: bool,
+}
+
+/// Reserved Memory Region, which `source` keeps reaching behind the kernel's back
+#[derive(Clone, Copy)]
+struct Reserved {
+    base: u64,
+    limit: u64,
+    source: u16,
+}
+
+/// Page tables of the one function whose requests carry `source`
+#[derive(Clone, Copy)]
+struct Domain {
+    source: u16,
+    unit: usize,
+    id: u16,
+    table: usize,
+}
+
+struct Units {
+    units: [Unit; MAX_UNIT_COUNT],
+    count: usize,
+    scopes: [Option<Scope>; MAX_SCOPE_COUNT],
+    reserved: [Option<Reserved>; MAX_RESERVED_COUNT],
+}
+impl Units {
+    fn as_slice(&self) -> &[Unit] {
+        &self.units[..self.count]
+    }
+}
+
+static UNITS: RwLock<Units> = RwLock::new(Units {
+    units: [Unit::null(); MAX_UNIT_COUNT],
+    count: 0,
+    scopes: [None; MAX_SCOPE_COUNT],
+    reserved: [None; MAX_RESERVED_COUNT],
+});
+
+/// Task logging faults, `usize::MAX` until it starts
+static LOGGER: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+static DOMAINS: Spinlock<[Option<Domain>; MAX_DOMAIN_COUNT]> =
+    Spinlock::new([None; MAX_DOMAIN_COUNT]);
+
+/// Requester ID: Bus in bits 8 ..= 15, device in bits 3 ..= 7, function in bits 0 ..= 2
+fn source_id(bus: u8, device: u8, function: u8) -> u16 {
+    (bus as u16) << 8 | (device as u16 & 0x1F) << 3 | function as u16 & 0x7
+}
+
+/// Zeroed page for a root, context or page table
+fn table() -> Result<usize, crate::Error> {
+    let addr = physical::allocate(PAGE_SIZE)?;
+    unsafe { write_bytes(addr as *mut u8, 0, PAGE_SIZE) };
+    Ok(addr)
+}
+
+/// Records a DRHD and returns its index
+pub fn append(addr: usize, include_all: bool) -> Result<usize, Error> {
+    let mut units = UNITS.write();
+    if units.count == MAX_UNIT_COUNT {
+        return Err(Error::InvalidCount("Unit"));
+    }
+    let index = units.count;
+    units.units[index] = Unit::new(addr, include_all);
+    units.count += 1;
+    Ok(index)
+}
+
+/// Records a function, or the hierarchy below a bridge, that `unit` remaps
+pub fn add_scope(
+    unit: usize,
+    bus: u8,
+    device: u8,
+    function: u8,
+    bridge: bool,
+) -> Result<(), Error> {
+    let mut units = UNITS.write();
+    let slot = units
+        .scopes
+        .iter_mut()
+        .find(|scope| scope.is_none())
+        .ok_or(Error::InvalidCount("Device Scope"))?;
+    *slot = Some(Scope {
+        unit,
+        source: source_id(bus, device, function),
+        bridge,
+    });
+    Ok(())
+}
+
+/// Records an RMRR of one function, mapped before translation is turned on
+pub fn add_reserved(base: u64, limit: u64, bus: u8, device: u8, function: u8) -> Result<(), Error> {
+    let mut units = UNITS.write();
+    let slot = units
+        .reserved
+        .iter_mut()
+        .find(|reserved| reserved.is_none())
+        .ok_or(Error::InvalidCount("Reserved Memory Region"))?;
+    *slot = Some(Reserved {
+        base,
+        limit,
+        source: source_id(bus, device, function),
+    });
+    Ok(())
+}
+
+/// Unit remapping `source`, the one listing it before the one including all
+fn unit_of(units: &Units, source: u16) -> Option<usize> {
+    let bus = (source >> 8) as u8;
+    let listed = units.scopes.iter().flatten().find(|scope| {
+        if !scope.bridge {
+            return scope.source == source;
+        }
+        let (bridge_bus, device, function) = (
+            (scope.source >> 8) as u8,
+            (scope.source >> 3) as u8 & 0x1F,
+            scope.source as u8 & 0x7,
+        );
+        mcfg::address(bridge_bus, device, function).is_some_and(|addr| {
+            let bridge = pcie::Type1::get_ref(addr);
+            (bridge.secondary_bus()..=bridge.subordinate_bus()).contains(&bus)
+        })
+    });
+    match listed {
+        Some(scope) => Some(scope.unit),
+        None => units.as_slice().iter().position(|unit| unit.include_all),
+    }
+}
+
+/// Domain of `source`, created with empty page tables on first use
+///
+/// `None` when no unit remaps it, so its requests are not translated.
+fn domain(source: u16) -> Result<Option<Domain>, crate::Error> {
+    let units = UNITS.read();
+    let mut domains = DOMAINS.lock();
+    if let Some(domain) = domains
+        .iter()
+        .flatten()
+        .find(|domain| domain.source == source)
+    {
+        return Ok(Some(*domain));
+    }
+    let Some(unit) = unit_of(&units, source) else {
+        return Ok(None);
+    };
+    let index = domains
+        .iter()
+        .position(|domain| domain.is_none())
+        .ok_or(Error::InvalidCount("Domain"))?;
+    // Domain 0 is reserved when caching mode is on, so start from 1 everywhere
+    let id = index as u16 + 1;
+    if id as usize >= units.units[unit].domain_count {
+        return Err(Error::InvalidCount("Domain").into());
+    }
+    let domain = Domain {
+        source,
+        unit,
+        id,
+        table: table()?,
+    };
+    units.units[unit].attach(source, id, domain.table)?;
+    domains[index] = Some(domain);
+    Ok(Some(domain))
+}
+
+/// Index into the table at `level`, 1 being the one holding pages
+fn index(addr: u64, level: usize) -> usize {
+    ((addr >> (12 + 9 * (level - 1))) & 0x1FF) as usize
+}
+
+/// Walks to the leaf entry of `addr`, adding missing tables when `create` is set
+fn leaf(
+    unit: &Unit,
+    domain: &Domain,
+    addr: u64,
+    create: bool,
+) -> Result<Option<*mut u64>, crate::Error> {
+    let mut table = domain.table;
+    for level in (2..=unit.levels).rev() {
+        let entry = (table as *mut u64).wrapping_add(index(addr, level));
+        let mut value = unsafe { read_volatile(entry) };
+        if value & READ_WRITE == 0 {
+            if !create {
+                return Ok(None);
+            }
+            value = self::table()? as u64 | READ_WRITE;
+            unsafe { write_volatile(entry, value) };
+            unit.flush(entry as usize);
+        }
+        table = (value & ADDRESS_MASK) as usize;
+    }
+    Ok(Some((table as *mut u64).wrapping_add(index(addr, 1))))
+}
+
+/// Pages covering `addr ..= last`
+fn pages(addr: u64, last: u64) -> impl Iterator<Item = u64> {
+    (addr & !(PAGE_SIZE as u64 - 1)..=last).step_by(PAGE_SIZE)
+}
+
+fn map_range(domain: &Domain, addr: u64, last: u64) -> Result<(), crate::Error> {
+    let units = UNITS.read();
+    let unit = &units.units[domain.unit];
+    // Keeps two walks from adding the same table
+    let _domains = DOMAINS.lock();
+    for page in pages(addr, last) {
+        if let Some(entry) = leaf(unit, domain, page, true)? {
+            unsafe { write_volatile(entry, page | READ_WRITE) };
+            unit.flush(entry as usize);
+        }
+    }
+    unit.invalidate_new(domain.id)?;
+    Ok(())
+}
+
+/// Lets the function at PCIe configuration address `device` read and write `addr .. addr + size`
+/// and returns the I/O virtual address to program into it
+pub fn map(device: usize, addr: usize, size: usize) -> Result<u64, crate::Error> {
+    if UNITS.read().count == 0 || size == 0 {
+        return Ok(addr as u64);
+    }
+    let (bus, dev, function) = mcfg::locate(device).ok_or(Error::Unsupported("Segment"))?;
+    if let Some(domain) = domain(source_id(bus, dev, function))? {
+        map_range(&domain, addr as u64, (addr + size - 1) as u64)?;
+    }
+    Ok(addr as u64)
+}
+
+/// Takes back what `map` gave, once the function is done with the buffer
+pub fn unmap(device: usize, addr: usize, size: usize) -> Result<(), crate::Error> {
+    if UNITS.read().count == 0 || size == 0 {
+        return Ok(());
+    }
+    let (bus, dev, function) = mcfg::locate(device).ok_or(Error::Unsupported("Segment"))?;
+    let source = source_id(bus, dev, function);
+    let Some(domain) = DOMAINS
+        .lock()
+        .iter()
+        .flatten()
+        .find(|domain| domain.source == source)
+        .copied()
+    else {
+        return Ok(());
+    };
+    let units = UNITS.read();
+    let unit = &units.units[domain.unit];
+    let _domains = DOMAINS.lock();
+    for page in pages(addr as u64, (addr + size - 1) as u64) {
+        if let Some(entry) = leaf(unit, &domain, page, false)? {
+            unsafe { write_volatile(entry, 0) };
+            unit.flush(entry as usize);
+        }
+    }
+    unit.invalidate_all(Some(domain.id))?;
+    Ok(())
+}
+
+fn interrupt(_: usize) {
+    task::wake(LOGGER.load(Ordering::Relaxed));
+}
+
+/// Logs and clears the faults every unit recorded
+///
+/// - 0x05: Write to a page without write permission
+/// - 0x06: Read from a page without read permission
+fn logger(_: usize) -> usize {
+    loop {
+        task::sleep_until(u64::MAX);
+        for unit in UNITS.read().as_slice() {
+            unit.faults(log_fault);
+        }
+    }
+}
+
+fn log_fault(fault: unit::Fault) {
+    crate::error!(
+        "IOMMU",
+        "Fault ",
+        fault.reason as u64,
+        " on ",
+        if fault.read { "read" } else { "write" },
+        " of ",
+        fault.addr,
+        " from ",
+        (fault.source >> 8) as usize,
+        ":",
+        ((fault.source >> 3) & 0x1F) as usize,
+        ".",
+        (fault.source & 0x7) as usize
+    );
+}
+
+pub fn init() -> Result<(), crate::Error> {
+    if table::find(dmar::SIGNATURE, 0).is_none() {
+        info!("IOMMU", "No DMAR, DMA unrestricted");
+        return Ok(());
+    }
+    dmar::init()?;
+
+    let count = {
+        let mut units = UNITS.write();
+        let count = units.count;
+        for unit in &mut units.units[..count] {
+            unit.init()?;
+        }
+        count
+    };
+    LOGGER.store(task::spawn(logger, 0)?, Ordering::Relaxed);
+    let vector = vector::allocate(1)?;
+    vector::register(vector, interrupt, 0)?;
+    for unit in UNITS.read().as_slice() {
+        unit.set_fault_vector(vector);
+    }
+
+    // Firmware keeps using these, for USB legacy emulation or the framebuffer
+    let reserved = UNITS.read().reserved;
+    for region in reserved.iter().flatten() {
+        if let Some(domain) = domain(region.source)? {
+            map_range(&domain, region.base, region.limit)?;
+        }
+    }
+
+    for unit in UNITS.read().as_slice() {
+        unit.enable()?;
+    }
+    info!("IOMMU", "DMA remapping on ", count, " units");
+    Ok(())
+}
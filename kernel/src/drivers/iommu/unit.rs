@@ -0,0 +1,366 @@
+//! DMA Remapping Hardware Unit
+
+use core::{
+    arch::asm,
+    ptr::{read_volatile, write_volatile},
+};
+
+use crate::io::port;
+
+use super::Error;
+
+/// Microseconds a command may take to show up in the status register
+const TIMEOUT_US: usize = 1_000_000;
+
+/// Bytes per root or context entry
+const ENTRY_SIZE: usize = 16;
+
+/// Global Command, reflected by the same bit of Global Status
+/// - Bit 31: TE for Translation Enable
+/// - Bit 30: SRTP for Set Root Table Pointer
+/// - Bit 27: WBF for Write Buffer Flush, whose status bit clears when done
+const TE: u32 = 1 << 31;
+const SRTP: u32 = 1 << 30;
+const WBF: u32 = 1 << 27;
+
+/// Persistent enables to carry into every Global Command write
+/// - Bit 31: TE
+/// - Bit 26: QIE for Queued Invalidation Enable
+/// - Bit 25: IRE for Interrupt Remapping Enable
+/// - Bit 23: CFI for Compatibility Format Interrupt
+const PERSISTENT: u32 = TE | 1 << 26 | 1 << 25 | 1 << 23;
+
+#[repr(usize)]
+enum Register {
+    /// Capability
+    /// - Bits 0 ..= 2: ND for Number of Domains, 2 ^ (4 + 2 * ND)
+    /// - Bit 4: RWBF for Required Write-Buffer Flushing
+    /// - Bit 7: CM for Caching Mode
+    /// - Bits 8 ..= 12: SAGAW for Supported Adjusted Guest Address Widths
+    ///   - Bit 1: 39-bit, 3-level
+    ///   - Bit 2: 48-bit, 4-level
+    /// - Bits 24 ..= 33: FRO for Fault-recording Register Offset, in 16 bytes
+    /// - Bits 40 ..= 47: NFR for Number of Fault-recording Registers, minus one
+    CAP = 0x08,
+
+    /// Extended Capability
+    /// - Bit 0: C for Page-walk Coherency
+    /// - Bits 8 ..= 17: IRO for IOTLB Register Offset, in 16 bytes
+    ECAP = 0x10,
+
+    /// Global Command
+    GCMD = 0x18,
+
+    /// Global Status
+    GSTS = 0x1C,
+
+    /// Root Table Address
+    /// - Bits 10 ..= 11: TTM for Translation Table Mode, 0b00 for legacy root entries
+    /// - Bits 12 ..= 63: RTA for Root Table Address
+    RTADDR = 0x20,
+
+    /// Context Command
+    /// - Bits 0 ..= 15: DID for Domain-ID
+    /// - Bits 61 ..= 62: CIRG for Context Invalidation Request Granularity
+    ///   - 0b01: Global
+    ///   - 0b10: Domain-selective
+    /// - Bit 63: ICC for Invalidate Context-Cache
+    CCMD = 0x28,
+
+    /// Fault Status
+    /// - Bit 0: PFO for Primary Fault Overflow
+    /// - Bit 1: PPF for Primary Pending Fault
+    /// - Bits 8 ..= 15: FRI for Fault Record Index
+    FSTS = 0x34,
+
+    /// Fault Event Control
+    /// - Bit 31: IM for Interrupt Mask
+    FECTL = 0x38,
+
+    /// Fault Event Data
+    FEDATA = 0x3C,
+
+    /// Fault Event Address
+    FEADDR = 0x40,
+
+    /// Fault Event Upper Address
+    FEUADDR = 0x44,
+}
+
+/// IOTLB Invalidate, at ECAP.IRO + 8
+/// - Bits 32 ..= 47: DID for Domain-ID
+/// - Bit 48: DW for Drain Writes
+/// - Bit 49: DR for Drain Reads
+/// - Bits 60 ..= 61: IIRG for IOTLB Invalidation Request Granularity
+///   - 0b01: Global
+///   - 0b10: Domain-selective
+/// - Bit 63: IVT for Invalidate IOTLB
+const IOTLB: usize = 8;
+const IVT: u64 = 1 << 63;
+
+/// Fault Recording, 128 bits each from CAP.FRO
+/// - Bits 12 ..= 63: FI for Fault Info, the faulting page
+/// - Bits 64 ..= 79: SID for Source Identifier
+/// - Bits 96 ..= 103: FR for Fault Reason
+/// - Bit 126: T1 for Type, set for a read
+/// - Bit 127: F for Fault, write one to clear
+const FAULT_RECORD_SIZE: usize = 16;
+
+#[derive(Clone, Copy)]
+pub struct Fault {
+    pub addr: u64,
+    pub source: u16,
+    pub reason: u8,
+    pub read: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct Unit {
+    addr: usize,
+
+    /// Covers every function no other unit lists
+    pub include_all: bool,
+
+    /// Root table, one entry per bus pointing at a context table
+    root: usize,
+
+    /// Page-table levels, 3 or 4
+    pub levels: usize,
+
+    /// Domain IDs the unit tells apart
+    pub domain_count: usize,
+
+    /// Page walks do not snoop, so table writes are flushed out of the cache
+    coherent: bool,
+
+    /// Not-present entries may be cached, so even new mappings are invalidated
+    caching_mode: bool,
+
+    write_buffer_flush: bool,
+
+    iotlb: usize,
+
+    faults: usize,
+    fault_count: usize,
+}
+impl Unit {
+    pub const fn null() -> Self {
+        Self {
+            addr: 0,
+            include_all: false,
+            root: 0,
+            levels: 0,
+            domain_count: 0,
+            coherent: true,
+            caching_mode: false,
+            write_buffer_flush: false,
+            iotlb: 0,
+            faults: 0,
+            fault_count: 0,
+        }
+    }
+
+    pub fn new(addr: usize, include_all: bool) -> Self {
+        Self {
+            addr,
+            include_all,
+            ..Self::null()
+        }
+    }
+
+    fn read32(&self, offset: usize) -> u32 {
+        unsafe { read_volatile((self.addr + offset) as *const u32) }
+    }
+
+    fn write32(&self, offset: usize, value: u32) {
+        unsafe { write_volatile((self.addr + offset) as *mut u32, value) }
+    }
+
+    fn read64(&self, offset: usize) -> u64 {
+        unsafe { read_volatile((self.addr + offset) as *const u64) }
+    }
+
+    fn write64(&self, offset: usize, value: u64) {
+        unsafe { write_volatile((self.addr + offset) as *mut u64, value) }
+    }
+
+    /// Issues `command` and waits for the status bit it toggles to read `set`
+    fn command(&self, command: u32, status: u32, set: bool) -> Result<(), Error> {
+        let enables = self.read32(Register::GSTS as usize) & PERSISTENT;
+        let value = if set {
+            enables | command
+        } else {
+            enables & !command
+        };
+        self.write32(Register::GCMD as usize, value);
+        for _ in 0..TIMEOUT_US {
+            if (self.read32(Register::GSTS as usize) & status != 0) == set {
+                return Ok(());
+            }
+            port::out_byte(port::POST, 0);
+        }
+        Err(Error::Timeout("Global Command"))
+    }
+
+    /// Waits for bit 63 of a 64-bit invalidation register to clear
+    fn invalidate(&self, offset: usize, value: u64) -> Result<(), Error> {
+        self.write64(offset, value);
+        for _ in 0..TIMEOUT_US {
+            if self.read64(offset) & IVT == 0 {
+                return Ok(());
+            }
+            port::out_byte(port::POST, 0);
+        }
+        Err(Error::Timeout("Invalidation"))
+    }
+
+    /// Reads the capabilities and installs an empty root table, leaving translation off
+    pub fn init(&mut self) -> Result<(), crate::Error> {
+        let cap = self.read64(Register::CAP as usize);
+        let ecap = self.read64(Register::ECAP as usize);
+        let sagaw = (cap >> 8) & 0x1F;
+        self.levels = if sagaw & 1 << 2 != 0 {
+            4
+        } else if sagaw & 1 << 1 != 0 {
+            3
+        } else {
+            return Err(Error::Unsupported("Address Width").into());
+        };
+        self.domain_count = 1 << (4 + 2 * (cap & 0x7));
+        self.coherent = ecap & 1 != 0;
+        self.caching_mode = cap & 1 << 7 != 0;
+        self.write_buffer_flush = cap & 1 << 4 != 0;
+        self.iotlb = ((ecap >> 8) & 0x3FF) as usize * 16;
+        self.faults = ((cap >> 24) & 0x3FF) as usize * 16;
+        self.fault_count = ((cap >> 40) & 0xFF) as usize + 1;
+
+        // Firmware may have left it translating with tables about to be reclaimed
+        if self.read32(Register::GSTS as usize) & TE != 0 {
+            self.command(TE, TE, false)?;
+        }
+
+        self.root = super::table()?;
+        self.write64(Register::RTADDR as usize, self.root as u64);
+        self.command(SRTP, SRTP, true)?;
+        self.invalidate_all(None)?;
+        Ok(())
+    }
+
+    pub fn enable(&self) -> Result<(), Error> {
+        self.command(TE, TE, true)
+    }
+
+    /// Raises fault events on `vector` of this processor
+    pub fn set_fault_vector(&self, vector: u8) {
+        // The ID sits in bits 24 ..= 31 of its register
+        let id = crate::x86_64::apic::lapic::id() >> 24;
+        self.write32(Register::FEDATA as usize, vector as u32);
+        self.write32(Register::FEADDR as usize, 0xFEE << 20 | id << 12);
+        self.write32(Register::FEUADDR as usize, 0);
+        self.write32(Register::FECTL as usize, 0);
+    }
+
+    /// Drains posted writes to tables on units that need it
+    fn flush_write_buffer(&self) -> Result<(), Error> {
+        if self.write_buffer_flush {
+            let enables = self.read32(Register::GSTS as usize) & PERSISTENT;
+            self.write32(Register::GCMD as usize, enables | WBF);
+            for _ in 0..TIMEOUT_US {
+                if self.read32(Register::GSTS as usize) & WBF == 0 {
+                    return Ok(());
+                }
+                port::out_byte(port::POST, 0);
+            }
+            return Err(Error::Timeout("Write Buffer Flush"));
+        }
+        Ok(())
+    }
+
+    /// Context and IOTLB caches of `domain`, or of every domain without one
+    pub fn invalidate_all(&self, domain: Option<u16>) -> Result<(), Error> {
+        self.flush_write_buffer()?;
+        // Global or domain-selective
+        let (granularity, did) = domain.map_or((0b01, 0), |domain| (0b10, domain as u64));
+        self.invalidate(Register::CCMD as usize, IVT | granularity << 61 | did)?;
+        self.invalidate(
+            self.iotlb + IOTLB,
+            IVT | granularity << 60 | 0b11 << 48 | did << 32,
+        )
+    }
+
+    /// Invalidates after filling entries that were not present, which only caching mode caches
+    pub fn invalidate_new(&self, domain: u16) -> Result<(), Error> {
+        if self.caching_mode {
+            self.invalidate_all(Some(domain))
+        } else {
+            self.flush_write_buffer()
+        }
+    }
+
+    /// Points the context entry of `source` at the page tables `table` of `domain`
+    ///
+    /// - Root entry: Bit 0 present, bits 12 ..= 63 context table
+    /// - Context entry
+    ///   - Bit 0: Present
+    ///   - Bits 2 ..= 3: Translation Type, 0b00 for untranslated requests only
+    ///   - Bits 12 ..= 63: Second-level Page Translation Pointer
+    ///   - Bits 64 ..= 66: Address Width, 1 for 3 levels and 2 for 4
+    ///   - Bits 72 ..= 87: Domain Identifier
+    pub fn attach(&self, source: u16, domain: u16, table: usize) -> Result<(), crate::Error> {
+        let root_entry = (self.root + (source >> 8) as usize * ENTRY_SIZE) as *mut u64;
+        let mut context = unsafe { read_volatile(root_entry) };
+        if context & 1 == 0 {
+            context = super::table()? as u64 | 1;
+            unsafe { write_volatile(root_entry, context) };
+            self.flush(root_entry as usize);
+        }
+        let entry =
+            ((context & !0xFFF) as usize + (source & 0xFF) as usize * ENTRY_SIZE) as *mut u64;
+        unsafe {
+            write_volatile(
+                entry.add(1),
+                (self.levels as u64 - 2) | (domain as u64) << 8,
+            );
+            write_volatile(entry, table as u64 | 1);
+        }
+        self.flush(entry as usize);
+        // Not present before, so cached only under caching mode, and then as domain 0
+        if self.caching_mode {
+            self.invalidate_all(None)?;
+        } else {
+            self.flush_write_buffer()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a table entry back to memory if the unit does not snoop
+    pub fn flush(&self, addr: usize) {
+        if !self.coherent {
+            unsafe { asm!("clflush [{}]", "mfence", in(reg) addr) };
+        }
+    }
+
+    /// Takes every pending fault record, clearing each and the overflow
+    pub fn faults(&self, mut f: impl FnMut(Fault)) {
+        let status = self.read32(Register::FSTS as usize);
+        if status & 0b11 == 0 {
+            return;
+        }
+        let first = ((status >> 8) & 0xFF) as usize;
+        for i in 0..self.fault_count {
+            let record = self.faults + (first + i) % self.fault_count * FAULT_RECORD_SIZE;
+            let high = self.read64(record + 8);
+            if high & 1 << 63 == 0 {
+                break;
+            }
+            f(Fault {
+                addr: self.read64(record) & !0xFFF,
+                source: high as u16,
+                reason: (high >> 32) as u8,
+                read: high & 1 << 62 != 0,
+            });
+            self.write32(record + 12, 1 << 31);
+        }
+        self.write32(Register::FSTS as usize, 0b1);
+    }
+}
@@ -1,6 +1,7 @@
 //! Drivers
 
 mod error;
+pub mod iommu;
 pub mod net;
 pub mod pcie;
 pub mod storage;
@@ -8,6 +9,7 @@ pub mod storage;
 pub use error::Error;
 
 pub fn init() -> Result<(), crate::Error> {
+    iommu::init()?;
     pcie::init()?;
     storage::init()?;
     net::init()?;
@@ -4,6 +4,9 @@ pub enum Error {
     InvalidHeaderType,
     InvalidIndex(&'static str),
     InvalidRegisterValue(&'static str),
+
+    /// INTx pin no `_PRT` on the way to the host bridge covers
+    Unrouted,
 }
 impl From<Error> for super::super::Error {
     fn from(err: Error) -> Self {
@@ -28,6 +31,7 @@ impl crate::Output for Error {
                 reg.out();
                 " Value"
             }
+            Error::Unrouted => "INTx Unrouted",
         }
         .out();
     }
@@ -0,0 +1,82 @@
+//! Legacy INTx Interrupt
+//!
+//! For functions without MSI or MSI-X. The pin is followed up through the bridges, swizzled at
+//! each one, until a `_PRT` covers it, and every function wired to the same GSI shares one vector.
+
+use crate::{
+    acpi::{mcfg, prt},
+    debug,
+    mem::Memory,
+    sync::Spinlock,
+    x86_64::{
+        apic::ioapic,
+        idt::vector::{self, Handler},
+    },
+};
+
+use super::{Error, Type0};
+
+const MAX_LINE_COUNT: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Line {
+    gsi: u32,
+    vector: u8,
+}
+
+static LINES: Spinlock<[Option<Line>; MAX_LINE_COUNT]> = Spinlock::new([None; MAX_LINE_COUNT]);
+
+/// Where the INTx pin of the function at `addr` lands
+fn route(addr: usize) -> Result<prt::Route, crate::Error> {
+    let (mut bus, mut device, _) =
+        mcfg::locate(addr).ok_or(Error::InvalidRegisterValue("Configuration Address"))?;
+    let mut pin = match Type0::get_ref(addr).interrupt_pin() {
+        pin @ 1..=4 => pin - 1,
+        _ => return Err(Error::InvalidRegisterValue("Interrupt Pin").into()),
+    };
+    loop {
+        if let Some(route) = prt::resolve(bus, device, pin)? {
+            return Ok(route);
+        }
+        // A bridge maps INTx of its device N to INT((N + x) % 4) of its own
+        let (parent, bridge, _) = super::bridge(bus).ok_or(Error::Unrouted)?;
+        pin = (pin + device) % 4;
+        bus = parent;
+        device = bridge;
+    }
+}
+
+/// Calls `handler` with `context` on the INTx interrupt of the function at `addr` and unmasks it
+///
+/// The line may be shared, so `handler` must check its own device.
+pub fn enable(addr: usize, handler: Handler, context: usize) -> Result<u8, crate::Error> {
+    let route = route(addr)?;
+    let mut lines = LINES.lock();
+    let vector = match lines.iter().flatten().find(|line| line.gsi == route.gsi) {
+        Some(line) => {
+            vector::register(line.vector, handler, context)?;
+            line.vector
+        }
+        None => {
+            let slot = lines
+                .iter_mut()
+                .find(|line| line.is_none())
+                .ok_or(Error::InvalidIndex("INTx Line"))?;
+            let vector = vector::allocate(1)?;
+            // Handler first, as the line may already be asserted
+            vector::register(vector, handler, context)?;
+            ioapic::route(route.gsi, vector, route.polarity, route.trigger_mode)?;
+            *slot = Some(Line {
+                gsi: route.gsi,
+                vector,
+            });
+            vector
+        }
+    };
+    Type0::get_mut(addr).header.set_interrupt(true);
+    debug!(
+        "PCIe",
+        "INTx on GSI ", route.gsi as usize, " vector ", vector as usize
+    );
+    Ok(vector)
+}
@@ -4,11 +4,13 @@ use crate::{acpi::mcfg, mem::Memory};
 
 pub mod capabilities;
 pub mod error;
+pub mod intx;
 mod type0;
 mod type1;
 
 pub use error::Error;
 pub use type0::Type0;
+pub use type1::Type1;
 
 #[repr(C)]
 pub struct Header {
@@ -78,9 +80,16 @@ impl Header {
         (self.header_type & 0b1000_0000) != 0
     }
 
+    pub fn is_bridge(&self) -> bool {
+        self.header_type & 0b111_1111 == 1
+    }
+
     pub fn handle(&self) -> Result<(), crate::Error> {
         match self.header_type & 0b111_1111 {
-            0 => Ok(Type0::get_ref(self as *const _ as usize).handle()),
+            0 => {
+                Type0::get_ref(self as *const _ as usize).handle();
+                Ok(())
+            }
             1 => Ok(()),
             _ => Err(Error::InvalidHeaderType.into()),
         }
@@ -152,6 +161,34 @@ impl BAR {
     }
 }
 
+/// Bridge whose secondary bus is `bus`, as its bus, device and function
+///
+/// Firmware numbers buses depth-first, so the bridge sits on a lower bus.
+pub fn bridge(bus: u8) -> Option<(u8, u8, u8)> {
+    for parent in 0..bus {
+        for device in 0..32 {
+            let Some(addr) = mcfg::address(parent, device, 0) else {
+                break;
+            };
+            let header = Header::get_ref(addr);
+            if !header.is_present() {
+                continue;
+            }
+            for function in 0..(if header.is_multi_function() { 8 } else { 1 }) {
+                let addr = addr + ((function as usize) << 12);
+                let header = Header::get_ref(addr);
+                if header.is_present()
+                    && header.is_bridge()
+                    && Type1::get_ref(addr).secondary_bus() == bus
+                {
+                    return Some((parent, device, function));
+                }
+            }
+        }
+    }
+    None
+}
+
 pub fn init() -> Result<(), crate::Error> {
     mcfg::init()
 }
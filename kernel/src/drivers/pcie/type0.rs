@@ -93,4 +93,10 @@ impl Type0 {
     pub fn p_capabilities(&self) -> usize {
         self.p_capabilities as usize
     }
+
+    /// - 0: None
+    /// - 1 ..= 4: INTA# ..= INTD#
+    pub fn interrupt_pin(&self) -> u8 {
+        self.interrupt_pin
+    }
 }
@@ -1 +1,32 @@
 //! PCI-to-PCI Bridge
+
+use crate::mem::Memory;
+
+use super::Header;
+
+#[repr(C)]
+pub struct Type1 {
+    pub header: Header,
+
+    bar: [super::BAR; 2],
+
+    primary_bus_number: u8,
+
+    /// Bus directly below the bridge
+    secondary_bus_number: u8,
+
+    /// Highest bus below the bridge
+    subordinate_bus_number: u8,
+
+    secondary_latency_timer: u8,
+}
+impl Memory for Type1 {}
+impl Type1 {
+    pub fn secondary_bus(&self) -> u8 {
+        self.secondary_bus_number
+    }
+
+    pub fn subordinate_bus(&self) -> u8 {
+        self.subordinate_bus_number
+    }
+}
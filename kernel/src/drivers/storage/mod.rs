@@ -14,3 +14,8 @@ pub fn init() -> Result<(), crate::Error> {
 pub fn read(start: u64, offset: usize, size: usize) -> Result<usize, crate::Error> {
     nvme::read(start, offset, size)
 }
+
+/// Lets the drives finish writing back before power is cut
+pub fn shutdown() -> Result<(), crate::Error> {
+    nvme::shutdown()
+}
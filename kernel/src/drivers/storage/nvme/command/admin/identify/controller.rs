@@ -2,6 +2,8 @@
 
 use crate::{mem::Memory, types::guid::GUID};
 
+use super::super::super::super::Error;
+
 impl super::super::super::Submission {
     /// - CDW10.CNS: 0x01
     pub fn to_identify_controller_data_structure(&mut self, addr: usize) {
@@ -10,8 +12,18 @@ impl super::super::super::Submission {
     }
 }
 
+impl super::super::super::Completion {
+    pub fn to_identify_controller_data_structure(&self) -> Result<(), Error> {
+        match self.sct() {
+            0x0 => return self.gcs_sc_to_str(),
+            _ => {}
+        }
+        Err(Error::Queue("Unknown Status Code Type"))
+    }
+}
+
 #[repr(C, packed)]
-struct Data {
+pub struct Data {
     /// PCI Vendor ID
     vid: u16,
 
@@ -542,6 +554,12 @@ impl Data {
     fn handle(&self) -> &Self {
         self
     }
+
+    /// Microseconds from a shutdown notification to the controller being ready to lose power
+    /// - None: Not reported
+    pub fn rtd3e(&self) -> Option<u32> {
+        Some(self.rtd3e).filter(|&rtd3e| rtd3e != 0)
+    }
 }
 
 #[repr(C)]
@@ -4,6 +4,7 @@ pub enum Error {
     InvalidAddress(&'static str),
     InvalidRegisterValue(&'static str),
     Queue(&'static str),
+    Timeout(&'static str),
 }
 impl From<Error> for super::super::Error {
     fn from(err: Error) -> Self {
@@ -36,6 +37,10 @@ impl crate::Output for Error {
                 "Queue ".out();
                 msg
             }
+            Error::Timeout(operation) => {
+                operation.out();
+                " Timeout"
+            }
         }
         .out();
     }
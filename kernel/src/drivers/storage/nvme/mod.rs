@@ -3,11 +3,17 @@
 use core::hint::spin_loop;
 
 use crate::{
-    drivers::pcie::{self, capabilities::MSIX},
+    drivers::{
+        iommu,
+        pcie::{self, capabilities::MSIX},
+    },
     find_capabilities,
+    io::port,
     math::Math,
     mem::Memory,
     mem::physical::allocate,
+    sync::Spinlock,
+    x86_64::idt::vector,
 };
 
 mod command;
@@ -17,10 +23,16 @@ mod queue;
 pub use error::Error;
 use queue::Queue;
 
-static mut DEVICE: Device = Device::null();
+/// Shutdown wait when the controller reports no RTD3E
+const DEFAULT_SHUTDOWN_TIMEOUT_US: u64 = 5_000_000;
+
+/// Longest shutdown wait, whatever RTD3E claims
+const MAX_SHUTDOWN_TIMEOUT_US: u64 = 60_000_000;
+
+static DEVICE: Spinlock<Device> = Spinlock::new(Device::null());
 
 pub fn set_config(addr: usize) {
-    unsafe { DEVICE.pcie_addr = addr };
+    DEVICE.lock().pcie_addr = addr;
 }
 
 struct Device {
@@ -36,7 +48,11 @@ struct Device {
     io: Queue,
 
     ns: Namespace,
+
+    /// From RTD3E, the longest a shutdown notification may take
+    shutdown_timeout_us: u64,
 }
+unsafe impl Send for Device {}
 impl Device {
     /// Controller Capabilities
     /// - Bits 0 ..= 15: MQES for Maximum Queue Entries Supported
@@ -335,6 +351,7 @@ impl Device {
                 lba_count: 0,
                 lba_size: 0,
             },
+            shutdown_timeout_us: DEFAULT_SHUTDOWN_TIMEOUT_US,
         }
     }
 
@@ -384,15 +401,15 @@ impl Device {
             spin_loop();
         }
 
-        // MSI-X
-        {
-            if self.msi_x.addr == 0 {
-                return Err(Error::InvalidAddress("MSI-X").into());
-            }
+        // MSI-X, or the INTx pin without it
+        if self.msi_x.addr == 0 {
+            pcie::intx::enable(self.pcie_addr, intx_interrupt, self.addr)?;
+        } else {
             self.msi_x.disable();
             self.msi_x.set_tables(pcie.bar(self.msi_x.table_bir()?));
-            self.msi_x
-                .configure(0, crate::x86_64::idt::Interrupt::NVMe as u8)?;
+            let vector = vector::allocate(1)?;
+            vector::register(vector, interrupt, 0)?;
+            self.msi_x.configure(0, vector)?;
             self.write(Self::INTMC, 0xFFFFFFFF);
             self.msi_x.enable();
         }
@@ -407,12 +424,22 @@ impl Device {
             admin_size,
             admin_size,
         )?;
+        let asq = iommu::map(
+            self.pcie_addr,
+            asq,
+            admin_size as usize * size_of::<command::Submission>(),
+        )?;
+        let acq = iommu::map(
+            self.pcie_addr,
+            acq,
+            admin_size as usize * size_of::<command::Completion>(),
+        )?;
         self.write(
             Self::AQA,
             ((admin_size as u64 - 1) << 16) | (admin_size as u64 - 1),
         );
-        self.write(Self::ASQ, asq as u64);
-        self.write(Self::ACQ, acq as u64);
+        self.write(Self::ASQ, asq);
+        self.write(Self::ACQ, acq);
         self.write(Self::CC, {
             let cap = self.read(Self::CAP);
             1 | ({
@@ -451,14 +478,40 @@ impl Device {
             spin_loop();
         }
 
+        // Identify Controller
+        {
+            use command::admin::identify;
+
+            let data = identify::controller::Data::new()?;
+            let data_size = size_of::<identify::controller::Data>();
+            let data_iova = iommu::map(self.pcie_addr, data.addr(), data_size)?;
+            self.admin
+                .next_submission()
+                .to_identify_controller_data_structure(data_iova as usize);
+            self.admin.doorbell_submission(1)?;
+            self.admin
+                .next_completion()
+                .to_identify_controller_data_structure()?;
+            self.admin.doorbell_completion();
+
+            self.shutdown_timeout_us = data.rtd3e().map_or(DEFAULT_SHUTDOWN_TIMEOUT_US, |rtd3e| {
+                (rtd3e as u64).min(MAX_SHUTDOWN_TIMEOUT_US)
+            });
+
+            iommu::unmap(self.pcie_addr, data.addr(), data_size)?;
+            data.delete()?;
+        }
+
         // Identify Namespace
         {
             use command::admin::identify;
 
             let list = identify::active_namespace_id_list::List::new()?;
+            let list_size = size_of::<identify::active_namespace_id_list::List>();
+            let list_iova = iommu::map(self.pcie_addr, list.addr(), list_size)?;
             self.admin
                 .next_submission()
-                .to_active_namespace_id_list(list.addr());
+                .to_active_namespace_id_list(list_iova as usize);
             self.admin.doorbell_submission(1)?;
             self.admin.next_completion().to_active_namespace_id_list()?;
             self.admin.doorbell_completion();
@@ -468,9 +521,11 @@ impl Device {
             }
             self.ns.id = list.0[0];
             let data = identify::namespace::Data::new()?;
+            let data_size = size_of::<identify::namespace::Data>();
+            let data_iova = iommu::map(self.pcie_addr, data.addr(), data_size)?;
             self.admin
                 .next_submission()
-                .to_identify_namespace_data_structure(data.addr(), self.ns.id);
+                .to_identify_namespace_data_structure(data_iova as usize, self.ns.id);
             self.admin.doorbell_submission(1)?;
             self.admin
                 .next_completion()
@@ -479,6 +534,8 @@ impl Device {
 
             (self.ns.lba_count, self.ns.lba_size) = data.handle()?;
 
+            iommu::unmap(self.pcie_addr, data.addr(), data_size)?;
+            iommu::unmap(self.pcie_addr, list.addr(), list_size)?;
             data.delete()?;
             list.delete()?;
         }
@@ -489,19 +546,24 @@ impl Device {
             let (iosq, iocq) = self
                 .io
                 .init(self.addr + Self::DOORBELL, self.dstrd, size, size)?;
+            let iosq = iommu::map(
+                self.pcie_addr,
+                iosq,
+                size as usize * size_of::<command::Submission>(),
+            )?;
+            let iocq = iommu::map(
+                self.pcie_addr,
+                iocq,
+                size as usize * size_of::<command::Completion>(),
+            )?;
             let id = self.io.id() as u32;
 
-            self.admin.next_submission().to_create_io_completion_queue(
-                iocq as u64,
-                id,
-                size as u32,
-                0,
-            );
-            self.admin.next_submission().to_create_io_submission_queue(
-                iosq as u64,
-                id,
-                size as u32,
-            );
+            self.admin
+                .next_submission()
+                .to_create_io_completion_queue(iocq, id, size as u32, 0);
+            self.admin
+                .next_submission()
+                .to_create_io_submission_queue(iosq, id, size as u32);
             self.admin.doorbell_submission(2)?;
             self.admin
                 .next_completion()
@@ -515,6 +577,24 @@ impl Device {
         Ok(())
     }
 
+    /// Sends a normal shutdown notification and waits for the controller to flush its caches
+    fn shutdown(&mut self) -> Result<(), crate::Error> {
+        if self.addr == 0 {
+            return Ok(());
+        }
+        let cc = self.read(Self::CC);
+        self.write(Self::CC, (cc & !(0b11 << 14)) | (0b01 << 14));
+
+        // Counted in port writes since interrupts may be off
+        for _ in 0..self.shutdown_timeout_us {
+            if (self.read(Self::CSTS) >> 2) & 0b11 == 0b10 {
+                return Ok(());
+            }
+            port::out_byte(port::POST, 0);
+        }
+        Err(Error::Timeout("Shutdown").into())
+    }
+
     fn read_lba(
         &mut self,
         mut start: u64,
@@ -526,15 +606,18 @@ impl Device {
         size += offset;
 
         let addr = allocate(size)?;
+        let iova = iommu::map(self.pcie_addr, addr, size)?;
         self.io.next_submission().to_read(
             self.ns.id,
-            addr as u64,
+            iova,
             start,
-            ((size + self.ns.lba_size - 1) / self.ns.lba_size) as u32,
+            size.div_ceil(self.ns.lba_size) as u32,
         );
         self.io.doorbell_submission(1)?;
-        self.io.next_completion().to_read()?;
+        let result = self.io.next_completion().to_read();
         self.io.doorbell_completion();
+        iommu::unmap(self.pcie_addr, addr, size)?;
+        result?;
         Ok(addr + offset)
     }
 }
@@ -546,10 +629,23 @@ struct Namespace {
     lba_size: usize,
 }
 
+/// Completions are polled, so the vector only needs acknowledging
+fn interrupt(_: usize) {}
+
+/// Completions are polled, so the controller is masked rather than left to hold the level-triggered
+/// line asserted until they are reaped
+fn intx_interrupt(addr: usize) {
+    unsafe { ((addr + Device::INTMS) as *mut u32).write_volatile(u32::MAX) };
+}
+
 pub fn init() -> Result<(), crate::Error> {
-    unsafe { (*(&raw mut DEVICE)).init() }
+    DEVICE.lock().init()
 }
 
 pub fn read(start: u64, offset: usize, size: usize) -> Result<usize, crate::Error> {
-    unsafe { (*(&raw mut DEVICE)).read_lba(start, offset, size) }
+    DEVICE.lock().read_lba(start, offset, size)
+}
+
+pub fn shutdown() -> Result<(), crate::Error> {
+    DEVICE.lock().shutdown()
 }
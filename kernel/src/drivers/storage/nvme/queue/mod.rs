@@ -1,12 +1,14 @@
 //! Queue
 
+use core::sync::atomic::{AtomicU16, Ordering};
+
 mod completion;
 mod submission;
 
 use completion::Completion;
 use submission::Submission;
 
-static mut ID_COUNTER: u16 = 0;
+static ID_COUNTER: AtomicU16 = AtomicU16::new(0);
 
 pub struct Queue {
     id: u16,
@@ -33,10 +35,7 @@ impl Queue {
         submission_size: u16,
         completion_size: u16,
     ) -> Result<(usize, usize), crate::Error> {
-        unsafe {
-            self.id = ID_COUNTER;
-            ID_COUNTER += 1;
-        };
+        self.id = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
         Ok((
             self.submission.init(
                 submission_size,
@@ -0,0 +1,43 @@
+//! Error
+
+pub enum Error {
+    InvalidAddress(usize),
+    InvalidHeader(&'static str),
+    InvalidPath,
+    InvalidRelocation(u32),
+    InvalidSegment(usize),
+    InvalidSize,
+    InvalidStack,
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::ELF(err)
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "ELF ".out();
+        match self {
+            Error::InvalidAddress(addr) => {
+                (*addr as u64).out();
+                " Address"
+            }
+            Error::InvalidHeader(field) => {
+                field.out();
+                " in Header"
+            }
+            Error::InvalidPath => "File Not Found",
+            Error::InvalidRelocation(type_) => {
+                (*type_ as usize).out();
+                " Relocation Type"
+            }
+            Error::InvalidSegment(index) => {
+                index.out();
+                " Segment"
+            }
+            Error::InvalidSize => "File Size",
+            Error::InvalidStack => "Arguments Overflow",
+        }
+        .out();
+    }
+}
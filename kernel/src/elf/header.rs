@@ -0,0 +1,155 @@
+//! Header
+
+/// ELF Header
+#[repr(C)]
+pub struct Header {
+    /// - Bytes 0 ..= 3: Magic as 0x7F "ELF"
+    /// - Byte 4: Class
+    ///   - 1: 32-bit
+    ///   - 2: 64-bit
+    /// - Byte 5: Data encoding
+    ///   - 1: Little endian
+    ///   - 2: Big endian
+    /// - Byte 6: Version as 1
+    /// - Byte 7: OS ABI
+    /// - Bytes 8 ..= 15: Reserved
+    pub ident: [u8; 16],
+
+    /// - 0: None
+    /// - 1: Relocatable
+    /// - 2: Executable
+    /// - 3: Shared object
+    /// - 4: Core
+    pub type_: u16,
+
+    /// - 0x3E: AMD x86-64
+    pub machine: u16,
+
+    pub version: u32,
+
+    pub entry: u64,
+
+    /// File offset of the program header table
+    pub phoff: u64,
+
+    /// File offset of the section header table
+    pub shoff: u64,
+
+    pub flags: u32,
+
+    pub ehsize: u16,
+
+    /// Size of each program header
+    pub phentsize: u16,
+
+    /// Count of program headers
+    pub phnum: u16,
+
+    pub shentsize: u16,
+    pub shnum: u16,
+    pub shstrndx: u16,
+}
+impl Header {
+    pub const EXECUTABLE: u16 = 2;
+    pub const SHARED_OBJECT: u16 = 3;
+
+    pub fn validate(&self) -> Result<(), super::Error> {
+        use super::Error::InvalidHeader;
+
+        if self.ident[0..4] != *b"\x7FELF" {
+            return Err(InvalidHeader("Magic"));
+        }
+        if self.ident[4] != 2 {
+            return Err(InvalidHeader("Class"));
+        }
+        if self.ident[5] != 1 {
+            return Err(InvalidHeader("Data Encoding"));
+        }
+        if self.ident[6] != 1 || self.version != 1 {
+            return Err(InvalidHeader("Version"));
+        }
+        if !matches!(self.type_, Self::EXECUTABLE | Self::SHARED_OBJECT) {
+            return Err(InvalidHeader("Type"));
+        }
+        if self.machine != 0x3E {
+            return Err(InvalidHeader("Machine"));
+        }
+        if self.phentsize as usize != size_of::<ProgramHeader>() {
+            return Err(InvalidHeader("Program Header Size"));
+        }
+        Ok(())
+    }
+}
+
+#[repr(C)]
+pub struct ProgramHeader {
+    /// - 0: Null
+    /// - 1: Load
+    /// - 2: Dynamic
+    /// - 3: Interpreter
+    /// - 4: Note
+    /// - 6: Program header table
+    /// - 7: Thread-local storage
+    pub type_: u32,
+
+    /// - Bit 0: Execute
+    /// - Bit 1: Write
+    /// - Bit 2: Read
+    pub flags: u32,
+
+    /// File offset of the segment
+    pub offset: u64,
+
+    pub vaddr: u64,
+    pub paddr: u64,
+
+    /// Bytes of the segment in the file
+    pub filesz: u64,
+
+    /// Bytes of the segment in memory, the part beyond `filesz` being zeroed
+    pub memsz: u64,
+
+    pub align: u64,
+}
+impl ProgramHeader {
+    pub const LOAD: u32 = 1;
+    pub const DYNAMIC: u32 = 2;
+    pub const INTERPRETER: u32 = 3;
+    pub const PHDR: u32 = 6;
+
+    pub const EXECUTE: u32 = 1 << 0;
+    pub const WRITE: u32 = 1 << 1;
+}
+
+/// Entry of the dynamic section
+#[repr(C)]
+pub struct Dynamic {
+    /// - 0: Null
+    /// - 7: RELA table address
+    /// - 8: RELA table size
+    /// - 9: RELA entry size
+    pub tag: i64,
+
+    pub value: u64,
+}
+impl Dynamic {
+    pub const NULL: i64 = 0;
+    pub const RELA: i64 = 7;
+    pub const RELASZ: i64 = 8;
+    pub const RELAENT: i64 = 9;
+}
+
+#[repr(C)]
+pub struct Rela {
+    pub offset: u64,
+
+    /// - Bits 0 ..= 31: Type
+    /// - Bits 32 ..= 63: Symbol index
+    pub info: u64,
+
+    pub addend: i64,
+}
+impl Rela {
+    pub const NONE: u32 = 0;
+    pub const RELATIVE: u32 = 8;
+}
@@ -0,0 +1,297 @@
+//! Executable and Linkable Format
+
+use core::{mem::MaybeUninit, slice::from_raw_parts_mut};
+
+use crate::{
+    fs::{self, File},
+    mem::{
+        PAGE_SIZE,
+        paging::{self, AddressSpace},
+    },
+    task,
+};
+
+mod error;
+mod header;
+
+pub use error::Error;
+use header::{Dynamic, Header, ProgramHeader, Rela};
+
+/// Load address of position independent executables
+const PIE_BASE: usize = paging::USER_START;
+
+const STACK_TOP: usize = paging::USER_END - PAGE_SIZE;
+const STACK_SIZE: usize = 0x10000;
+
+const MAX_ARG_COUNT: usize = 32;
+
+/// Auxiliary vector types
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_BASE: u64 = 7;
+const AT_ENTRY: u64 = 9;
+
+/// Loads the executable at `path` into a fresh address space and starts it as a new task
+pub fn exec(path: &str, argv: &[&str], envp: &[&str]) -> Result<usize, crate::Error> {
+    let file = fs::open(path)?.ok_or(Error::InvalidPath)?;
+    let header: Header = read(&file, 0)?;
+    header.validate()?;
+
+    let mut address_space = AddressSpace::new()?;
+    let (err, address_space) = match load(&file, &header, &mut address_space, argv, envp) {
+        Ok((rip, rsp)) => match task::spawn_user(address_space, rip, rsp) {
+            Ok(id) => return Ok(id),
+            Err(failure) => failure,
+        },
+        Err(err) => (err, address_space),
+    };
+    address_space.delete()?;
+    Err(err)
+}
+
+/// Returns the entry point and the initial stack pointer
+fn load(
+    file: &File,
+    header: &Header,
+    address_space: &mut AddressSpace,
+    argv: &[&str],
+    envp: &[&str],
+) -> Result<(usize, usize), crate::Error> {
+    let base = match header.type_ {
+        Header::SHARED_OBJECT => PIE_BASE,
+        _ => 0,
+    };
+
+    let mut phdr = 0;
+    let mut dynamic = None;
+    for i in 0..header.phnum as usize {
+        let ph: ProgramHeader = read(file, header.phoff as usize + i * size_of::<ProgramHeader>())?;
+        match ph.type_ {
+            ProgramHeader::LOAD => {
+                load_segment(file, &ph, base, address_space, i)?;
+                if phdr == 0 && ph.offset == 0 {
+                    phdr = base + ph.vaddr as usize + header.phoff as usize;
+                }
+            }
+            ProgramHeader::DYNAMIC => dynamic = Some(base + ph.vaddr as usize),
+            ProgramHeader::INTERPRETER => return Err(Error::InvalidSegment(i).into()),
+            ProgramHeader::PHDR => phdr = base + ph.vaddr as usize,
+            _ => {}
+        }
+    }
+    if let Some(dynamic) = dynamic {
+        relocate(address_space, base, dynamic)?;
+    }
+
+    let entry = base + header.entry as usize;
+    let rsp = stack(
+        address_space,
+        argv,
+        envp,
+        &[
+            (AT_PHDR, phdr as u64),
+            (AT_PHENT, size_of::<ProgramHeader>() as u64),
+            (AT_PHNUM, header.phnum as u64),
+            (AT_PAGESZ, PAGE_SIZE as u64),
+            (AT_BASE, 0),
+            (AT_ENTRY, entry as u64),
+            (AT_NULL, 0),
+        ],
+    )?;
+    Ok((entry, rsp))
+}
+
+fn load_segment(
+    file: &File,
+    ph: &ProgramHeader,
+    base: usize,
+    address_space: &mut AddressSpace,
+    index: usize,
+) -> Result<(), crate::Error> {
+    let start = base + ph.vaddr as usize;
+    let end = start.checked_add(ph.memsz as usize);
+    let end = match end {
+        Some(end) if ph.filesz <= ph.memsz && start >= paging::USER_START => end,
+        _ => return Err(Error::InvalidSegment(index).into()),
+    };
+    if end > STACK_TOP - STACK_SIZE {
+        return Err(Error::InvalidSegment(index).into());
+    }
+
+    let mut flags = paging::USER;
+    if ph.flags & ProgramHeader::WRITE != 0 {
+        flags |= paging::WRITABLE;
+    }
+    if ph.flags & ProgramHeader::EXECUTE == 0 {
+        flags |= paging::EXECUTE_DISABLE;
+    }
+    for page in (start & !(PAGE_SIZE - 1)..end).step_by(PAGE_SIZE) {
+        match address_space.flags(page) {
+            None => address_space.allocate(page, PAGE_SIZE, flags)?,
+            // Segments sharing a page get the union of their permissions
+            Some(old) => address_space.protect(
+                page,
+                PAGE_SIZE,
+                (old | flags) & !paging::EXECUTE_DISABLE | old & flags & paging::EXECUTE_DISABLE,
+            )?,
+        }
+    }
+
+    let mut done = 0;
+    while done < ph.filesz as usize {
+        let addr = start + done;
+        let len = (PAGE_SIZE - addr % PAGE_SIZE).min(ph.filesz as usize - done);
+        let phys = address_space
+            .translate(addr)
+            .ok_or(Error::InvalidAddress(addr))?;
+        let buf = unsafe { from_raw_parts_mut(phys as *mut u8, len) };
+        if file.read(ph.offset as usize + done, buf)? != len {
+            return Err(Error::InvalidSize.into());
+        }
+        done += len;
+    }
+    Ok(())
+}
+
+/// Applies the RELA relocations of a static PIE loaded at `base`
+fn relocate(address_space: &AddressSpace, base: usize, dynamic: usize) -> Result<(), Error> {
+    let mut rela = 0;
+    let mut rela_size = 0;
+    let mut rela_entry_size = size_of::<Rela>();
+    for i in 0.. {
+        let entry: Dynamic = read_user(address_space, dynamic + i * size_of::<Dynamic>())?;
+        match entry.tag {
+            Dynamic::NULL => break,
+            Dynamic::RELA => rela = base + entry.value as usize,
+            Dynamic::RELASZ => rela_size = entry.value as usize,
+            Dynamic::RELAENT => rela_entry_size = entry.value as usize,
+            _ => {}
+        }
+    }
+    if rela_entry_size < size_of::<Rela>() {
+        return Err(Error::InvalidHeader("RELA Entry Size"));
+    }
+
+    for offset in (0..rela_size).step_by(rela_entry_size) {
+        let entry: Rela = read_user(address_space, rela + offset)?;
+        match entry.info as u32 {
+            Rela::NONE => {}
+            Rela::RELATIVE => write_user(
+                address_space,
+                base + entry.offset as usize,
+                &(base as u64)
+                    .wrapping_add(entry.addend as u64)
+                    .to_le_bytes(),
+            )?,
+            type_ => return Err(Error::InvalidRelocation(type_)),
+        }
+    }
+    Ok(())
+}
+
+/// Lays out argc, argv, envp and auxv as the System V ABI expects and returns the RSP
+fn stack(
+    address_space: &mut AddressSpace,
+    argv: &[&str],
+    envp: &[&str],
+    auxv: &[(u64, u64)],
+) -> Result<usize, crate::Error> {
+    if argv.len() > MAX_ARG_COUNT || envp.len() > MAX_ARG_COUNT {
+        return Err(Error::InvalidStack.into());
+    }
+    let bottom = STACK_TOP - STACK_SIZE;
+    address_space.allocate(
+        bottom,
+        STACK_SIZE,
+        paging::USER | paging::WRITABLE | paging::EXECUTE_DISABLE,
+    )?;
+
+    let mut sp = STACK_TOP;
+    let mut push_strings = |strings: &[&str], pointers: &mut [u64]| -> Result<(), Error> {
+        for (string, pointer) in strings.iter().zip(pointers.iter_mut()) {
+            sp = sp
+                .checked_sub(string.len() + 1)
+                .filter(|&sp| sp >= bottom)
+                .ok_or(Error::InvalidStack)?;
+            write_user(address_space, sp, string.as_bytes())?;
+            write_user(address_space, sp + string.len(), &[0])?;
+            *pointer = sp as u64;
+        }
+        Ok(())
+    };
+    let mut argv_pointers = [0u64; MAX_ARG_COUNT];
+    let mut envp_pointers = [0u64; MAX_ARG_COUNT];
+    push_strings(argv, &mut argv_pointers)?;
+    push_strings(envp, &mut envp_pointers)?;
+
+    let words = 1 + argv.len() + 1 + envp.len() + 1 + auxv.len() * 2;
+    sp = (sp & !0xF)
+        .checked_sub(words * 8)
+        .map(|sp| sp & !0xF)
+        .filter(|&sp| sp >= bottom)
+        .ok_or(Error::InvalidStack)?;
+
+    let mut cursor = sp;
+    let mut push = |value: u64| -> Result<(), Error> {
+        write_user(address_space, cursor, &value.to_le_bytes())?;
+        cursor += 8;
+        Ok(())
+    };
+    push(argv.len() as u64)?;
+    argv_pointers[..argv.len()]
+        .iter()
+        .try_for_each(|&pointer| push(pointer))?;
+    push(0)?;
+    envp_pointers[..envp.len()]
+        .iter()
+        .try_for_each(|&pointer| push(pointer))?;
+    push(0)?;
+    for &(type_, value) in auxv {
+        push(type_)?;
+        push(value)?;
+    }
+    Ok(sp)
+}
+
+fn read<T>(file: &File, offset: usize) -> Result<T, crate::Error> {
+    let mut value = MaybeUninit::<T>::zeroed();
+    let buf = unsafe { from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    if file.read(offset, buf)? != size_of::<T>() {
+        return Err(Error::InvalidSize.into());
+    }
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Copies `bytes` to `addr` of an address space that need not be active
+fn write_user(address_space: &AddressSpace, addr: usize, bytes: &[u8]) -> Result<(), Error> {
+    let mut done = 0;
+    while done < bytes.len() {
+        let addr = addr + done;
+        let len = (PAGE_SIZE - addr % PAGE_SIZE).min(bytes.len() - done);
+        let phys = address_space
+            .translate(addr)
+            .ok_or(Error::InvalidAddress(addr))?;
+        unsafe { core::ptr::copy_nonoverlapping(bytes[done..].as_ptr(), phys as *mut u8, len) };
+        done += len;
+    }
+    Ok(())
+}
+
+fn read_user<T>(address_space: &AddressSpace, addr: usize) -> Result<T, Error> {
+    let mut value = MaybeUninit::<T>::zeroed();
+    let buf = unsafe { from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    let mut done = 0;
+    while done < buf.len() {
+        let addr = addr + done;
+        let len = (PAGE_SIZE - addr % PAGE_SIZE).min(buf.len() - done);
+        let phys = address_space
+            .translate(addr)
+            .ok_or(Error::InvalidAddress(addr))?;
+        unsafe { core::ptr::copy_nonoverlapping(phys as *const u8, buf[done..].as_mut_ptr(), len) };
+        done += len;
+    }
+    Ok(unsafe { value.assume_init() })
+}
@@ -3,8 +3,13 @@
 pub enum Error {
     ACPI(crate::acpi::Error),
     Drivers(crate::drivers::Error),
+    ELF(crate::elf::Error),
     FS(crate::fs::Error),
+    Log(crate::log::Error),
     Mem(crate::mem::Error),
+    SMBIOS(crate::smbios::Error),
+    Task(crate::task::Error),
+    UEFI(crate::uefi::Error),
     X86_64(crate::x86_64::Error),
 }
 impl crate::Output for Error {
@@ -13,8 +18,13 @@ impl crate::Output for Error {
         match self {
             Error::ACPI(e) => e.out(),
             Error::Drivers(e) => e.out(),
+            Error::ELF(e) => e.out(),
             Error::FS(e) => e.out(),
+            Error::Log(e) => e.out(),
             Error::Mem(e) => e.out(),
+            Error::SMBIOS(e) => e.out(),
+            Error::Task(e) => e.out(),
+            Error::UEFI(e) => e.out(),
             Error::X86_64(e) => e.out(),
         }
         ".\n".out();
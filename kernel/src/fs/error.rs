@@ -1,7 +1,10 @@
 //! Error
 
 pub enum Error {
+    InvalidCluster(usize),
+    InvalidCount,
     InvalidFileSystem(usize, usize),
+    InvalidName,
 }
 impl From<Error> for crate::Error {
     fn from(err: Error) -> Self {
@@ -12,12 +15,18 @@ impl crate::Output for Error {
     fn out(&self) {
         "FileSystem".out();
         match self {
+            Error::InvalidCluster(cluster) => {
+                " Cluster ".out();
+                cluster.out();
+            }
+            Error::InvalidCount => " Volume Count Overflow".out(),
             Error::InvalidFileSystem(start, end) => {
                 " Type from LBA ".out();
                 start.out();
                 " to LBA ".out();
                 end.out();
             }
+            Error::InvalidName => " Name".out(),
         }
     }
 }
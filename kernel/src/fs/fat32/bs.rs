@@ -60,17 +60,17 @@ impl BS {
         self.bpb.sec_per_clus as usize * self.sector_bytes()
     }
 
-    fn data_sector_offset(&self) -> usize {
-        (self.bpb.rsvd_sec_cnt as usize + self.bpb.num_fats as usize * self.bpb.fat_sz_32 as usize)
-            * self.sector_bytes()
+    pub fn fat_offset(&self) -> usize {
+        self.bpb.rsvd_sec_cnt as usize * self.sector_bytes()
     }
 
-    fn cluster_offset(&self, cluster: usize) -> usize {
-        self.data_sector_offset() + (cluster - 2) * self.cluster_bytes()
+    pub fn data_offset(&self) -> usize {
+        self.fat_offset()
+            + self.bpb.num_fats as usize * self.bpb.fat_sz_32 as usize * self.sector_bytes()
     }
 
-    pub fn root_offset(&self) -> usize {
-        self.cluster_offset(self.bpb.root_clus as usize)
+    pub fn root_cluster(&self) -> usize {
+        self.bpb.root_clus as usize
     }
 }
 
@@ -66,10 +66,23 @@ pub struct Entry {
     file_size: u32,
 }
 impl Entry {
+    pub fn null() -> Self {
+        unsafe { core::mem::zeroed() }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self as *mut _ as *mut u8, size_of::<Self>()) }
+    }
+
     pub fn name(&self) -> &[u8; 11] {
         &self.name
     }
 
+    /// Read only, Hidden, System and Volume ID all set
+    pub fn is_long_name(&self) -> bool {
+        self.attr & 0b1111 == 0b1111
+    }
+
     pub fn is_dir(&self) -> bool {
         self.attr & 0b1_0000 != 0
     }
@@ -77,4 +90,8 @@ impl Entry {
     pub fn first_cluster(&self) -> usize {
         ((self.fst_clus_hi as usize) << 16) | self.fst_clus_lo as usize
     }
+
+    pub fn file_size(&self) -> usize {
+        self.file_size as usize
+    }
 }
@@ -1,6 +1,13 @@
 //! File Allocation Table 32
 
-use crate::{io::text::Output, mem::Memory};
+use crate::{
+    drivers::storage::read,
+    info,
+    mem::{Memory, physical::deallocate},
+    sync::Spinlock,
+};
+
+use super::Error;
 
 mod bs;
 mod directory;
@@ -10,9 +17,175 @@ use bs::BS;
 use directory::Entry;
 use fsinfo::FSI;
 
-pub fn handle(start: u64) -> Result<bool, crate::Error> {
-    use crate::drivers::storage::read;
+const MAX_VOLUME_COUNT: usize = 8;
+
+static VOLUMES: Spinlock<[Option<Volume>; MAX_VOLUME_COUNT]> =
+    Spinlock::new([None; MAX_VOLUME_COUNT]);
+
+#[derive(Clone, Copy)]
+pub struct Volume {
+    /// LBA of the Boot Sector
+    start: u64,
+
+    sector_bytes: usize,
+    cluster_bytes: usize,
+
+    /// Byte offset of the first FAT
+    fat_offset: usize,
+
+    /// Byte offset of cluster 2
+    data_offset: usize,
+
+    root_cluster: usize,
+}
+impl Volume {
+    /// Copies `buf.len()` bytes from the byte `offset` of the volume one sector at a time
+    fn read(&self, mut offset: usize, buf: &mut [u8]) -> Result<(), crate::Error> {
+        let mut done = 0;
+        while done < buf.len() {
+            let sector_offset = offset % self.sector_bytes;
+            let len = (self.sector_bytes - sector_offset).min(buf.len() - done);
+            let addr = read(self.start, offset - sector_offset, self.sector_bytes)?;
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    (addr + sector_offset) as *const u8,
+                    buf[done..].as_mut_ptr(),
+                    len,
+                )
+            };
+            deallocate(addr)?;
+            done += len;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    fn cluster_offset(&self, cluster: usize) -> usize {
+        self.data_offset + (cluster - 2) * self.cluster_bytes
+    }
+
+    /// - None: End of chain
+    fn next_cluster(&self, cluster: usize) -> Result<Option<usize>, crate::Error> {
+        let mut entry = [0u8; 4];
+        self.read(self.fat_offset + cluster * 4, &mut entry)?;
+        match u32::from_le_bytes(entry) as usize & 0x0FFF_FFFF {
+            0 | 1 | 0x0FFF_FFF7.. => Ok(None),
+            next => Ok(Some(next)),
+        }
+    }
+
+    /// Searches the directory starting at `cluster` for the short name `name`
+    fn find(&self, mut cluster: usize, name: &[u8; 11]) -> Result<Option<Entry>, crate::Error> {
+        loop {
+            for i in 0..self.cluster_bytes / size_of::<Entry>() {
+                let mut entry = Entry::null();
+                self.read(
+                    self.cluster_offset(cluster) + i * size_of::<Entry>(),
+                    entry.as_bytes_mut(),
+                )?;
+                match entry.name()[0] {
+                    0x00 => return Ok(None),
+                    0xE5 => continue,
+                    _ if entry.is_long_name() => continue,
+                    _ if entry.name() == name => return Ok(Some(entry)),
+                    _ => {}
+                }
+            }
+            cluster = match self.next_cluster(cluster)? {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+        }
+    }
+
+    /// Resolves an absolute path such as `/dir/file.txt`
+    fn open(&self, path: &str) -> Result<Option<File>, crate::Error> {
+        let mut cluster = self.root_cluster;
+        let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            let Some(entry) = self.find(cluster, &short_name(component)?)? else {
+                return Ok(None);
+            };
+            if components.peek().is_none() {
+                return Ok((!entry.is_dir()).then(|| File {
+                    volume: *self,
+                    first_cluster: entry.first_cluster(),
+                    size: entry.file_size(),
+                }));
+            }
+            if !entry.is_dir() {
+                return Ok(None);
+            }
+            cluster = entry.first_cluster();
+        }
+        Ok(None)
+    }
+}
+
+/// Converts `name.ext` to the padded upper-case 8.3 form
+fn short_name(component: &str) -> Result<[u8; 11], Error> {
+    let (name, ext) = component.rsplit_once('.').unwrap_or((component, ""));
+    if name.is_empty() || name.len() > 8 || ext.len() > 3 || !component.is_ascii() {
+        return Err(Error::InvalidName);
+    }
+    let mut short = [b' '; 11];
+    short[..name.len()].copy_from_slice(name.as_bytes());
+    short[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    short.make_ascii_uppercase();
+    Ok(short)
+}
+
+#[derive(Clone, Copy)]
+pub struct File {
+    volume: Volume,
+
+    first_cluster: usize,
+
+    size: usize,
+}
+impl File {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the count of bytes copied from `offset`, which is short only at the end of the file
+    pub fn read(&self, offset: usize, buf: &mut [u8]) -> Result<usize, crate::Error> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let len = buf.len().min(self.size - offset);
+        let cluster_bytes = self.volume.cluster_bytes;
+
+        let mut cluster = self.first_cluster;
+        for _ in 0..offset / cluster_bytes {
+            cluster = self
+                .volume
+                .next_cluster(cluster)?
+                .ok_or(Error::InvalidCluster(cluster))?;
+        }
+
+        let mut done = 0;
+        let mut cluster_offset = offset % cluster_bytes;
+        loop {
+            let n = (cluster_bytes - cluster_offset).min(len - done);
+            self.volume.read(
+                self.volume.cluster_offset(cluster) + cluster_offset,
+                &mut buf[done..done + n],
+            )?;
+            done += n;
+            if done == len {
+                return Ok(len);
+            }
+            cluster_offset = 0;
+            cluster = self
+                .volume
+                .next_cluster(cluster)?
+                .ok_or(Error::InvalidCluster(cluster))?;
+        }
+    }
+}
 
+pub fn handle(start: u64) -> Result<bool, crate::Error> {
     let bs = BS::get_ref(read(start, 0, size_of::<BS>())?);
     let fsi = match bs.validate() {
         0 => return Ok(false),
@@ -22,23 +195,36 @@ pub fn handle(start: u64) -> Result<bool, crate::Error> {
             size_of::<FSI>(),
         )?),
     };
-    let entry_per_cluster = bs.cluster_bytes() / size_of::<Entry>();
-    let root = match fsi.validate() {
-        true => unsafe {
-            core::slice::from_raw_parts(
-                read(start, bs.root_offset(), bs.cluster_bytes())? as *const Entry,
-                entry_per_cluster,
-            )
-        },
-        false => return Ok(false),
-    };
-    "FAT32\n".out();
-    for entry in root {
-        let first_cluster = match entry.name()[0] {
-            0x00 => break,
-            0xE5 => continue,
-            _ => entry.first_cluster(),
-        };
+    if !fsi.validate() {
+        return Ok(false);
     }
+    let volume = Volume {
+        start,
+        sector_bytes: bs.sector_bytes(),
+        cluster_bytes: bs.cluster_bytes(),
+        fat_offset: bs.fat_offset(),
+        data_offset: bs.data_offset(),
+        root_cluster: bs.root_cluster(),
+    };
+    fsi.delete()?;
+    bs.delete()?;
+
+    let mut volumes = VOLUMES.lock();
+    let Some(slot) = volumes.iter_mut().find(|v| v.is_none()) else {
+        return Err(Error::InvalidCount.into());
+    };
+    *slot = Some(volume);
+    info!("FAT32", "Volume mounted");
     Ok(true)
 }
+
+/// Opens the file at `path` on the first volume that has it
+pub fn open(path: &str) -> Result<Option<File>, crate::Error> {
+    let volumes = *VOLUMES.lock();
+    for volume in volumes.iter().flatten() {
+        if let Some(file) = volume.open(path)? {
+            return Ok(Some(file));
+        }
+    }
+    Ok(None)
+}
@@ -4,6 +4,7 @@ mod error;
 mod fat32;
 
 pub use error::Error;
+pub use fat32::{File, open};
 
 pub fn handle(start: u64, end: u64) -> Result<(), crate::Error> {
     if fat32::handle(start)? {
@@ -1,6 +1,7 @@
 //! IO
 
 pub mod port;
+pub mod serial;
 pub mod text;
 
 pub fn init(
@@ -8,11 +9,13 @@ pub fn init(
     screen_width: usize,
     screen_height: usize,
     screen_stride: usize,
-) {
+) -> Result<(), crate::Error> {
+    serial::init()?;
     text::init(
         frame_buffer_base,
         screen_width,
         screen_height,
         screen_stride,
     );
+    Ok(())
 }
@@ -5,12 +5,34 @@ use core::arch::asm;
 pub const MASTER_PIC_COMMAND: u16 = 0x20;
 pub const MASTER_PIC_DATA: u16 = 0x21;
 
+pub const PIT_CHANNEL_2: u16 = 0x42;
+pub const PIT_COMMAND: u16 = 0x43;
+
 pub const PS2_DATA: u16 = 0x60;
+/// - Bit 0: PIT Channel 2 Gate
+/// - Bit 1: Speaker Data Enable
+/// - Bit 5: PIT Channel 2 Output
+pub const SYSTEM_CONTROL_B: u16 = 0x61;
 pub const PS2_COMMAND: u16 = 0x64;
 
+/// POST code port, whose writes take about a microsecond
+pub const POST: u16 = 0x80;
+
 pub const SLAVE_PIC_COMMAND: u16 = 0xA0;
 pub const SLAVE_PIC_DATA: u16 = 0xA1;
 
+pub const COM2: u16 = 0x2F8;
+pub const COM1: u16 = 0x3F8;
+
+/// Configuration Mechanism #1
+/// - Bits 0 ..= 7: Register offset, dword aligned
+/// - Bits 8 ..= 10: Function
+/// - Bits 11 ..= 15: Device
+/// - Bits 16 ..= 23: Bus
+/// - Bit 31: Enable
+pub const PCI_CONFIG_ADDRESS: u16 = 0xCF8;
+pub const PCI_CONFIG_DATA: u16 = 0xCFC;
+
 #[inline(always)]
 pub fn in_byte(port: u16) -> u8 {
     let byte: u8;
@@ -34,3 +56,51 @@ pub fn out_byte(port: u16, byte: u8) {
         )
     };
 }
+
+#[inline(always)]
+pub fn in_word(port: u16) -> u16 {
+    let word: u16;
+    unsafe {
+        asm!(
+            "in ax, dx",
+            lateout("ax") word,
+            in("dx") port,
+        )
+    };
+    word
+}
+
+#[inline(always)]
+pub fn out_word(port: u16, word: u16) {
+    unsafe {
+        asm!(
+            "out dx, ax",
+            in("dx") port,
+            in("ax") word,
+        )
+    };
+}
+
+#[inline(always)]
+pub fn in_dword(port: u16) -> u32 {
+    let dword: u32;
+    unsafe {
+        asm!(
+            "in eax, dx",
+            lateout("eax") dword,
+            in("dx") port,
+        )
+    };
+    dword
+}
+
+#[inline(always)]
+pub fn out_dword(port: u16, dword: u32) {
+    unsafe {
+        asm!(
+            "out dx, eax",
+            in("dx") port,
+            in("eax") dword,
+        )
+    };
+}
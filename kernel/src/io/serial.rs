@@ -0,0 +1,236 @@
+//! Serial
+//!
+//! 16550 UART on COM1 as the console, polled for output so it works before anything else is
+//! up, and interrupt-driven for input once the IOAPIC is programmed.
+
+use core::{
+    hint::spin_loop,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    log,
+    sync::IrqSpinlock,
+    x86_64::{apic::ioapic, idt::vector},
+};
+
+use super::port;
+
+/// ISA IRQ of COM1
+const IRQ: u8 = 4;
+
+/// 115200 baud from the 1.8432 MHz clock
+const DIVISOR: u16 = 1;
+
+const LINE_SIZE: usize = 256;
+
+static CONSOLE: Uart = Uart::new(port::COM1);
+static PRESENT: AtomicBool = AtomicBool::new(false);
+
+static LINE: IrqSpinlock<Line> = IrqSpinlock::new(Line {
+    buffer: [0; LINE_SIZE],
+    len: 0,
+    ready: false,
+});
+
+#[repr(u16)]
+enum Register {
+    /// Read: Receiver Buffer
+    /// Write: Transmitter Holding
+    /// DLAB: Divisor Latch Low
+    Data,
+
+    /// Interrupt Enable
+    /// - Bit 0: Received Data Available
+    /// - Bit 1: Transmitter Holding Register Empty
+    /// - Bit 2: Receiver Line Status
+    /// - Bit 3: Modem Status
+    /// - Bits 4 ..= 7: Reserved
+    /// DLAB: Divisor Latch High
+    IER,
+
+    /// Write: FIFO Control
+    /// - Bit 0: Enable FIFOs
+    /// - Bit 1: Clear Receive FIFO
+    /// - Bit 2: Clear Transmit FIFO
+    /// - Bits 6 ..= 7: Receive Trigger Level
+    FCR,
+
+    /// Line Control
+    /// - Bits 0 ..= 1: Word Length - 5
+    /// - Bit 2: Stop Bits
+    /// - Bits 3 ..= 5: Parity
+    /// - Bit 7: DLAB for Divisor Latch Access Bit
+    LCR,
+
+    /// Modem Control
+    /// - Bit 0: DTR
+    /// - Bit 1: RTS
+    /// - Bit 3: OUT2, gates the IRQ line
+    /// - Bit 4: Loopback
+    MCR,
+
+    /// Line Status
+    /// - Bit 0: Data Ready
+    /// - Bit 5: Transmitter Holding Register Empty
+    LSR,
+}
+
+pub struct Uart {
+    base: u16,
+}
+impl Uart {
+    pub const fn new(base: u16) -> Self {
+        Self { base }
+    }
+
+    fn read(&self, register: Register) -> u8 {
+        port::in_byte(self.base + register as u16)
+    }
+
+    fn write(&self, register: Register, byte: u8) {
+        port::out_byte(self.base + register as u16, byte)
+    }
+
+    /// Programs 8N1 at 115200 baud and checks the UART answers in loopback mode
+    pub fn init(&self) -> bool {
+        self.write(Register::IER, 0);
+        self.write(Register::LCR, 1 << 7);
+        self.write(Register::Data, DIVISOR as u8);
+        self.write(Register::IER, (DIVISOR >> 8) as u8);
+        self.write(Register::LCR, 0b11);
+        self.write(Register::FCR, 0b1100_0111);
+
+        self.write(Register::MCR, 0b1_1110);
+        self.write(Register::Data, 0xAE);
+        if self.read(Register::Data) != 0xAE {
+            return false;
+        }
+        self.write(Register::MCR, 0b1011);
+        true
+    }
+
+    pub fn out_byte(&self, byte: u8) {
+        while self.read(Register::LSR) & (1 << 5) == 0 {
+            spin_loop();
+        }
+        self.write(Register::Data, byte);
+    }
+
+    pub fn try_in_byte(&self) -> Option<u8> {
+        (self.read(Register::LSR) & 1 == 1).then(|| self.read(Register::Data))
+    }
+
+    pub fn in_byte(&self) -> u8 {
+        loop {
+            if let Some(byte) = self.try_in_byte() {
+                return byte;
+            }
+            spin_loop();
+        }
+    }
+
+    /// Raises the IRQ whenever a byte arrives
+    pub fn enable_receive(&self) {
+        self.write(Register::IER, 1);
+    }
+}
+
+/// Line being typed on the serial console
+struct Line {
+    buffer: [u8; LINE_SIZE],
+    len: usize,
+
+    /// Set by Enter until `read_line` takes the line
+    ready: bool,
+}
+
+/// Adds COM1 as a log sink if it is there
+pub fn init() -> Result<(), crate::Error> {
+    let present = CONSOLE.init();
+    PRESENT.store(present, Ordering::Release);
+    if present {
+        log::add_sink(log::sink::serial)?;
+    }
+    Ok(())
+}
+
+/// Whether COM1 answered the loopback test
+pub fn is_present() -> bool {
+    PRESENT.load(Ordering::Acquire)
+}
+
+pub fn out_byte(byte: u8) {
+    if PRESENT.load(Ordering::Acquire) {
+        CONSOLE.out_byte(byte);
+    }
+}
+
+pub fn out_char(c: char) {
+    let mut buffer = [0u8; 4];
+    for &byte in c.encode_utf8(&mut buffer).as_bytes() {
+        if byte == b'\n' {
+            out_byte(b'\r');
+        }
+        out_byte(byte);
+    }
+}
+
+fn handle(_: usize) {
+    let mut line = LINE.lock();
+    while let Some(byte) = CONSOLE.try_in_byte() {
+        if line.ready {
+            continue;
+        }
+        match byte {
+            b'\r' | b'\n' => {
+                line.ready = true;
+                out_char('\n');
+            }
+            // Backspace & Delete
+            0x08 | 0x7F if line.len > 0 => {
+                line.len -= 1;
+                for byte in [0x08, b' ', 0x08] {
+                    out_byte(byte);
+                }
+            }
+            0x08 | 0x7F => {}
+            _ if line.len < LINE_SIZE => {
+                let len = line.len;
+                line.buffer[len] = byte;
+                line.len += 1;
+                out_byte(byte);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Takes the line once Enter was pressed, newline included
+pub fn read_line(buf: &mut [u8]) -> Option<usize> {
+    let mut line = LINE.lock();
+    if !line.ready {
+        return None;
+    }
+    let mut n = line.len.min(buf.len());
+    buf[..n].copy_from_slice(&line.buffer[..n]);
+    if n < buf.len() {
+        buf[n] = b'\n';
+        n += 1;
+    }
+    line.len = 0;
+    line.ready = false;
+    Some(n)
+}
+
+/// Starts receiving through the IOAPIC
+pub fn init_receive() -> Result<(), crate::Error> {
+    if !PRESENT.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    let vector = vector::allocate(1)?;
+    vector::register(vector, handle, 0)?;
+    ioapic::route_isa(IRQ, vector)?;
+    CONSOLE.enable_receive();
+    Ok(())
+}
@@ -1,18 +1,12 @@
 //! Cursor
 
+use crate::sync::{IrqSpinlock, IrqSpinlockGuard};
+
 use super::output::{font, frame_buffer, screen};
 
 const CURSOR_WIDTH: usize = 2;
 
-static mut CURSOR: Cursor = Cursor {
-    x: 0,
-    y: 0,
-    dx: 0,
-    dy: 0,
-    ptr: core::ptr::null_mut(),
-};
-
-static mut CACHE: [u8; font::HEIGHT] = [0u8; font::HEIGHT];
+static CURSOR: IrqSpinlock<Cursor> = IrqSpinlock::new(Cursor::null());
 
 #[repr(u32)]
 pub enum Color {
@@ -28,15 +22,33 @@ pub struct Cursor {
     dy: usize,
 
     pub ptr: *mut u32,
+
+    cache: [u8; font::HEIGHT],
 }
+unsafe impl Send for Cursor {}
 impl Cursor {
+    const fn null() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            dx: 0,
+            dy: 0,
+            ptr: core::ptr::null_mut(),
+            cache: [0u8; font::HEIGHT],
+        }
+    }
+
     #[inline(never)]
     pub fn init() {
-        unsafe {
-            CURSOR.ptr = (frame_buffer::base() as *mut u32)
+        Self::lock().ptr = unsafe {
+            (frame_buffer::base() as *mut u32)
                 .add(screen::MARGIN * screen::stride())
-                .add(screen::MARGIN);
-        }
+                .add(screen::MARGIN)
+        };
+    }
+
+    pub fn lock() -> IrqSpinlockGuard<'static, Self> {
+        CURSOR.lock()
     }
 
     pub fn max_x() -> usize {
@@ -47,32 +59,32 @@ impl Cursor {
         (screen::height() - screen::MARGIN * 2) / font::HEIGHT
     }
 
-    fn out_pixel(color: u32) {
+    fn out_pixel(&mut self, color: u32) {
         unsafe {
-            (CURSOR.ptr as *mut u32).write_volatile(color);
+            self.ptr.write_volatile(color);
 
             // Next Pixel
-            if CURSOR.dx + 1 == font::WIDTH {
-                CURSOR.dx = 0;
-                if CURSOR.dy + 1 == font::HEIGHT {
-                    CURSOR.dy = 0;
+            if self.dx + 1 == font::WIDTH {
+                self.dx = 0;
+                if self.dy + 1 == font::HEIGHT {
+                    self.dy = 0;
                 } else {
-                    CURSOR.dy += 1;
-                    CURSOR.ptr = CURSOR.ptr.add(screen::stride()).sub(font::WIDTH - 1);
+                    self.dy += 1;
+                    self.ptr = self.ptr.add(screen::stride()).sub(font::WIDTH - 1);
                 }
             } else {
-                CURSOR.dx += 1;
-                CURSOR.ptr = CURSOR.ptr.add(1);
+                self.dx += 1;
+                self.ptr = self.ptr.add(1);
             }
         }
     }
 
-    pub fn read_cache() {
+    pub fn read_cache(&mut self) {
         unsafe {
-            let mut ptr = CURSOR.ptr;
+            let mut ptr = self.ptr;
             for y in 0..font::HEIGHT {
                 for x in 0..CURSOR_WIDTH {
-                    ptr.write_volatile(if (CACHE[y] >> x) & 1 == 1 {
+                    ptr.write_volatile(if (self.cache[y] >> x) & 1 == 1 {
                         Color::White
                     } else {
                         Color::Black
@@ -84,14 +96,14 @@ impl Cursor {
         }
     }
 
-    pub fn write_cache() {
+    pub fn write_cache(&mut self) {
         unsafe {
-            let mut ptr = CURSOR.ptr;
+            let mut ptr = self.ptr;
             for y in 0..font::HEIGHT {
-                CACHE[y] = 0;
+                self.cache[y] = 0;
                 for x in 0..CURSOR_WIDTH {
                     if ptr.read_volatile() != 0 {
-                        CACHE[y] |= 1 << x;
+                        self.cache[y] |= 1 << x;
                     }
                     ptr = ptr.add(1);
                 }
@@ -100,9 +112,9 @@ impl Cursor {
         }
     }
 
-    pub fn show() {
+    pub fn show(&mut self) {
         unsafe {
-            let mut ptr = CURSOR.ptr;
+            let mut ptr = self.ptr;
             for _ in 0..font::HEIGHT {
                 for _ in 0..CURSOR_WIDTH {
                     ptr.write_volatile(Color::White as u32);
@@ -113,25 +125,26 @@ impl Cursor {
         }
     }
 
-    pub fn wrapper<F: FnOnce()>(f: F) {
-        Self::read_cache();
-        f();
-        Self::write_cache();
-        Self::show();
+    pub fn wrapper<F: FnOnce(&mut Self)>(f: F) {
+        let mut cursor = Self::lock();
+        cursor.read_cache();
+        f(&mut cursor);
+        cursor.write_cache();
+        cursor.show();
     }
 
-    pub fn out_char(c: char, right: bool) {
+    pub fn out_char(&mut self, c: char, right: bool) {
         if right {
             if !screen::last_is_black() {
-                Self::up();
+                self.up();
                 screen::up();
             }
-            unsafe { screen::right(CURSOR.x, CURSOR.y, CURSOR.ptr) };
+            screen::right(self.x, self.y, self.ptr);
         }
 
         for row in font::bitmap(c) {
             for col in 0..font::WIDTH {
-                Self::out_pixel(if (row >> col) & 1 == 1 {
+                self.out_pixel(if (row >> col) & 1 == 1 {
                     Color::White as u32
                 } else {
                     Color::Black as u32
@@ -141,17 +154,17 @@ impl Cursor {
 
         // Next Char
         unsafe {
-            if CURSOR.x + 1 == Self::max_x() {
-                CURSOR.x = 0;
-                if CURSOR.y + 1 == Self::max_y() {
+            if self.x + 1 == Self::max_x() {
+                self.x = 0;
+                if self.y + 1 == Self::max_y() {
                     screen::up();
-                    CURSOR.ptr = CURSOR
+                    self.ptr = self
                         .ptr
                         .sub((font::HEIGHT - 1) * screen::stride())
                         .sub(Self::max_x() * font::WIDTH - 1);
                 } else {
-                    CURSOR.y += 1;
-                    CURSOR.ptr = CURSOR
+                    self.y += 1;
+                    self.ptr = self
                         .ptr
                         .add(screen::MARGIN)
                         .add(screen::stride() - screen::width())
@@ -159,68 +172,66 @@ impl Cursor {
                         .add(1);
                 }
             } else {
-                CURSOR.x += 1;
-                CURSOR.ptr = CURSOR.ptr.sub((font::HEIGHT - 1) * screen::stride() - 1);
+                self.x += 1;
+                self.ptr = self.ptr.sub((font::HEIGHT - 1) * screen::stride() - 1);
             }
         }
     }
 
-    pub fn backspace() {
-        Self::left();
-        unsafe { screen::left(CURSOR.x, CURSOR.y, CURSOR.ptr) };
+    pub fn backspace(&mut self) {
+        self.left();
+        screen::left(self.x, self.y, self.ptr);
     }
 
-    pub fn tab() {
+    pub fn tab(&mut self) {
         for _ in 0..4 {
-            Self::space();
+            self.space();
         }
     }
 
-    pub fn enter() {
+    pub fn enter(&mut self) {
         unsafe {
-            if CURSOR.y + 1 == Self::max_y() {
-                CURSOR.ptr = CURSOR.ptr.sub(CURSOR.x * font::WIDTH);
+            if self.y + 1 == Self::max_y() {
+                self.ptr = self.ptr.sub(self.x * font::WIDTH);
                 screen::up();
             } else {
-                CURSOR.ptr = CURSOR
+                self.ptr = self
                     .ptr
-                    .sub(CURSOR.x * font::WIDTH)
+                    .sub(self.x * font::WIDTH)
                     .add(font::HEIGHT * screen::stride());
-                CURSOR.y += 1;
+                self.y += 1;
             }
-            CURSOR.x = 0;
+            self.x = 0;
         }
     }
 
-    pub fn space() {
+    pub fn space(&mut self) {
         if !screen::last_is_black() {
-            Self::up();
+            self.up();
             screen::up();
         }
-        unsafe { screen::right(CURSOR.x, CURSOR.y, CURSOR.ptr) };
-        Self::out_char(' ', false);
+        screen::right(self.x, self.y, self.ptr);
+        self.out_char(' ', false);
     }
 
-    pub fn home() {}
+    pub fn home(&mut self) {}
 
-    pub fn up() {
-        unsafe {
-            if CURSOR.y != 0 {
-                CURSOR.y -= 1;
-                CURSOR.ptr = CURSOR.ptr.sub(font::HEIGHT * screen::stride());
-            }
+    pub fn up(&mut self) {
+        if self.y != 0 {
+            self.y -= 1;
+            self.ptr = unsafe { self.ptr.sub(font::HEIGHT * screen::stride()) };
         }
     }
 
-    pub fn left() {
+    pub fn left(&mut self) {
         unsafe {
-            if CURSOR.x == 0 {
-                if CURSOR.y == 0 {
+            if self.x == 0 {
+                if self.y == 0 {
                     return;
                 }
-                CURSOR.x = Self::max_x() - 1;
-                CURSOR.y -= 1;
-                CURSOR.ptr = CURSOR
+                self.x = Self::max_x() - 1;
+                self.y -= 1;
+                self.ptr = self
                     .ptr
                     .sub(screen::MARGIN)
                     .sub(screen::stride() - screen::width())
@@ -228,21 +239,21 @@ impl Cursor {
                     .sub((font::HEIGHT - 1) * screen::stride())
                     .sub(font::WIDTH);
             } else {
-                CURSOR.x -= 1;
-                CURSOR.ptr = CURSOR.ptr.sub(font::WIDTH);
+                self.x -= 1;
+                self.ptr = self.ptr.sub(font::WIDTH);
             }
         }
     }
 
-    pub fn right() {
+    pub fn right(&mut self) {
         unsafe {
-            if CURSOR.x + 1 == Self::max_x() {
-                if CURSOR.y + 1 == Self::max_y() {
+            if self.x + 1 == Self::max_x() {
+                if self.y + 1 == Self::max_y() {
                     return;
                 }
-                CURSOR.x = 0;
-                CURSOR.y += 1;
-                CURSOR.ptr = CURSOR
+                self.x = 0;
+                self.y += 1;
+                self.ptr = self
                     .ptr
                     .add(font::WIDTH)
                     .add(screen::MARGIN)
@@ -250,24 +261,22 @@ impl Cursor {
                     .add(screen::MARGIN)
                     .add((font::HEIGHT - 1) * screen::stride());
             } else {
-                CURSOR.x += 1;
-                CURSOR.ptr = CURSOR.ptr.add(font::WIDTH);
+                self.x += 1;
+                self.ptr = self.ptr.add(font::WIDTH);
             }
         }
     }
 
-    pub fn end() {}
+    pub fn end(&mut self) {}
 
-    pub fn down() {
-        unsafe {
-            if CURSOR.y + 1 != Self::max_y() {
-                CURSOR.y += 1;
-                CURSOR.ptr = CURSOR.ptr.add(font::HEIGHT * screen::stride());
-            }
+    pub fn down(&mut self) {
+        if self.y + 1 != Self::max_y() {
+            self.y += 1;
+            self.ptr = unsafe { self.ptr.add(font::HEIGHT * screen::stride()) };
         }
     }
 
-    pub fn delete() {
-        unsafe { screen::left(CURSOR.x, CURSOR.y, CURSOR.ptr) }
+    pub fn delete(&mut self) {
+        screen::left(self.x, self.y, self.ptr)
     }
 }
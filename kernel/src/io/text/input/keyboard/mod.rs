@@ -1,12 +1,46 @@
 //! Keyboard
 
+use crate::{
+    io::port,
+    sync::Spinlock,
+    x86_64::{apic::ioapic, idt::vector},
+};
+
 use super::super::Cursor;
 
 mod scancode_map;
 
-static mut CAPS_LOCK: bool = false;
-static mut SHIFT: bool = false;
+/// ISA IRQ of the PS/2 keyboard
+const IRQ: u8 = 1;
+
+static STATE: Spinlock<State> = Spinlock::new(State {
+    caps_lock: false,
+    shift: false,
+});
+
+struct State {
+    caps_lock: bool,
+    shift: bool,
+}
+impl State {
+    #[inline(always)]
+    fn upper(&self) -> bool {
+        self.caps_lock ^ self.shift
+    }
+}
 
 pub fn input(byte: u8) {
-    Cursor::wrapper(|| scancode_map::map(byte));
+    let mut state = STATE.lock();
+    Cursor::wrapper(|cursor| scancode_map::map(cursor, &mut state, byte));
+}
+
+fn handle(_: usize) {
+    input(port::in_byte(port::PS2_DATA));
+}
+
+pub fn init() -> Result<(), crate::Error> {
+    let vector = vector::allocate(1)?;
+    vector::register(vector, handle, 0)?;
+    ioapic::route_isa(IRQ, vector)?;
+    Ok(())
 }
@@ -6,7 +6,7 @@ mod output;
 
 pub use cursor::Cursor;
 pub use input::keyboard;
-pub use output::{Output, screen};
+pub use output::{Output, out_screen, screen};
 
 pub fn init(
     frame_buffer_base: usize,
@@ -1,19 +1,22 @@
 //! Frame Buffer
 
-static mut BASE: usize = 0;
-static mut SIZE: usize = 0;
+use crate::sync::Once;
+
+static CONFIG: Once<Config> = Once::new();
+
+struct Config {
+    base: usize,
+    size: usize,
+}
 
 pub fn set_config(base: usize, size: usize) {
-    unsafe {
-        BASE = base;
-        SIZE = size;
-    }
+    CONFIG.call_once(|| Config { base, size });
 }
 
 pub fn base() -> usize {
-    unsafe { BASE }
+    CONFIG.get().map_or(0, |config| config.base)
 }
 
 pub fn size() -> usize {
-    unsafe { SIZE }
+    CONFIG.get().map_or(0, |config| config.size)
 }
@@ -1,6 +1,6 @@
 //! Output
 
-use super::Cursor;
+use super::{super::serial, Cursor};
 
 pub mod font;
 pub mod frame_buffer;
@@ -9,7 +9,10 @@ pub mod screen;
 pub trait Output {
     fn out(&self);
 
-    fn nibble_to_hex_char(nibble: u8) -> char {
+    fn nibble_to_hex_char(nibble: u8) -> char
+    where
+        Self: Sized,
+    {
         if nibble < 10 {
             (b'0' + nibble) as char
         } else {
@@ -17,7 +20,10 @@ pub trait Output {
         }
     }
 
-    fn byte_to_hex_str(n: u8) {
+    fn byte_to_hex_str(n: u8)
+    where
+        Self: Sized,
+    {
         Self::nibble_to_hex_char(n >> 4).out();
         Self::nibble_to_hex_char(n & 0xF).out();
     }
@@ -95,15 +101,11 @@ impl Output for bool {
 }
 impl Output for char {
     fn out(&self) {
-        if self.is_ascii_control() {
-            match self {
-                '\t' => Cursor::tab(),
-                '\n' => Cursor::enter(),
-                _ => {}
-            }
-        } else {
-            Cursor::out_char(*self, false);
+        if crate::log::capture(*self) {
+            return;
         }
+        serial::out_char(*self);
+        out_screen(*self);
     }
 }
 impl Output for &str {
@@ -119,6 +121,23 @@ impl Output for [u8] {
     }
 }
 
+/// Frame buffer only, a no-op before `text::init`
+pub fn out_screen(c: char) {
+    let mut cursor = Cursor::lock();
+    if cursor.ptr.is_null() {
+        return;
+    }
+    if c.is_ascii_control() {
+        match c {
+            '\t' => cursor.tab(),
+            '\n' => cursor.enter(),
+            _ => {}
+        }
+    } else {
+        cursor.out_char(c, false);
+    }
+}
+
 pub fn init(
     frame_buffer_base: usize,
     screen_width: usize,
@@ -1,31 +1,37 @@
 //! Screen
 
+use crate::sync::Once;
+
 use super::{super::cursor::Color, Cursor, font, frame_buffer};
 
 pub const MARGIN: usize = 8;
 
-static mut WIDTH: usize = 0;
-static mut HEIGHT: usize = 0;
-static mut STRIDE: usize = 0;
+static CONFIG: Once<Config> = Once::new();
+
+struct Config {
+    width: usize,
+    height: usize,
+    stride: usize,
+}
 
 pub fn set_config(width: usize, height: usize, stride: usize) {
-    unsafe {
-        WIDTH = width;
-        HEIGHT = height;
-        STRIDE = stride;
-    }
+    CONFIG.call_once(|| Config {
+        width,
+        height,
+        stride,
+    });
 }
 
 pub fn width() -> usize {
-    unsafe { WIDTH }
+    CONFIG.get().map_or(0, |config| config.width)
 }
 
 pub fn height() -> usize {
-    unsafe { HEIGHT }
+    CONFIG.get().map_or(0, |config| config.height)
 }
 
 pub fn stride() -> usize {
-    unsafe { STRIDE }
+    CONFIG.get().map_or(0, |config| config.stride)
 }
 
 pub fn clear() {
@@ -40,11 +46,12 @@ pub fn clear() {
 }
 
 pub fn last_is_black() -> bool {
+    let stride = stride();
     unsafe {
         let mut ptr = (frame_buffer::base() as *mut u32)
-            .add(MARGIN * STRIDE)
+            .add(MARGIN * stride)
             .add(MARGIN)
-            .add(((Cursor::max_y() - 1) * font::HEIGHT) * STRIDE)
+            .add(((Cursor::max_y() - 1) * font::HEIGHT) * stride)
             .add((Cursor::max_x() - 1) * font::WIDTH);
         for _ in 0..font::HEIGHT {
             for _ in 0..font::WIDTH {
@@ -53,28 +60,30 @@ pub fn last_is_black() -> bool {
                 }
                 ptr = ptr.add(1);
             }
-            ptr = ptr.add(STRIDE).sub(font::WIDTH);
+            ptr = ptr.add(stride).sub(font::WIDTH);
         }
     }
     true
 }
 
 pub fn up() {
+    let (width, height, stride) = (width(), height(), stride());
     unsafe {
         let mut ptr = (frame_buffer::base() as *mut u32)
-            .add(MARGIN * STRIDE)
+            .add(MARGIN * stride)
             .add(MARGIN);
-        for _ in MARGIN..(HEIGHT - MARGIN) {
-            for _ in MARGIN..(WIDTH - MARGIN) {
-                ptr.write_volatile(ptr.add(font::HEIGHT * STRIDE).read_volatile());
+        for _ in MARGIN..(height - MARGIN) {
+            for _ in MARGIN..(width - MARGIN) {
+                ptr.write_volatile(ptr.add(font::HEIGHT * stride).read_volatile());
                 ptr = ptr.add(1);
             }
-            ptr = ptr.add(MARGIN).add(STRIDE - WIDTH).add(MARGIN);
+            ptr = ptr.add(MARGIN).add(stride - width).add(MARGIN);
         }
     }
 }
 
 pub fn left(x: usize, y: usize, mut ptr: *mut u32) {
+    let (width, stride) = (width(), stride());
     for i in y..Cursor::max_y() {
         for j in 0..font::HEIGHT {
             unsafe {
@@ -89,12 +98,12 @@ pub fn left(x: usize, y: usize, mut ptr: *mut u32) {
                         Color::Black as u32
                     } else {
                         ptr.sub((Cursor::max_x() - 1) * font::WIDTH)
-                            .add(font::HEIGHT * STRIDE)
+                            .add(font::HEIGHT * stride)
                             .read_volatile()
                     });
                     ptr = ptr.add(1);
                 }
-                ptr = ptr.add(MARGIN).add(STRIDE - WIDTH).add(MARGIN);
+                ptr = ptr.add(MARGIN).add(stride - width).add(MARGIN);
                 if i == y && j + 1 != font::HEIGHT {
                     ptr = ptr.add(x * font::WIDTH);
                 }
@@ -104,9 +113,10 @@ pub fn left(x: usize, y: usize, mut ptr: *mut u32) {
 }
 
 pub fn right(x: usize, y: usize, ptr: *mut u32) {
+    let (width, stride) = (width(), stride());
     unsafe {
         let mut tail = ptr
-            .add(((Cursor::max_y() - y) * font::HEIGHT - 1) * STRIDE)
+            .add(((Cursor::max_y() - y) * font::HEIGHT - 1) * stride)
             .add((Cursor::max_x() - x) * font::WIDTH - 1);
         for i in (y..Cursor::max_y()).rev() {
             for _ in 0..font::HEIGHT {
@@ -120,7 +130,7 @@ pub fn right(x: usize, y: usize, ptr: *mut u32) {
                     tail.write_volatile(if i == y {
                         Color::Black as u32
                     } else {
-                        tail.sub(font::HEIGHT * STRIDE)
+                        tail.sub(font::HEIGHT * stride)
                             .add((Cursor::max_x() - 1) * font::WIDTH)
                             .read_volatile()
                     });
@@ -129,7 +139,7 @@ pub fn right(x: usize, y: usize, ptr: *mut u32) {
                 if i == y {
                     tail = tail.sub(x * font::WIDTH);
                 }
-                tail = tail.sub(MARGIN).sub(STRIDE - WIDTH).sub(MARGIN);
+                tail = tail.sub(MARGIN).sub(stride - width).sub(MARGIN);
             }
         }
     }
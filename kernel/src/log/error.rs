@@ -0,0 +1,21 @@
+//! Error
+
+pub enum Error {
+    InvalidCount,
+    InvalidTag,
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::Log(err)
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "Log ".out();
+        match self {
+            Error::InvalidCount => "Count Overflow",
+            Error::InvalidTag => "Invalid Tag",
+        }
+        .out();
+    }
+}
@@ -0,0 +1,312 @@
+//! Log
+//!
+//! Records carry a level, the tag of the subsystem that wrote them and the uptime, and are
+//! kept in a ring buffer before being handed to every registered sink.
+//! Messages are built from `Output` values: while a record is written, `char::out` is captured
+//! into it instead of reaching the screen.
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use crate::{io::text::Output, sync::IrqSpinlock, time};
+
+mod error;
+pub mod sink;
+
+pub use error::Error;
+pub use sink::Sink;
+
+const MESSAGE_SIZE: usize = 128;
+const RECORD_COUNT: usize = 128;
+const MAX_TAG_LEVEL_COUNT: usize = 16;
+const MAX_TAG_LEN: usize = 16;
+const MAX_SINK_COUNT: usize = 4;
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Interrupt handlers log too, so every lock here keeps interrupts off
+static TAG_LEVELS: IrqSpinlock<[Option<TagLevel>; MAX_TAG_LEVEL_COUNT]> =
+    IrqSpinlock::new([None; MAX_TAG_LEVEL_COUNT]);
+static SINKS: IrqSpinlock<[Option<Sink>; MAX_SINK_COUNT]> =
+    IrqSpinlock::new([Some(sink::console), None, None, None]);
+
+/// Held while a record is built and stored, with interrupts off so nothing else gets captured
+static RING: IrqSpinlock<Ring> = IrqSpinlock::new(Ring {
+    records: [Record::null(); RECORD_COUNT],
+    next: 0,
+    count: 0,
+});
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static MESSAGE: IrqSpinlock<Message> = IrqSpinlock::new(Message::null());
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+impl Level {
+    fn from_u8(level: u8) -> Self {
+        Self::new(level as u64).unwrap_or(Level::Trace)
+    }
+
+    /// `Error` is 0 and `Trace` 4
+    pub fn new(level: u64) -> Option<Self> {
+        match level {
+            0 => Some(Level::Error),
+            1 => Some(Level::Warn),
+            2 => Some(Level::Info),
+            3 => Some(Level::Debug),
+            4 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// Tag copied in, since the syscall setting it passes a user string
+#[derive(Clone, Copy)]
+struct TagLevel {
+    tag: [u8; MAX_TAG_LEN],
+    len: usize,
+    level: Level,
+}
+impl TagLevel {
+    fn tag(&self) -> &[u8] {
+        &self.tag[..self.len]
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Message {
+    buffer: [u8; MESSAGE_SIZE],
+    len: usize,
+}
+impl Message {
+    const fn null() -> Self {
+        Self {
+            buffer: [0; MESSAGE_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Drops what does not fit, never splitting a character
+    fn push(&mut self, c: char) {
+        let mut bytes = [0u8; 4];
+        let bytes = c.encode_utf8(&mut bytes).as_bytes();
+        if self.len + bytes.len() <= MESSAGE_SIZE {
+            self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Record {
+    pub uptime_ms: u64,
+    pub level: Level,
+    pub tag: &'static str,
+    message: Message,
+}
+impl Record {
+    const fn null() -> Self {
+        Self {
+            uptime_ms: 0,
+            level: Level::Info,
+            tag: "",
+            message: Message::null(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        // Only whole characters are ever pushed
+        unsafe { core::str::from_utf8_unchecked(&self.message.buffer[..self.message.len]) }
+    }
+}
+
+struct Ring {
+    records: [Record; RECORD_COUNT],
+
+    /// Slot the next record overwrites
+    next: usize,
+
+    count: usize,
+}
+impl Ring {
+    fn push(&mut self, record: Record) {
+        self.records[self.next] = record;
+        self.next = (self.next + 1) % RECORD_COUNT;
+        self.count = (self.count + 1).min(RECORD_COUNT);
+    }
+
+    /// Oldest first
+    fn iter(&self) -> impl Iterator<Item = &Record> {
+        let start = (self.next + RECORD_COUNT - self.count) % RECORD_COUNT;
+        (0..self.count).map(move |i| &self.records[(start + i) % RECORD_COUNT])
+    }
+}
+
+/// Diverts `c` into the record being written, if any
+pub fn capture(c: char) -> bool {
+    if !CAPTURING.load(Ordering::Acquire) {
+        return false;
+    }
+    MESSAGE.lock().push(c);
+    true
+}
+
+pub fn level() -> Level {
+    Level::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Most verbose level still recorded for tags without their own
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Overrides the level of one tag, `None` restores the global one
+pub fn set_tag_level(tag: &str, level: Option<Level>) -> Result<(), Error> {
+    let tag = tag.as_bytes();
+    if tag.is_empty() || tag.len() > MAX_TAG_LEN {
+        return Err(Error::InvalidTag);
+    }
+    let mut levels = TAG_LEVELS.lock();
+    if let Some(slot) = levels
+        .iter_mut()
+        .find(|slot| slot.is_some_and(|tag_level| tag_level.tag() == tag))
+    {
+        *slot = level.map(|level| TagLevel {
+            level,
+            ..slot.unwrap()
+        });
+        return Ok(());
+    }
+    let Some(level) = level else {
+        return Ok(());
+    };
+    let slot = levels
+        .iter_mut()
+        .find(|slot| slot.is_none())
+        .ok_or(Error::InvalidCount)?;
+    let mut tag_level = TagLevel {
+        tag: [0; MAX_TAG_LEN],
+        len: tag.len(),
+        level,
+    };
+    tag_level.tag[..tag.len()].copy_from_slice(tag);
+    *slot = Some(tag_level);
+    Ok(())
+}
+
+pub fn enabled(level: Level, tag: &str) -> bool {
+    let limit = TAG_LEVELS
+        .lock()
+        .iter()
+        .flatten()
+        .find(|tag_level| tag_level.tag() == tag.as_bytes())
+        .map_or_else(self::level, |tag_level| tag_level.level);
+    level <= limit
+}
+
+pub fn add_sink(sink: Sink) -> Result<(), Error> {
+    *SINKS
+        .lock()
+        .iter_mut()
+        .find(|slot| slot.is_none())
+        .ok_or(Error::InvalidCount)? = Some(sink);
+    Ok(())
+}
+
+/// Hands every buffered record to `sink`, oldest first
+pub fn replay(sink: Sink) {
+    for record in RING.lock().iter() {
+        sink(record);
+    }
+}
+
+/// Use the `error!` to `trace!` macros rather than calling this directly
+pub fn write(level: Level, tag: &'static str, parts: &[&dyn Output]) {
+    if !enabled(level, tag) {
+        return;
+    }
+
+    let record = {
+        let mut ring = RING.lock();
+        CAPTURING.store(true, Ordering::Release);
+        for part in parts {
+            part.out();
+        }
+        CAPTURING.store(false, Ordering::Release);
+
+        let mut message = core::mem::replace(&mut *MESSAGE.lock(), Message::null());
+        while message.len > 0 && message.buffer[message.len - 1] == b'\n' {
+            message.len -= 1;
+        }
+        let record = Record {
+            uptime_ms: time::uptime_ms(),
+            level,
+            tag,
+            message,
+        };
+        ring.push(record);
+        record
+    };
+
+    let sinks = *SINKS.lock();
+    for sink in sinks.iter().flatten() {
+        sink(&record);
+    }
+}
+
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log::write($level, $tag, &[$(&$part as &dyn $crate::io::text::Output),+])
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log!($crate::log::Level::Error, $tag, $($part),+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log!($crate::log::Level::Warn, $tag, $($part),+)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log!($crate::log::Level::Info, $tag, $($part),+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log!($crate::log::Level::Debug, $tag, $($part),+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($tag:expr, $($part:expr),+ $(,)?) => {
+        $crate::log!($crate::log::Level::Trace, $tag, $($part),+)
+    };
+}
@@ -0,0 +1,50 @@
+//! Sink
+
+use crate::io::{serial, text};
+
+use super::Record;
+
+pub type Sink = fn(&Record);
+
+/// `[    12.345] INFO  FAT32: message`
+fn format(record: &Record, out: fn(char)) {
+    let seconds = record.uptime_ms / 1000;
+    let millis = record.uptime_ms % 1000;
+
+    out('[');
+    let mut digits = [b' '; 10];
+    let mut n = seconds;
+    for digit in digits.iter_mut().rev() {
+        *digit = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().for_each(|&digit| out(digit as char));
+    out('.');
+    for divisor in [100, 10, 1] {
+        out((b'0' + (millis / divisor % 10) as u8) as char);
+    }
+    out(']');
+    out(' ');
+
+    let level = record.level.as_str();
+    level.chars().for_each(out);
+    (level.len()..6).for_each(|_| out(' '));
+    record.tag.chars().for_each(out);
+    out(':');
+    out(' ');
+    record.message().chars().for_each(out);
+    out('\n');
+}
+
+/// Frame buffer console
+pub fn console(record: &Record) {
+    format(record, text::out_screen);
+}
+
+/// COM1
+pub fn serial(record: &Record) {
+    format(record, serial::out_char);
+}
@@ -6,16 +6,44 @@ use core::{arch::asm, panic::PanicInfo};
 mod acpi;
 mod arch;
 mod drivers;
+mod elf;
 mod error;
 mod fs;
 mod io;
+mod log;
 mod math;
 mod mem;
+mod random;
+mod smbios;
+mod sync;
+mod syscall;
+mod task;
+mod time;
 mod types;
+mod uefi;
 
 use arch::x86_64;
 use error::Error;
-use io::text::{Output, screen};
+use io::text::{Output, keyboard, screen};
+
+/// First user program, installed on the `main` partition by `make write-user`
+const INIT_PATH: &str = "/hello";
+
+/// What the bootloader hands over in registers
+struct BootInfo {
+    frame_buffer_base: usize,
+    screen_width: usize,
+    screen_height: usize,
+    screen_stride: usize,
+    rsdp_addr: usize,
+    memory_map_entry: usize,
+    memory_descriptor_size: usize,
+    memory_descriptor_count: usize,
+    /// Zero without SMBIOS
+    smbios_addr: usize,
+    /// Zero without a UEFI system table
+    system_table: usize,
+}
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
@@ -38,37 +66,34 @@ fn panic(info: &PanicInfo) -> ! {
 
 #[unsafe(no_mangle)]
 pub extern "C" fn _start() -> ! {
-    let mut frame_buffer_base: usize;
-    let mut width: usize;
-    let mut height: usize;
-    let mut stride: usize;
-    let mut rsdp_addr: usize;
-    let mut memory_map_entry: usize;
-    let mut memory_descriptor_size: usize;
-    let mut memory_descriptor_count: usize;
+    let mut boot_info = BootInfo {
+        frame_buffer_base: 0,
+        screen_width: 0,
+        screen_height: 0,
+        screen_stride: 0,
+        rsdp_addr: 0,
+        memory_map_entry: 0,
+        memory_descriptor_size: 0,
+        memory_descriptor_count: 0,
+        smbios_addr: 0,
+        system_table: 0,
+    };
     unsafe {
         asm!(
             "",
-            lateout("r8") frame_buffer_base,
-            lateout("r9") width,
-            lateout("r10") height,
-            lateout("r11") stride,
-            lateout("r12") rsdp_addr,
-            lateout("r13") memory_map_entry,
-            lateout("r14") memory_descriptor_size,
-            lateout("r15") memory_descriptor_count,
+            lateout("r8") boot_info.frame_buffer_base,
+            lateout("r9") boot_info.screen_width,
+            lateout("r10") boot_info.screen_height,
+            lateout("r11") boot_info.screen_stride,
+            lateout("r12") boot_info.rsdp_addr,
+            lateout("r13") boot_info.memory_map_entry,
+            lateout("r14") boot_info.memory_descriptor_size,
+            lateout("r15") boot_info.memory_descriptor_count,
+            lateout("rdi") boot_info.smbios_addr,
+            lateout("rsi") boot_info.system_table,
         )
     };
-    match init(
-        frame_buffer_base,
-        width,
-        height,
-        stride,
-        rsdp_addr,
-        memory_map_entry,
-        memory_descriptor_size,
-        memory_descriptor_count,
-    ) {
+    match init(&boot_info) {
         Ok(_) => screen::clear(),
         Err(e) => {
             e.out();
@@ -96,31 +121,46 @@ pub extern "C" fn _start() -> ! {
         )
     };
 
-    loop {}
+    if let Err(e) = elf::exec(INIT_PATH, &[INIT_PATH], &[]) {
+        error!("init", INIT_PATH, ": ", e);
+    }
+    task::idle()
 }
 
-fn init(
-    frame_buffer_base: usize,
-    screen_width: usize,
-    screen_height: usize,
-    screen_stride: usize,
-    rsdp_addr: usize,
-    memory_map_entry: usize,
-    memory_descriptor_size: usize,
-    memory_descriptor_count: usize,
-) -> Result<(), Error> {
+fn init(boot_info: &BootInfo) -> Result<(), Error> {
     io::init(
-        frame_buffer_base,
-        screen_width,
-        screen_height,
-        screen_stride,
-    );
-    acpi::init(rsdp_addr)?;
+        boot_info.frame_buffer_base,
+        boot_info.screen_width,
+        boot_info.screen_height,
+        boot_info.screen_stride,
+    )?;
+    acpi::init(boot_info.rsdp_addr)?;
+    // Only describes the hardware, so boot goes on without it
+    if let Err(e) = smbios::init(boot_info.smbios_addr) {
+        warn!("SMBIOS", e);
+    }
     x86_64::init()?;
+    random::init();
+    acpi::sci::init();
+    keyboard::init()?;
+    io::serial::init_receive()?;
+    x86_64::gdb::init()?;
     mem::init(
-        memory_map_entry,
-        memory_descriptor_size,
-        memory_descriptor_count,
+        boot_info.memory_map_entry,
+        boot_info.memory_descriptor_size,
+        boot_info.memory_descriptor_count,
     )?;
+    // Runtime services stay unset, which their callers already handle
+    if let Err(e) = uefi::init(
+        boot_info.system_table,
+        boot_info.memory_map_entry,
+        boot_info.memory_descriptor_size,
+        boot_info.memory_descriptor_count,
+    ) {
+        warn!("UEFI", e);
+    }
+    task::init();
+    x86_64::mca::start_polling()?;
+    acpi::sci::start_worker()?;
     drivers::init()
 }
@@ -1,9 +1,12 @@
 //! Error
 
 pub enum Error {
+    InvalidAddress,
     InvalidAllocationSize,
     InvalidIndex,
 
+    Mapped,
+
     OutOfMemory,
 }
 impl From<Error> for crate::Error {
@@ -15,8 +18,10 @@ impl crate::Output for Error {
     fn out(&self) {
         "Memory ".out();
         match self {
+            Error::InvalidAddress => "Virtual Address",
             Error::InvalidAllocationSize => "Allocation Size",
             Error::InvalidIndex => "Page Index",
+            Error::Mapped => "Page Mapped",
             Error::OutOfMemory => "Overflow",
         }
         .out();
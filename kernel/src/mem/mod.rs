@@ -1,14 +1,17 @@
 //! Memory
 
 mod error;
+pub mod paging;
 pub mod physical;
 
 pub use error::Error;
 
-const PAGE_SIZE: usize = 0x1000;
+pub const PAGE_SIZE: usize = 0x1000;
 
 pub fn init(entry: usize, descriptor_size: usize, descriptor_count: usize) -> Result<(), Error> {
-    physical::init(entry, descriptor_size, descriptor_count)
+    physical::init(entry, descriptor_size, descriptor_count)?;
+    paging::init();
+    Ok(())
 }
 
 pub trait Memory: Sized {
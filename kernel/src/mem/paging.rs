@@ -0,0 +1,300 @@
+//! Paging
+
+use core::{
+    ptr::write_bytes,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    sync::Once,
+    x86_64::{
+        cpu::{self, Feature},
+        cr, msr,
+    },
+};
+
+use super::{Error, Memory, PAGE_SIZE, physical};
+
+/// Lowest user address, above everything the firmware identity maps
+pub const USER_START: usize = 0x0000_4000_0000_0000;
+
+/// End of the lower canonical half
+pub const USER_END: usize = 0x0000_8000_0000_0000;
+
+pub const PRESENT: u64 = 1 << 0;
+pub const WRITABLE: u64 = 1 << 1;
+pub const USER: u64 = 1 << 2;
+pub const CACHE_DISABLE: u64 = 1 << 4;
+pub const HUGE: u64 = 1 << 7;
+/// Available to software: the frame was allocated by and belongs to the address space
+pub const OWNED: u64 = 1 << 9;
+pub const EXECUTE_DISABLE: u64 = 1 << 63;
+
+const ADDRESS_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+const ENTRY_COUNT: usize = 512;
+
+/// PML4 the firmware left in CR3, shared by every address space
+static KERNEL: Once<usize> = Once::new();
+
+/// Whether EFER.NXE is set so that `EXECUTE_DISABLE` does not fault
+static NX: AtomicBool = AtomicBool::new(false);
+
+/// - Bit 0: P for Present
+/// - Bit 1: R/W for Read/write
+/// - Bit 2: U/S for User/supervisor
+/// - Bit 3: PWT for Page-level write-through
+/// - Bit 4: PCD for Page-level cache disable
+/// - Bit 5: A for Accessed
+/// - Bit 6: D for Dirty
+/// - Bit 7: PS for Page size
+/// - Bit 8: G for Global
+/// - Bits 9 ..= 11: Ignored
+/// - Bits 12 ..= (MAXPHYADDR - 1): Address
+/// - Bits MAXPHYADDR ..= 62: Reserved or Ignored
+/// - Bit 63: XD for Execute-disable
+#[repr(C, align(4096))]
+struct Table {
+    entries: [u64; ENTRY_COUNT],
+}
+impl Memory for Table {}
+impl Table {
+    fn zeroed() -> Result<&'static mut Self, Error> {
+        let table = Self::new()?;
+        unsafe { write_bytes(table as *mut Self, 0, 1) };
+        Ok(table)
+    }
+
+    /// Frees every table below this one and every owned frame, then the table itself
+    fn delete_recursive(&mut self, level: usize) -> Result<(), Error> {
+        for entry in self.entries {
+            if entry & PRESENT == 0 {
+                continue;
+            }
+            let addr = (entry & ADDRESS_MASK) as usize;
+            if level > 1 && entry & HUGE == 0 {
+                Table::get_mut(addr).delete_recursive(level - 1)?;
+            } else if entry & OWNED != 0 {
+                physical::deallocate(addr)?;
+            }
+        }
+        self.delete()
+    }
+}
+
+fn index(addr: usize, level: usize) -> usize {
+    (addr >> (12 + 9 * (level - 1))) & (ENTRY_COUNT - 1)
+}
+
+pub fn init() {
+    KERNEL.call_once(|| (cr::read_cr3() & ADDRESS_MASK) as usize);
+    if cpu::has(Feature::NX) {
+        msr::write(msr::IA32_EFER, msr::read(msr::IA32_EFER) | (1 << 11));
+        NX.store(true, Ordering::Relaxed);
+    }
+}
+
+fn supported(flags: u64) -> u64 {
+    if NX.load(Ordering::Relaxed) {
+        flags
+    } else {
+        flags & !EXECUTE_DISABLE
+    }
+}
+
+/// Whether `addr` is mapped in the active address space, kernel half included
+pub fn mapped(addr: usize) -> bool {
+    if ((addr << 16) as isize >> 16) as usize != addr {
+        return false;
+    }
+    let mut table = Table::get_ref((cr::read_cr3() & ADDRESS_MASK) as usize);
+    for level in (2..=4).rev() {
+        let entry = table.entries[index(addr, level)];
+        if entry & PRESENT == 0 {
+            return false;
+        }
+        if entry & HUGE != 0 {
+            return true;
+        }
+        table = Table::get_ref((entry & ADDRESS_MASK) as usize);
+    }
+    table.entries[index(addr, 1)] & PRESENT != 0
+}
+
+/// Maps `size` bytes from `addr` to themselves in the kernel half, where the firmware did not
+///
+/// Tables added below an existing PML4 entry reach every address space; new PML4 entries only
+/// those created afterwards.
+pub fn identity_map(addr: usize, size: usize, flags: u64) -> Result<(), Error> {
+    let kernel = *KERNEL.get().expect("Paging uninitialized");
+    let end = addr.checked_add(size).ok_or(Error::InvalidAddress)?;
+    'pages: for page in (addr & !(PAGE_SIZE - 1)..end).step_by(PAGE_SIZE) {
+        if page >= USER_START {
+            return Err(Error::InvalidAddress);
+        }
+        let mut table = Table::get_mut(kernel);
+        for level in (2..=4).rev() {
+            let entry = &mut table.entries[index(page, level)];
+            if *entry & PRESENT == 0 {
+                *entry = Table::zeroed()?.addr() as u64 | PRESENT | WRITABLE;
+            } else if *entry & HUGE != 0 {
+                continue 'pages;
+            }
+            table = Table::get_mut((*entry & ADDRESS_MASK) as usize);
+        }
+        let entry = &mut table.entries[index(page, 1)];
+        if *entry & PRESENT == 0 {
+            *entry = page as u64 | supported(flags) | PRESENT;
+            cr::invlpg(page);
+        }
+    }
+    Ok(())
+}
+
+/// A PML4 sharing the kernel half with every other one and owning `USER_START .. USER_END`
+pub struct AddressSpace {
+    pml4: usize,
+}
+impl AddressSpace {
+    pub fn new() -> Result<Self, Error> {
+        let kernel = Table::get_ref(*KERNEL.get().expect("Paging uninitialized"));
+        let pml4 = Table::zeroed()?;
+        for i in 0..ENTRY_COUNT {
+            if !(index(USER_START, 4)..=index(USER_END - 1, 4)).contains(&i) {
+                pml4.entries[i] = kernel.entries[i];
+            }
+        }
+        Ok(Self { pml4: pml4.addr() })
+    }
+
+    pub fn addr(&self) -> usize {
+        self.pml4
+    }
+
+    /// Loads the kernel-only PML4
+    pub fn activate_kernel() {
+        let kernel = *KERNEL.get().expect("Paging uninitialized");
+        if (cr::read_cr3() & ADDRESS_MASK) as usize != kernel {
+            cr::write_cr3(kernel as u64);
+        }
+    }
+
+    pub fn activate(&self) {
+        if (cr::read_cr3() & ADDRESS_MASK) as usize != self.pml4 {
+            cr::write_cr3(self.pml4 as u64);
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        (cr::read_cr3() & ADDRESS_MASK) as usize == self.pml4
+    }
+
+    /// Page table entry mapping `addr`, creating intermediate tables if `create`
+    fn entry(&self, addr: usize, create: bool) -> Result<Option<&'static mut u64>, Error> {
+        if !(USER_START..USER_END).contains(&addr) || !addr.is_multiple_of(PAGE_SIZE) {
+            return Err(Error::InvalidAddress);
+        }
+        let mut table = Table::get_mut(self.pml4);
+        for level in (2..=4).rev() {
+            let entry = &mut table.entries[index(addr, level)];
+            if *entry & PRESENT == 0 {
+                if !create {
+                    return Ok(None);
+                }
+                *entry = Table::zeroed()?.addr() as u64 | PRESENT | WRITABLE | USER;
+            }
+            table = Table::get_mut((*entry & ADDRESS_MASK) as usize);
+        }
+        Ok(Some(&mut table.entries[index(addr, 1)]))
+    }
+
+    /// Maps the page at `addr` to the frame at `phys`
+    pub fn map(&mut self, addr: usize, phys: usize, flags: u64) -> Result<(), Error> {
+        let entry = self.entry(addr, true)?.ok_or(Error::InvalidAddress)?;
+        if *entry & PRESENT != 0 {
+            return Err(Error::Mapped);
+        }
+        *entry = phys as u64 & ADDRESS_MASK | supported(flags) | PRESENT;
+        Ok(())
+    }
+
+    /// Flags of the page mapping `addr`
+    pub fn flags(&self, addr: usize) -> Option<u64> {
+        let entry = self.entry(addr & !(PAGE_SIZE - 1), false).ok()??;
+        (*entry & PRESENT != 0).then_some(*entry & !ADDRESS_MASK)
+    }
+
+    /// Replaces the flags of the mapped pages of `size` bytes from `addr`, keeping ownership
+    pub fn protect(&mut self, addr: usize, size: usize, flags: u64) -> Result<(), Error> {
+        for page in (addr..addr + size).step_by(PAGE_SIZE) {
+            let Some(entry) = self.entry(page, false)? else {
+                continue;
+            };
+            if *entry & PRESENT == 0 {
+                continue;
+            }
+            *entry = *entry & (ADDRESS_MASK | OWNED) | supported(flags) | PRESENT;
+            if self.is_active() {
+                cr::invlpg(page);
+            }
+        }
+        Ok(())
+    }
+
+    /// Backs `size` bytes from `addr` with fresh zeroed frames
+    pub fn allocate(&mut self, addr: usize, size: usize, flags: u64) -> Result<(), Error> {
+        for page in (addr..addr + size).step_by(PAGE_SIZE) {
+            let frame = physical::allocate(PAGE_SIZE)?;
+            unsafe { write_bytes(frame as *mut u8, 0, PAGE_SIZE) };
+            if let Err(e) = self.map(page, frame, flags | OWNED) {
+                physical::deallocate(frame)?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the mappings of `size` bytes from `addr`, freeing owned frames
+    pub fn unmap(&mut self, addr: usize, size: usize) -> Result<(), Error> {
+        for page in (addr..addr + size).step_by(PAGE_SIZE) {
+            let Some(entry) = self.entry(page, false)? else {
+                continue;
+            };
+            if *entry & PRESENT == 0 {
+                continue;
+            }
+            let old = *entry;
+            *entry = 0;
+            if self.is_active() {
+                cr::invlpg(page);
+            }
+            if old & OWNED != 0 {
+                physical::deallocate((old & ADDRESS_MASK) as usize)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Physical address `addr` is mapped to
+    pub fn translate(&self, addr: usize) -> Option<usize> {
+        let entry = self.entry(addr & !(PAGE_SIZE - 1), false).ok()??;
+        if *entry & PRESENT == 0 {
+            return None;
+        }
+        Some((*entry & ADDRESS_MASK) as usize + addr % PAGE_SIZE)
+    }
+
+    /// Frees the user half and the PML4 itself
+    ///
+    /// Must not be the active address space.
+    pub fn delete(self) -> Result<(), Error> {
+        let pml4 = Table::get_mut(self.pml4);
+        for i in index(USER_START, 4)..=index(USER_END - 1, 4) {
+            let entry = pml4.entries[i];
+            if entry & PRESENT != 0 {
+                Table::get_mut((entry & ADDRESS_MASK) as usize).delete_recursive(3)?;
+            }
+        }
+        pml4.delete()
+    }
+}
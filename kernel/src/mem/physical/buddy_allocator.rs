@@ -6,8 +6,6 @@ use crate::math::Math;
 
 use super::{Error, PAGE_SIZE};
 
-static mut BUDDY_ALLOCATOR: BuddyAllocator = BuddyAllocator::null();
-
 struct PageInfo {
     /// - True: Free
     /// - False: Used
@@ -37,7 +35,7 @@ pub struct BuddyAllocator {
     free_list: &'static mut [usize],
 }
 impl BuddyAllocator {
-    const fn null() -> Self {
+    pub const fn null() -> Self {
         Self {
             page_count: 0,
             max_order: 0,
@@ -46,50 +44,44 @@ impl BuddyAllocator {
         }
     }
 
-    pub fn pre_init(memory_size: usize) -> usize {
-        unsafe {
-            BUDDY_ALLOCATOR.page_count = (memory_size + PAGE_SIZE - 1) / PAGE_SIZE;
-            BUDDY_ALLOCATOR.max_order = BUDDY_ALLOCATOR.page_count.log2() as u8;
-            size_of::<PageInfo>() * BUDDY_ALLOCATOR.page_count
-                + size_of::<usize>() * (BUDDY_ALLOCATOR.max_order as usize + 1)
-        }
+    pub fn pre_init(&mut self, memory_size: usize) -> usize {
+        self.page_count = memory_size.div_ceil(PAGE_SIZE);
+        self.max_order = self.page_count.log2() as u8;
+        size_of::<PageInfo>() * self.page_count + size_of::<usize>() * (self.max_order as usize + 1)
     }
 
-    pub fn init(addr: usize) {
-        unsafe {
-            BUDDY_ALLOCATOR.page_info =
-                from_raw_parts_mut(addr as *mut PageInfo, BUDDY_ALLOCATOR.page_count);
-            for i in 0..(BUDDY_ALLOCATOR.page_count) {
-                BUDDY_ALLOCATOR.page_info[i] = PageInfo::null();
-            }
+    pub fn init(&mut self, addr: usize) {
+        self.page_info = unsafe { from_raw_parts_mut(addr as *mut PageInfo, self.page_count) };
+        for i in 0..(self.page_count) {
+            self.page_info[i] = PageInfo::null();
+        }
 
-            BUDDY_ALLOCATOR.free_list = from_raw_parts_mut(
-                (addr + size_of::<PageInfo>() * BUDDY_ALLOCATOR.page_count) as *mut usize,
-                BUDDY_ALLOCATOR.max_order as usize + 1,
-            );
-            for i in 0..=(BUDDY_ALLOCATOR.max_order as usize) {
-                BUDDY_ALLOCATOR.free_list[i] = 0;
-            }
+        self.free_list = unsafe {
+            from_raw_parts_mut(
+                (addr + size_of::<PageInfo>() * self.page_count) as *mut usize,
+                self.max_order as usize + 1,
+            )
+        };
+        for i in 0..=(self.max_order as usize) {
+            self.free_list[i] = 0;
         }
     }
 
-    pub fn add(addr: usize, mut count: usize) -> Result<(), Error> {
+    pub fn add(&mut self, addr: usize, mut count: usize) -> Result<(), Error> {
         let mut index = addr / PAGE_SIZE;
         while count > 0 {
-            let mut order = unsafe { BUDDY_ALLOCATOR.max_order };
+            let mut order = self.max_order;
             while order > 0 && ((1 << order) > count || (index & ((1 << order) - 1)) != 0) {
                 order -= 1;
             }
-            if index + (1 << order) > unsafe { BUDDY_ALLOCATOR.page_count } {
+            if index + (1 << order) > self.page_count {
                 return Err(Error::InvalidIndex);
             }
 
-            unsafe {
-                BUDDY_ALLOCATOR.page_info[index].state = true;
-                BUDDY_ALLOCATOR.page_info[index].order = order;
-                BUDDY_ALLOCATOR.page_info[index].next = BUDDY_ALLOCATOR.free_list[order as usize];
-                BUDDY_ALLOCATOR.free_list[order as usize] = index;
-            }
+            self.page_info[index].state = true;
+            self.page_info[index].order = order;
+            self.page_info[index].next = self.free_list[order as usize];
+            self.free_list[order as usize] = index;
 
             index += 1 << order;
             count -= 1 << order;
@@ -97,9 +89,9 @@ impl BuddyAllocator {
         Ok(())
     }
 
-    pub fn allocate(size: usize) -> Result<usize, Error> {
-        let pages = (size + PAGE_SIZE - 1) / PAGE_SIZE;
-        if pages == 0 || pages > (1 << unsafe { BUDDY_ALLOCATOR.max_order }) {
+    pub fn allocate(&mut self, size: usize) -> Result<usize, Error> {
+        let pages = size.div_ceil(PAGE_SIZE);
+        if pages == 0 || pages > (1 << self.max_order) {
             return Err(Error::InvalidAllocationSize);
         }
 
@@ -109,79 +101,69 @@ impl BuddyAllocator {
         }
 
         let mut current_order = order;
-        unsafe {
-            while current_order <= BUDDY_ALLOCATOR.max_order {
-                let head = BUDDY_ALLOCATOR.free_list[current_order as usize];
-                if head != 0 {
-                    BUDDY_ALLOCATOR.free_list[current_order as usize] =
-                        BUDDY_ALLOCATOR.page_info[head].next;
-
-                    while current_order > order {
-                        current_order -= 1;
-                        let buddy = head + (1 << current_order);
-                        BUDDY_ALLOCATOR.page_info[buddy].state = true;
-                        BUDDY_ALLOCATOR.page_info[buddy].order = current_order;
-                        BUDDY_ALLOCATOR.page_info[buddy].next =
-                            BUDDY_ALLOCATOR.free_list[current_order as usize];
-                        BUDDY_ALLOCATOR.free_list[current_order as usize] = buddy;
-                    }
+        while current_order <= self.max_order {
+            let head = self.free_list[current_order as usize];
+            if head != 0 {
+                self.free_list[current_order as usize] = self.page_info[head].next;
+
+                while current_order > order {
+                    current_order -= 1;
+                    let buddy = head + (1 << current_order);
+                    self.page_info[buddy].state = true;
+                    self.page_info[buddy].order = current_order;
+                    self.page_info[buddy].next = self.free_list[current_order as usize];
+                    self.free_list[current_order as usize] = buddy;
+                }
 
-                    BUDDY_ALLOCATOR.page_info[head].state = false;
-                    BUDDY_ALLOCATOR.page_info[head].order = order;
-                    BUDDY_ALLOCATOR.page_info[head].next = 0;
+                self.page_info[head].state = false;
+                self.page_info[head].order = order;
+                self.page_info[head].next = 0;
 
-                    return Ok(head * PAGE_SIZE);
-                }
-                current_order += 1;
+                return Ok(head * PAGE_SIZE);
             }
+            current_order += 1;
         }
         Err(Error::OutOfMemory)
     }
 
-    pub fn deallocate(addr: usize) -> Result<(), Error> {
+    pub fn deallocate(&mut self, addr: usize) -> Result<(), Error> {
         let index = addr / PAGE_SIZE;
-        unsafe {
-            if index >= BUDDY_ALLOCATOR.page_count {
-                return Err(Error::InvalidIndex);
-            }
+        if index >= self.page_count {
+            return Err(Error::InvalidIndex);
+        }
 
-            let mut index = index;
-            BUDDY_ALLOCATOR.page_info[index].state = true;
+        let mut index = index;
+        self.page_info[index].state = true;
 
-            let mut order = BUDDY_ALLOCATOR.page_info[index].order;
-            while order < BUDDY_ALLOCATOR.max_order {
-                let buddy = index ^ (1 << order);
+        let mut order = self.page_info[index].order;
+        while order < self.max_order {
+            let buddy = index ^ (1 << order);
 
-                if !BUDDY_ALLOCATOR.page_info[buddy].state
-                    || BUDDY_ALLOCATOR.page_info[buddy].order != order
-                {
-                    break;
-                }
+            if !self.page_info[buddy].state || self.page_info[buddy].order != order {
+                break;
+            }
 
-                let mut prev = 0;
-                let mut curr = BUDDY_ALLOCATOR.free_list[order as usize];
-                while curr != 0 {
-                    if curr == buddy {
-                        if prev == 0 {
-                            BUDDY_ALLOCATOR.free_list[order as usize] =
-                                BUDDY_ALLOCATOR.page_info[curr].next;
-                        } else {
-                            BUDDY_ALLOCATOR.page_info[prev].next =
-                                BUDDY_ALLOCATOR.page_info[curr].next;
-                        }
-                        break;
+            let mut prev = 0;
+            let mut curr = self.free_list[order as usize];
+            while curr != 0 {
+                if curr == buddy {
+                    if prev == 0 {
+                        self.free_list[order as usize] = self.page_info[curr].next;
+                    } else {
+                        self.page_info[prev].next = self.page_info[curr].next;
                     }
-                    prev = curr;
-                    curr = BUDDY_ALLOCATOR.page_info[curr].next;
+                    break;
                 }
-
-                index = if index < buddy { index } else { buddy };
-                order += 1;
+                prev = curr;
+                curr = self.page_info[curr].next;
             }
-            BUDDY_ALLOCATOR.page_info[index].order = order;
-            BUDDY_ALLOCATOR.page_info[index].next = BUDDY_ALLOCATOR.free_list[order as usize];
-            BUDDY_ALLOCATOR.free_list[order as usize] = index;
+
+            index = if index < buddy { index } else { buddy };
+            order += 1;
         }
+        self.page_info[index].order = order;
+        self.page_info[index].next = self.free_list[order as usize];
+        self.free_list[order as usize] = index;
         Ok(())
     }
 }
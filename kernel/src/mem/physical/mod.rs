@@ -1,13 +1,17 @@
 //! Physical
 
+use crate::sync::IrqSpinlock;
+
 use super::{Error, PAGE_SIZE};
 
 mod buddy_allocator;
 
 use buddy_allocator::BuddyAllocator;
 
+static BUDDY_ALLOCATOR: IrqSpinlock<BuddyAllocator> = IrqSpinlock::new(BuddyAllocator::null());
+
 #[repr(C)]
-struct Descriptor {
+pub struct Descriptor {
     /// - 0: Reserved
     /// - 1: Loader Code
     /// - 2: Loader Data
@@ -25,12 +29,12 @@ struct Descriptor {
     /// - 14: Persistent Memory
     /// - 15: Unaccepted
     /// - 16: Max
-    type_: u32,
+    pub type_: u32,
 
-    phys_start: u64,
-    virt_start: u64,
+    pub phys_start: u64,
+    pub virt_start: u64,
 
-    page_count: u64,
+    pub page_count: u64,
 
     /// - Bit 0: Uncacheable
     /// - Bit 1: Write Combine
@@ -48,23 +52,37 @@ struct Descriptor {
     /// - Bits 44 ..= 59: ISA Mask
     /// - Bit 62: ISA Valid
     /// - Bit 63: Runtime
-    attributes: u64,
+    pub attributes: u64,
+}
+impl Descriptor {
+    /// Firmware keeps using it after exiting boot services
+    pub fn is_runtime(&self) -> bool {
+        self.attributes & 1 << 63 != 0
+    }
+}
+
+/// Entries of the memory map the bootloader got from `ExitBootServices`
+pub fn descriptors(
+    entry: usize,
+    descriptor_size: usize,
+    descriptor_count: usize,
+) -> impl Iterator<Item = &'static mut Descriptor> {
+    (0..descriptor_count)
+        .map(move |i| unsafe { &mut *((entry + i * descriptor_size) as *mut Descriptor) })
 }
 
 pub fn init(entry: usize, descriptor_size: usize, descriptor_count: usize) -> Result<(), Error> {
     let mut size = 0;
-    for i in 0..descriptor_count {
-        let descriptor = unsafe { &*((entry + i * descriptor_size) as *const Descriptor) };
+    for descriptor in descriptors(entry, descriptor_size, descriptor_count) {
         let phys_end = descriptor.phys_start as usize + PAGE_SIZE * descriptor.page_count as usize;
         if phys_end > size {
             size = phys_end
         };
     }
-    let pending_allocation_page_count =
-        (BuddyAllocator::pre_init(size) + PAGE_SIZE - 1) / PAGE_SIZE;
+    let mut buddy_allocator = BUDDY_ALLOCATOR.lock();
+    let pending_allocation_page_count = buddy_allocator.pre_init(size).div_ceil(PAGE_SIZE);
     let mut allocate_addr = 0;
-    for i in 0..descriptor_count {
-        let descriptor = unsafe { &*((entry + i * descriptor_size) as *const Descriptor) };
+    for descriptor in descriptors(entry, descriptor_size, descriptor_count) {
         if descriptor.type_ != 7 {
             continue;
         }
@@ -74,21 +92,20 @@ pub fn init(entry: usize, descriptor_size: usize, descriptor_count: usize) -> Re
         allocate_addr = descriptor.phys_start as usize;
         break;
     }
-    BuddyAllocator::init(allocate_addr);
-    for i in 0..descriptor_count {
-        let descriptor = unsafe { &*((entry + i * descriptor_size) as *const Descriptor) };
+    buddy_allocator.init(allocate_addr);
+    for descriptor in descriptors(entry, descriptor_size, descriptor_count) {
         if descriptor.type_ != 7 {
             continue;
         }
         if descriptor.phys_start as usize == allocate_addr
             && descriptor.page_count as usize > pending_allocation_page_count
         {
-            BuddyAllocator::add(
+            buddy_allocator.add(
                 descriptor.phys_start as usize + pending_allocation_page_count * PAGE_SIZE,
                 descriptor.page_count as usize - pending_allocation_page_count,
             )?;
         } else {
-            BuddyAllocator::add(
+            buddy_allocator.add(
                 descriptor.phys_start as usize,
                 descriptor.page_count as usize,
             )?;
@@ -98,9 +115,9 @@ pub fn init(entry: usize, descriptor_size: usize, descriptor_count: usize) -> Re
 }
 
 pub fn allocate(size: usize) -> Result<usize, Error> {
-    BuddyAllocator::allocate(size)
+    BUDDY_ALLOCATOR.lock().allocate(size)
 }
 
 pub fn deallocate(addr: usize) -> Result<(), Error> {
-    BuddyAllocator::deallocate(addr)
+    BUDDY_ALLOCATOR.lock().deallocate(addr)
 }
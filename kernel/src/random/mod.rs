@@ -0,0 +1,137 @@
+//! Random
+//!
+//! ChaCha20 CSPRNG with fast key erasure: every request ends by drawing the next key from the
+//! keystream, so the state never reveals bytes already handed out.
+//! The key is seeded from RDSEED or RDRAND where the processor has them, and from the jitter
+//! of interrupt arrival times, folded in once enough interrupts piled up.
+
+use core::{
+    hint::spin_loop,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::{
+    sync::{IrqSpinlock, Lazy},
+    warn,
+    x86_64::{
+        cpu::{self, Feature},
+        rng,
+    },
+};
+
+mod chacha;
+
+/// Interrupts folded into the key at once
+const RESEED_JITTER_COUNT: usize = 64;
+
+/// Bytes handed out before hardware entropy is mixed in again
+const RESEED_SIZE: usize = 1 << 20;
+
+/// Timestamps read per seed word without a hardware source
+const FALLBACK_SAMPLE_COUNT: usize = 256;
+
+/// Seeded on first use, which must come after CPU features are detected
+static STATE: Lazy<IrqSpinlock<State>> = Lazy::new(|| IrqSpinlock::new(State::seed()));
+
+/// Arrival times of interrupts since the last fold, mixed without locking
+static JITTER: AtomicU64 = AtomicU64::new(0);
+static JITTER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct State {
+    key: [u32; 8],
+
+    /// Bytes handed out since the last reseed
+    generated: usize,
+}
+impl State {
+    fn seed() -> Self {
+        let features = cpu::features();
+        if !features.has(Feature::RDSEED) && !features.has(Feature::RDRAND) {
+            warn!(
+                "random",
+                "No RDSEED or RDRAND, seeding from timestamp jitter"
+            );
+        }
+        let mut state = Self {
+            key: [0; 8],
+            generated: 0,
+        };
+        state.reseed();
+        state
+    }
+
+    fn reseed(&mut self) {
+        let mut seed = [0u64; 4];
+        for word in &mut seed {
+            *word = rng::rdseed()
+                .or_else(rng::rdrand)
+                .unwrap_or_else(timestamp_jitter);
+        }
+        self.mix(&seed);
+        self.generated = 0;
+    }
+
+    /// Spreads `words` over the whole key
+    fn mix(&mut self, words: &[u64]) {
+        for (i, word) in words.iter().enumerate() {
+            self.key[2 * i % 8] ^= *word as u32;
+            self.key[(2 * i + 1) % 8] ^= (*word >> 32) as u32;
+        }
+        self.rekey(0);
+    }
+
+    /// Replaces the key with keystream block `counter`
+    fn rekey(&mut self, counter: u64) {
+        let block = chacha::block(&self.key, counter);
+        for (word, bytes) in self.key.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+    }
+}
+
+/// Differences between back-to-back timestamps, for processors without RDSEED or RDRAND
+fn timestamp_jitter() -> u64 {
+    let mut value = 0u64;
+    let mut last = rng::rdtsc();
+    for _ in 0..FALLBACK_SAMPLE_COUNT {
+        spin_loop();
+        let now = rng::rdtsc();
+        value = value.rotate_left(5) ^ now.wrapping_sub(last);
+        last = now;
+    }
+    value
+}
+
+/// Called on every interrupt, cheap enough for the hottest vector
+pub fn add_jitter() {
+    let timestamp = rng::rdtsc();
+    let _ = JITTER.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pool| {
+        Some(pool.rotate_left(7) ^ timestamp)
+    });
+    JITTER_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Seeds the CSPRNG now rather than on the first request
+pub fn init() {
+    drop(STATE.lock());
+}
+
+/// Fills `buf` with cryptographically secure random bytes
+pub fn fill(buf: &mut [u8]) {
+    let mut state = STATE.lock();
+    if JITTER_COUNT.load(Ordering::Relaxed) >= RESEED_JITTER_COUNT {
+        JITTER_COUNT.store(0, Ordering::Relaxed);
+        state.mix(&[JITTER.swap(0, Ordering::Relaxed)]);
+    }
+    if state.generated >= RESEED_SIZE {
+        state.reseed();
+    }
+
+    let mut counter = 0;
+    for chunk in buf.chunks_mut(chacha::BLOCK_SIZE) {
+        chunk.copy_from_slice(&chacha::block(&state.key, counter)[..chunk.len()]);
+        counter += 1;
+    }
+    state.generated += buf.len();
+    state.rekey(counter);
+}
@@ -0,0 +1,33 @@
+//! Error
+
+pub enum Error {
+    InvalidAnchor,
+    InvalidChecksum,
+    InvalidLength,
+    InvalidStructure(u16),
+    Unsupported(&'static str),
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::SMBIOS(err)
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "SMBIOS ".out();
+        match self {
+            Error::InvalidAnchor => "Entry Point Anchor",
+            Error::InvalidChecksum => "Entry Point Checksum",
+            Error::InvalidLength => "Entry Point Length",
+            Error::InvalidStructure(handle) => {
+                (*handle as usize).out();
+                " Structure"
+            }
+            Error::Unsupported(feature) => {
+                feature.out();
+                " Unsupported"
+            }
+        }
+        .out();
+    }
+}
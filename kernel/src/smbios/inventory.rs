@@ -0,0 +1,277 @@
+//! Inventory
+//!
+//! Fields of the structure types the kernel reports, `None` where firmware left them out or
+//! marked them unknown.
+
+use crate::{io::text::Output, types::guid::GUID};
+
+use super::Structure;
+
+fn out_string(string: Option<&[u8]>) {
+    string.unwrap_or(b"Unknown").out();
+}
+
+/// BIOS Information, type 0
+pub struct Bios {
+    pub vendor: Option<&'static [u8]>,
+    pub version: Option<&'static [u8]>,
+    pub release_date: Option<&'static [u8]>,
+
+    /// Major and minor release, from SMBIOS 2.4 on
+    pub release: Option<(u8, u8)>,
+}
+impl Bios {
+    /// - Byte 4: Vendor
+    /// - Byte 5: Version
+    /// - Byte 8: Release Date
+    /// - Bytes 0x14 ..= 0x15: System BIOS Major and Minor Release, 0xFF if none
+    pub fn new(structure: &Structure) -> Self {
+        Self {
+            vendor: structure.string(0x04),
+            version: structure.string(0x05),
+            release_date: structure.string(0x08),
+            release: structure
+                .read::<u8>(0x14)
+                .zip(structure.read::<u8>(0x15))
+                .filter(|&(major, _)| major != 0xFF),
+        }
+    }
+}
+impl Output for Bios {
+    /// `BIOS EDK II 0.0.0 02/06/2015`
+    fn out(&self) {
+        "BIOS ".out();
+        out_string(self.vendor);
+        " ".out();
+        out_string(self.version);
+        " ".out();
+        out_string(self.release_date);
+    }
+}
+
+/// System Information, type 1
+pub struct System {
+    pub manufacturer: Option<&'static [u8]>,
+    pub product: Option<&'static [u8]>,
+    pub version: Option<&'static [u8]>,
+    pub serial_number: Option<&'static [u8]>,
+    pub uuid: Option<GUID>,
+    pub sku_number: Option<&'static [u8]>,
+    pub family: Option<&'static [u8]>,
+}
+impl System {
+    /// - Byte 4: Manufacturer
+    /// - Byte 5: Product Name
+    /// - Byte 6: Version
+    /// - Byte 7: Serial Number
+    /// - Bytes 8 ..= 0x17: UUID, all zeros if absent and all ones if not yet set
+    /// - Byte 0x19: SKU Number
+    /// - Byte 0x1A: Family
+    pub fn new(structure: &Structure) -> Self {
+        Self {
+            manufacturer: structure.string(0x04),
+            product: structure.string(0x05),
+            version: structure.string(0x06),
+            serial_number: structure.string(0x07),
+            uuid: structure
+                .read::<[u8; 16]>(0x08)
+                .filter(|uuid| *uuid != [0; 16] && *uuid != [0xFF; 16])
+                .and_then(|_| structure.read::<GUID>(0x08)),
+            sku_number: structure.string(0x19),
+            family: structure.string(0x1A),
+        }
+    }
+}
+impl Output for System {
+    /// `System QEMU Standard PC (Q35 + ICH9, 2009) pc-q35-9.2`
+    fn out(&self) {
+        "System ".out();
+        out_string(self.manufacturer);
+        " ".out();
+        out_string(self.product);
+        " ".out();
+        out_string(self.version);
+        if let Some(uuid) = self.uuid {
+            " UUID ".out();
+            uuid.out();
+        }
+    }
+}
+
+/// Baseboard Information, type 2
+pub struct Baseboard {
+    pub manufacturer: Option<&'static [u8]>,
+    pub product: Option<&'static [u8]>,
+    pub version: Option<&'static [u8]>,
+    pub serial_number: Option<&'static [u8]>,
+}
+impl Baseboard {
+    /// - Byte 4: Manufacturer
+    /// - Byte 5: Product
+    /// - Byte 6: Version
+    /// - Byte 7: Serial Number
+    pub fn new(structure: &Structure) -> Self {
+        Self {
+            manufacturer: structure.string(0x04),
+            product: structure.string(0x05),
+            version: structure.string(0x06),
+            serial_number: structure.string(0x07),
+        }
+    }
+}
+impl Output for Baseboard {
+    fn out(&self) {
+        "Baseboard ".out();
+        out_string(self.manufacturer);
+        " ".out();
+        out_string(self.product);
+        " ".out();
+        out_string(self.version);
+    }
+}
+
+/// Processor Information, type 4
+pub struct Processor {
+    pub socket: Option<&'static [u8]>,
+    pub manufacturer: Option<&'static [u8]>,
+    pub version: Option<&'static [u8]>,
+
+    /// MHz
+    pub max_speed: Option<u16>,
+    pub current_speed: Option<u16>,
+
+    pub populated: bool,
+
+    /// Per socket, from SMBIOS 2.5 on
+    pub core_count: Option<u16>,
+    pub thread_count: Option<u16>,
+}
+impl Processor {
+    /// - Byte 4: Socket Designation
+    /// - Byte 7: Processor Manufacturer
+    /// - Byte 0x10: Processor Version
+    /// - Bytes 0x14 ..= 0x15: Max Speed
+    /// - Bytes 0x16 ..= 0x17: Current Speed
+    /// - Byte 0x18: Status
+    ///   - Bit 6: Socket Populated
+    /// - Byte 0x23: Core Count, 0xFF to read Core Count 2 at 0x2A
+    /// - Byte 0x25: Thread Count, 0xFF to read Thread Count 2 at 0x2E
+    pub fn new(structure: &Structure) -> Self {
+        let count = |offset, offset2| match structure.read::<u8>(offset)? {
+            0 => None,
+            0xFF => structure.read::<u16>(offset2).filter(|&count| count != 0),
+            count => Some(count as u16),
+        };
+        Self {
+            socket: structure.string(0x04),
+            manufacturer: structure.string(0x07),
+            version: structure.string(0x10),
+            max_speed: structure.read::<u16>(0x14).filter(|&speed| speed != 0),
+            current_speed: structure.read::<u16>(0x16).filter(|&speed| speed != 0),
+            populated: structure.read::<u8>(0x18).unwrap_or(0) & 1 << 6 != 0,
+            core_count: count(0x23, 0x2A),
+            thread_count: count(0x25, 0x2E),
+        }
+    }
+}
+impl Output for Processor {
+    /// `CPU 0: QEMU pc-q35-9.2, 1 cores 1 threads at 2000 MHz`
+    fn out(&self) {
+        out_string(self.socket);
+        ": ".out();
+        if !self.populated {
+            return "Empty".out();
+        }
+        out_string(self.version);
+        if let Some(count) = self.core_count {
+            ", ".out();
+            (count as usize).out();
+            " cores".out();
+        }
+        if let Some(count) = self.thread_count {
+            " ".out();
+            (count as usize).out();
+            " threads".out();
+        }
+        if let Some(speed) = self.current_speed {
+            " at ".out();
+            (speed as usize).out();
+            " MHz".out();
+        }
+    }
+}
+
+/// Memory Device, type 17
+pub struct MemoryDevice {
+    pub locator: Option<&'static [u8]>,
+    pub bank_locator: Option<&'static [u8]>,
+
+    /// Bytes, zero for an empty slot
+    pub size: Option<u64>,
+
+    /// MT/s
+    pub speed: Option<u16>,
+
+    pub manufacturer: Option<&'static [u8]>,
+    pub serial_number: Option<&'static [u8]>,
+    pub part_number: Option<&'static [u8]>,
+}
+impl MemoryDevice {
+    /// - Bytes 0x0C ..= 0x0D: Size, 0xFFFF if unknown
+    ///   - Bits 0 ..= 14: Size in MiB, or in KiB if bit 15 is set
+    ///   - 0x7FFF: Size in MiB in Extended Size at 0x1C
+    /// - Byte 0x10: Device Locator
+    /// - Byte 0x11: Bank Locator
+    /// - Bytes 0x15 ..= 0x16: Speed
+    /// - Byte 0x17: Manufacturer
+    /// - Byte 0x18: Serial Number
+    /// - Byte 0x1A: Part Number
+    pub fn new(structure: &Structure) -> Self {
+        let size = match structure.read::<u16>(0x0C) {
+            None | Some(0xFFFF) => None,
+            Some(0x7FFF) => structure
+                .read::<u32>(0x1C)
+                .map(|size| ((size & 0x7FFF_FFFF) as u64) << 20),
+            Some(size) if size & 1 << 15 != 0 => Some(((size & 0x7FFF) as u64) << 10),
+            Some(size) => Some((size as u64) << 20),
+        };
+        Self {
+            locator: structure.string(0x10),
+            bank_locator: structure.string(0x11),
+            size,
+            speed: structure.read::<u16>(0x15).filter(|&speed| speed != 0),
+            manufacturer: structure.string(0x17),
+            serial_number: structure.string(0x18),
+            part_number: structure.string(0x1A),
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.size != Some(0)
+    }
+}
+impl Output for MemoryDevice {
+    /// `DIMM 0: 512 MiB QEMU`
+    fn out(&self) {
+        out_string(self.locator);
+        ": ".out();
+        match self.size {
+            Some(0) => return "Empty".out(),
+            Some(size) => {
+                ((size >> 20) as usize).out();
+                " MiB ".out();
+            }
+            None => "Unknown Size ".out(),
+        }
+        out_string(self.manufacturer);
+        if let Some(part_number) = self.part_number {
+            " ".out();
+            part_number.out();
+        }
+        if let Some(speed) = self.speed {
+            " at ".out();
+            (speed as usize).out();
+            " MT/s".out();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! System Management BIOS
+//!
+//! Hardware inventory firmware describes as a table of structures, each a formatted part followed
+//! by the strings it refers to. The bootloader finds the SMBIOS 3.0 entry point, with a 64-bit
+//! table address, or the 2.1 one on older firmware.
+
+use core::{ptr::addr_of, slice::from_raw_parts};
+
+use crate::{info, math::Checksum, mem::Memory, sync::Once};
+
+mod error;
+mod inventory;
+mod structure;
+
+pub use error::Error;
+pub use inventory::{Baseboard, Bios, MemoryDevice, Processor, System};
+pub use structure::Structure;
+use structure::Structures;
+
+/// Structure types
+pub const BIOS: u8 = 0;
+pub const SYSTEM: u8 = 1;
+pub const BASEBOARD: u8 = 2;
+pub const PROCESSOR: u8 = 4;
+pub const MEMORY_DEVICE: u8 = 17;
+const END_OF_TABLE: u8 = 127;
+
+static TABLE: Once<Table> = Once::new();
+
+#[derive(Clone, Copy)]
+struct Table {
+    addr: usize,
+
+    /// Bytes, an upper bound for SMBIOS 3.0 which ends the table with an End-of-Table structure
+    size: usize,
+
+    major: u8,
+    minor: u8,
+}
+
+/// SMBIOS 3.0 Entry Point
+#[repr(C, packed)]
+struct EntryPoint3 {
+    /// "_SM3_"
+    anchor: [u8; 5],
+
+    checksum: u8,
+
+    length: u8,
+
+    major: u8,
+    minor: u8,
+    docrev: u8,
+
+    /// 1: SMBIOS 3.0
+    revision: u8,
+
+    reserved: u8,
+
+    max_size: u32,
+
+    table_address: u64,
+}
+impl Checksum for EntryPoint3 {}
+impl Memory for EntryPoint3 {}
+impl EntryPoint3 {
+    fn init(&self) -> Result<Table, Error> {
+        if (self.length as usize) < size_of::<Self>() {
+            return Err(Error::InvalidLength);
+        }
+        if !self.checksum(self.length as usize) {
+            return Err(Error::InvalidChecksum);
+        }
+        if self.revision != 1 {
+            return Err(Error::Unsupported("Entry Point Revision"));
+        }
+        Ok(Table {
+            addr: self.table_address as usize,
+            size: self.max_size as usize,
+            major: self.major,
+            minor: self.minor,
+        })
+    }
+}
+
+/// SMBIOS 2.1 Entry Point
+#[repr(C, packed)]
+struct EntryPoint2 {
+    /// "_SM_"
+    anchor: [u8; 4],
+
+    checksum: u8,
+
+    /// 0x1F, or 0x1E where firmware copied a mistake of SMBIOS 2.1
+    length: u8,
+
+    major: u8,
+    minor: u8,
+
+    max_structure_size: u16,
+
+    revision: u8,
+
+    formatted_area: [u8; 5],
+
+    /// "_DMI_", starting the 15 bytes `intermediate_checksum` covers
+    intermediate_anchor: [u8; 5],
+
+    intermediate_checksum: u8,
+
+    table_length: u16,
+
+    table_address: u32,
+
+    structure_count: u16,
+
+    bcd_revision: u8,
+}
+impl Checksum for EntryPoint2 {}
+impl Memory for EntryPoint2 {}
+impl EntryPoint2 {
+    fn init(&self) -> Result<Table, Error> {
+        if (self.length as usize) < size_of::<Self>() - 1 {
+            return Err(Error::InvalidLength);
+        }
+        if self.intermediate_anchor != *b"_DMI_" {
+            return Err(Error::InvalidAnchor);
+        }
+        let intermediate =
+            unsafe { from_raw_parts(addr_of!(self.intermediate_anchor) as *const u8, 15) };
+        if !self.checksum(self.length as usize)
+            || intermediate.iter().copied().fold(0u8, u8::wrapping_add) != 0
+        {
+            return Err(Error::InvalidChecksum);
+        }
+        Ok(Table {
+            addr: self.table_address as usize,
+            size: self.table_length as usize,
+            major: self.major,
+            minor: self.minor,
+        })
+    }
+}
+
+/// Every structure before the End-of-Table one
+pub fn structures() -> impl Iterator<Item = Structure> {
+    let structures = match TABLE.get() {
+        Some(table) => Structures::new(table.addr, table.size),
+        None => Structures::new(0, 0),
+    };
+    structures.map_while(Result::ok)
+}
+
+/// Structures of `type_`, in table order
+pub fn find(type_: u8) -> impl Iterator<Item = Structure> {
+    structures().filter(move |structure| structure.type_() == type_)
+}
+
+pub fn bios() -> Option<Bios> {
+    find(BIOS).next().map(|structure| Bios::new(&structure))
+}
+
+pub fn system() -> Option<System> {
+    find(SYSTEM).next().map(|structure| System::new(&structure))
+}
+
+pub fn baseboard() -> Option<Baseboard> {
+    find(BASEBOARD)
+        .next()
+        .map(|structure| Baseboard::new(&structure))
+}
+
+pub fn processors() -> impl Iterator<Item = Processor> {
+    find(PROCESSOR).map(|structure| Processor::new(&structure))
+}
+
+pub fn memory_devices() -> impl Iterator<Item = MemoryDevice> {
+    find(MEMORY_DEVICE).map(|structure| MemoryDevice::new(&structure))
+}
+
+/// Validates the entry point at `addr` and every structure of its table, then logs the inventory
+///
+/// - `addr`: Zero when firmware has no SMBIOS
+pub fn init(addr: usize) -> Result<(), Error> {
+    if addr == 0 {
+        info!("SMBIOS", "No entry point");
+        return Ok(());
+    }
+    let anchor = unsafe { from_raw_parts(addr as *const u8, 5) };
+    let table = if anchor == b"_SM3_" {
+        EntryPoint3::get_ref(addr).init()?
+    } else if anchor.starts_with(b"_SM_") {
+        EntryPoint2::get_ref(addr).init()?
+    } else {
+        return Err(Error::InvalidAnchor);
+    };
+    let mut count = 0usize;
+    for structure in Structures::new(table.addr, table.size) {
+        structure?;
+        count += 1;
+    }
+    let table = TABLE.call_once(|| table);
+
+    info!(
+        "SMBIOS",
+        "Version ", table.major as usize, ".", table.minor as usize, ", ", count, " structures"
+    );
+    if let Some(bios) = bios() {
+        info!("SMBIOS", bios);
+    }
+    if let Some(system) = system() {
+        info!("SMBIOS", system);
+    }
+    if let Some(baseboard) = baseboard() {
+        info!("SMBIOS", baseboard);
+    }
+    for processor in processors() {
+        info!("SMBIOS", processor);
+    }
+    let mut memory = 0;
+    for device in memory_devices().filter(MemoryDevice::is_installed) {
+        memory += device.size.unwrap_or(0);
+        info!("SMBIOS", device);
+    }
+    info!(
+        "SMBIOS",
+        "Memory ",
+        (memory >> 20) as usize,
+        " MiB installed"
+    );
+    Ok(())
+}
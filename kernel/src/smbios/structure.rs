@@ -0,0 +1,101 @@
+//! Structure
+
+use core::{ptr::read_unaligned, slice::from_raw_parts};
+
+use super::Error;
+
+/// - Byte 0: Type
+/// - Byte 1: Length of the formatted part, header included
+/// - Bytes 2 ..= 3: Handle
+/// - Bytes Length ..: Strings the formatted part refers to by index, each ending in NUL, the set
+///   ending in another
+#[derive(Clone, Copy)]
+pub struct Structure {
+    addr: usize,
+
+    /// Formatted part
+    len: usize,
+
+    /// Formatted part and strings
+    size: usize,
+}
+impl Structure {
+    /// Structure at `addr`, or `None` if no header fits before `end`
+    fn parse(addr: usize, end: usize) -> Result<Option<Self>, Error> {
+        if addr + 4 > end {
+            return Ok(None);
+        }
+        let len = unsafe { read_unaligned((addr + 1) as *const u8) } as usize;
+        let handle = unsafe { read_unaligned((addr + 2) as *const u16) };
+        if len < 4 || addr + len > end {
+            return Err(Error::InvalidStructure(handle));
+        }
+        // Without strings the set is two NULs alone
+        let strings = unsafe { from_raw_parts((addr + len) as *const u8, end - addr - len) };
+        let strings_len = strings
+            .windows(2)
+            .position(|pair| pair == [0, 0])
+            .ok_or(Error::InvalidStructure(handle))?
+            + 2;
+        Ok(Some(Self {
+            addr,
+            len,
+            size: len + strings_len,
+        }))
+    }
+
+    pub fn type_(&self) -> u8 {
+        unsafe { read_unaligned(self.addr as *const u8) }
+    }
+
+    /// Field at `offset`, `None` past the formatted part, which older versions end early
+    pub fn read<T: Copy>(&self, offset: usize) -> Option<T> {
+        (offset + size_of::<T>() <= self.len)
+            .then(|| unsafe { read_unaligned((self.addr + offset) as *const T) })
+    }
+
+    /// String whose index, counted from 1, is the byte at `offset`; index 0 names none
+    pub fn string(&self, offset: usize) -> Option<&'static [u8]> {
+        let index = self.read::<u8>(offset)? as usize;
+        let strings =
+            unsafe { from_raw_parts((self.addr + self.len) as *const u8, self.size - self.len) };
+        strings
+            .split(|&c| c == 0)
+            .nth(index.checked_sub(1)?)
+            .filter(|string| !string.is_empty())
+    }
+}
+
+/// Structures from `addr` up to `end` or the End-of-Table one
+pub struct Structures {
+    addr: usize,
+    end: usize,
+}
+impl Structures {
+    pub(super) fn new(addr: usize, size: usize) -> Self {
+        Self {
+            addr,
+            end: addr + size,
+        }
+    }
+}
+impl Iterator for Structures {
+    type Item = Result<Structure, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let structure = match Structure::parse(self.addr, self.end) {
+            Ok(Some(structure)) => structure,
+            Ok(None) => return None,
+            Err(err) => {
+                self.addr = self.end;
+                return Some(Err(err));
+            }
+        };
+        if structure.type_() == super::END_OF_TABLE {
+            self.addr = self.end;
+            return None;
+        }
+        self.addr += structure.size;
+        Some(Ok(structure))
+    }
+}
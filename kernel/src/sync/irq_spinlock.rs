@@ -0,0 +1,83 @@
+//! Interrupt-Saving Spinlock
+
+use core::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+use crate::x86_64::rflags;
+
+use super::{Spinlock, SpinlockGuard};
+
+/// Spinlock that disables interrupts while held
+///
+/// The previous interrupt state is restored on release, so it nests.
+pub struct IrqSpinlock<T> {
+    inner: Spinlock<T>,
+}
+impl<T> IrqSpinlock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            inner: Spinlock::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> IrqSpinlockGuard<'_, T> {
+        let enabled = rflags::interrupts_enabled();
+        rflags::disable_interrupts();
+        IrqSpinlockGuard {
+            guard: ManuallyDrop::new(self.inner.lock()),
+            enabled,
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<IrqSpinlockGuard<'_, T>> {
+        let enabled = rflags::interrupts_enabled();
+        rflags::disable_interrupts();
+        match self.inner.try_lock() {
+            Some(guard) => Some(IrqSpinlockGuard {
+                guard: ManuallyDrop::new(guard),
+                enabled,
+            }),
+            None => {
+                if enabled {
+                    rflags::enable_interrupts();
+                }
+                None
+            }
+        }
+    }
+
+    /// # Safety
+    /// See `Spinlock::force_unlock`.
+    pub unsafe fn force_unlock(&self) {
+        unsafe { self.inner.force_unlock() }
+    }
+}
+
+pub struct IrqSpinlockGuard<'a, T> {
+    guard: ManuallyDrop<SpinlockGuard<'a, T>>,
+
+    /// Interrupt state before locking
+    enabled: bool,
+}
+impl<T> Deref for IrqSpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+impl<T> DerefMut for IrqSpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+impl<T> Drop for IrqSpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if self.enabled {
+            rflags::enable_interrupts();
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Synchronization
+
+mod irq_spinlock;
+mod once;
+mod rwlock;
+mod spinlock;
+
+pub use irq_spinlock::{IrqSpinlock, IrqSpinlockGuard};
+pub use once::{Lazy, Once};
+pub use rwlock::RwLock;
+pub use spinlock::{Spinlock, SpinlockGuard};
@@ -0,0 +1,82 @@
+//! One-Time Initialization
+
+use core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    mem::MaybeUninit,
+    ops::Deref,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+pub struct Once<T> {
+    state: AtomicU8,
+
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+unsafe impl<T: Send + Sync> Sync for Once<T> {}
+unsafe impl<T: Send> Send for Once<T> {}
+impl<T> Once<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Runs `f` on the first call only; later callers wait for it and share its value
+    pub fn call_once<F: FnOnce() -> T>(&self, f: F) -> &T {
+        match self
+            .state
+            .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                unsafe { (*self.data.get()).write(f()) };
+                self.state.store(COMPLETE, Ordering::Release);
+            }
+            Err(_) => {
+                while self.state.load(Ordering::Acquire) != COMPLETE {
+                    spin_loop();
+                }
+            }
+        }
+        unsafe { (*self.data.get()).assume_init_ref() }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        match self.state.load(Ordering::Acquire) {
+            COMPLETE => Some(unsafe { (*self.data.get()).assume_init_ref() }),
+            _ => None,
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
+
+/// Value computed by `init` on first dereference
+pub struct Lazy<T, F = fn() -> T> {
+    once: Once<T>,
+
+    init: F,
+}
+unsafe impl<T: Send + Sync, F: Send> Sync for Lazy<T, F> {}
+impl<T, F: Fn() -> T> Lazy<T, F> {
+    pub const fn new(init: F) -> Self {
+        Self {
+            once: Once::new(),
+            init,
+        }
+    }
+}
+impl<T, F: Fn() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.once.call_once(&self.init)
+    }
+}
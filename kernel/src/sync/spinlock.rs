@@ -0,0 +1,80 @@
+//! Ticket Spinlock
+
+use core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// FIFO-fair spinlock
+///
+/// Not safe against re-entry from interrupt handlers. Use `IrqSpinlock` for state
+/// shared with them.
+pub struct Spinlock<T> {
+    next: AtomicUsize,
+    serving: AtomicUsize,
+
+    data: UnsafeCell<T>,
+}
+unsafe impl<T: Send> Send for Spinlock<T> {}
+unsafe impl<T: Send> Sync for Spinlock<T> {}
+impl<T> Spinlock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> SpinlockGuard<'_, T> {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        while self.serving.load(Ordering::Acquire) != ticket {
+            spin_loop();
+        }
+        SpinlockGuard { lock: self }
+    }
+
+    pub fn try_lock(&self) -> Option<SpinlockGuard<'_, T>> {
+        let ticket = self.serving.load(Ordering::Relaxed);
+        self.next
+            .compare_exchange(ticket, ticket + 1, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinlockGuard { lock: self })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.next.load(Ordering::Relaxed) != self.serving.load(Ordering::Relaxed)
+    }
+
+    /// Releases the lock regardless of its holder
+    ///
+    /// # Safety
+    /// Only for paths that never return to the holder, e.g. panic.
+    pub unsafe fn force_unlock(&self) {
+        self.serving
+            .store(self.next.load(Ordering::Relaxed), Ordering::Release);
+    }
+}
+
+pub struct SpinlockGuard<'a, T> {
+    lock: &'a Spinlock<T>,
+}
+impl<T> Deref for SpinlockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+impl<T> DerefMut for SpinlockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+impl<T> Drop for SpinlockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.serving.fetch_add(1, Ordering::Release);
+    }
+}
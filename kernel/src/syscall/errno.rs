@@ -0,0 +1,73 @@
+//! Error Number
+
+/// Returned negated in RAX, numbered as on Linux
+#[repr(i64)]
+pub enum Errno {
+    /// No such file
+    NoEnt = 2,
+
+    /// I/O error
+    IO = 5,
+
+    /// Executable format error
+    NoExec = 8,
+
+    /// Bad file descriptor
+    BadFd = 9,
+
+    /// No such child task
+    Child = 10,
+
+    /// Out of task or file slots
+    Again = 11,
+
+    /// Out of memory
+    NoMem = 12,
+
+    /// Bad user address
+    Fault = 14,
+
+    /// Invalid argument
+    Invalid = 22,
+
+    /// Unknown system call number
+    NoSys = 38,
+
+    /// Missing hardware support
+    NotSup = 95,
+}
+impl From<crate::Error> for Errno {
+    fn from(err: crate::Error) -> Self {
+        use crate::{
+            elf, fs, log, mem, task,
+            x86_64::{self, pmu},
+        };
+
+        match err {
+            crate::Error::ELF(elf::Error::InvalidPath) => Errno::NoEnt,
+            crate::Error::ELF(_) => Errno::NoExec,
+            crate::Error::FS(fs::Error::InvalidName) => Errno::Invalid,
+            crate::Error::Log(log::Error::InvalidCount) => Errno::Again,
+            crate::Error::Log(log::Error::InvalidTag) => Errno::Invalid,
+            crate::Error::Mem(mem::Error::OutOfMemory) => Errno::NoMem,
+            crate::Error::Task(task::Error::InvalidCount) => Errno::Again,
+            crate::Error::Task(task::Error::InvalidID(_)) => Errno::Child,
+            crate::Error::X86_64(x86_64::Error::PMU(err)) => match err {
+                pmu::Error::Unavailable | pmu::Error::Unsupported(_) => Errno::NotSup,
+                pmu::Error::OutOfCounters => Errno::Again,
+                pmu::Error::InvalidPeriod(_) => Errno::Invalid,
+            },
+            _ => Errno::IO,
+        }
+    }
+}
+impl From<crate::mem::Error> for Errno {
+    fn from(err: crate::mem::Error) -> Self {
+        crate::Error::from(err).into()
+    }
+}
+impl From<crate::x86_64::pmu::Error> for Errno {
+    fn from(err: crate::x86_64::pmu::Error) -> Self {
+        crate::Error::from(err).into()
+    }
+}
@@ -0,0 +1,336 @@
+//! System Call
+//!
+//! The number goes in RAX and the arguments in RDI, RSI, RDX, R10, R8 and R9.
+//! RAX returns a non-negative value, or a negated `Errno`.
+
+use core::slice::from_raw_parts_mut;
+
+use crate::{
+    acpi, elf, fs,
+    io::{serial, text::Output},
+    log,
+    mem::{
+        PAGE_SIZE,
+        paging::{self, AddressSpace},
+    },
+    task, time,
+    x86_64::pmu,
+};
+
+mod errno;
+
+pub use errno::Errno;
+
+/// `exit(code) -> !`
+pub const EXIT: u64 = 0;
+
+/// `write(fd, buf, len) -> written`
+pub const WRITE: u64 = 1;
+
+/// `read(fd, buf, len) -> read`
+pub const READ: u64 = 2;
+
+/// `open(path, path_len) -> fd`
+pub const OPEN: u64 = 3;
+
+/// `close(fd) -> 0`
+pub const CLOSE: u64 = 4;
+
+/// `mmap(len, prot) -> addr`
+pub const MMAP: u64 = 5;
+
+/// `munmap(addr, len) -> 0`
+pub const MUNMAP: u64 = 6;
+
+/// `clock_gettime(clock, timespec) -> 0`
+pub const CLOCK_GETTIME: u64 = 7;
+
+/// `sleep(ms) -> 0`
+pub const SLEEP: u64 = 8;
+
+/// `spawn(path, path_len) -> id`
+pub const SPAWN: u64 = 9;
+
+/// `wait(id) -> exit code`
+pub const WAIT: u64 = 10;
+
+/// `getpid() -> id`
+pub const GETPID: u64 = 11;
+
+/// `acpi_dump() -> 0`, every ACPI table to the console
+pub const ACPI_DUMP: u64 = 12;
+
+/// `log_level(tag, tag_len, level) -> 0`
+pub const LOG_LEVEL: u64 = 13;
+
+/// `dmesg() -> 0`, the buffered log records to the console
+pub const DMESG: u64 = 14;
+
+/// `perf_count(events) -> 0`, with bit N of `events` for PMU event N
+pub const PERF_COUNT: u64 = 15;
+
+/// `perf_sample(event, period) -> 0`
+pub const PERF_SAMPLE: u64 = 16;
+
+/// `perf_read(event) -> count`
+pub const PERF_READ: u64 = 17;
+
+/// `perf_stop() -> 0`
+pub const PERF_STOP: u64 = 18;
+
+/// `perf_dump() -> 0`, the hottest sampled RIPs to the log
+pub const PERF_DUMP: u64 = 19;
+
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+const STDERR: usize = 2;
+
+/// How often a blocked stdin read checks for a finished serial line
+const STDIN_POLL_MS: u64 = 10;
+
+/// First descriptor of the per-task file table
+const FIRST_FILE: usize = 3;
+
+const PROT_WRITE: u64 = 1 << 1;
+const PROT_EXEC: u64 = 1 << 2;
+
+const CLOCK_MONOTONIC: u64 = 1;
+
+/// Anonymous mappings grow up from here, well above any loaded image
+const MMAP_START: usize = paging::USER_START + 0x1000_0000_0000;
+const MMAP_END: usize = paging::USER_START + 0x2000_0000_0000;
+
+#[repr(C)]
+struct Timespec {
+    sec: i64,
+    nsec: i64,
+}
+
+pub fn dispatch(number: u64, args: [u64; 6]) -> i64 {
+    let result = match number {
+        EXIT => task::exit(args[0] as usize),
+        WRITE => write(args[0] as usize, args[1] as usize, args[2] as usize),
+        READ => read(args[0] as usize, args[1] as usize, args[2] as usize),
+        OPEN => open(args[0] as usize, args[1] as usize),
+        CLOSE => close(args[0] as usize),
+        MMAP => mmap(args[0] as usize, args[1]),
+        MUNMAP => munmap(args[0] as usize, args[1] as usize),
+        CLOCK_GETTIME => clock_gettime(args[0], args[1] as usize),
+        SLEEP => sleep(args[0]),
+        SPAWN => spawn(args[0] as usize, args[1] as usize),
+        WAIT => task::wait(args[0] as usize).map_err(Errno::from),
+        GETPID => Ok(task::id()),
+        ACPI_DUMP => {
+            acpi::table::dump();
+            Ok(0)
+        }
+        LOG_LEVEL => log_level(args[0] as usize, args[1] as usize, args[2]),
+        DMESG => {
+            log::replay(log::sink::console);
+            Ok(0)
+        }
+        PERF_COUNT => perf_count(args[0]),
+        PERF_SAMPLE => perf_sample(args[0], args[1]),
+        PERF_READ => perf_read(args[0]),
+        PERF_STOP => {
+            pmu::stop();
+            Ok(0)
+        }
+        PERF_DUMP => {
+            pmu::dump();
+            Ok(0)
+        }
+        _ => Err(Errno::NoSys),
+    };
+    match result {
+        Ok(value) => value as i64,
+        Err(errno) => -(errno as i64),
+    }
+}
+
+/// Checks that `len` bytes from `addr` are mapped for ring 3 in the current task
+fn user_buffer(addr: usize, len: usize, writable: bool) -> Result<&'static mut [u8], Errno> {
+    let end = addr.checked_add(len).ok_or(Errno::Fault)?;
+    if addr < paging::USER_START || end > paging::USER_END {
+        return Err(Errno::Fault);
+    }
+    let accessible = task::with_current(|task| {
+        let Some(address_space) = &task.address_space else {
+            return false;
+        };
+        (addr & !(PAGE_SIZE - 1)..end)
+            .step_by(PAGE_SIZE)
+            .all(|page| {
+                address_space.flags(page).is_some_and(|flags| {
+                    flags & paging::USER != 0 && (!writable || flags & paging::WRITABLE != 0)
+                })
+            })
+    });
+    if !accessible {
+        return Err(Errno::Fault);
+    }
+    Ok(unsafe { from_raw_parts_mut(addr as *mut u8, len) })
+}
+
+fn user_str(addr: usize, len: usize) -> Result<&'static str, Errno> {
+    core::str::from_utf8(user_buffer(addr, len, false)?).map_err(|_| Errno::Invalid)
+}
+
+fn write(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let buf = user_buffer(addr, len, false)?;
+    match fd {
+        STDOUT | STDERR => {
+            buf.out();
+            Ok(len)
+        }
+        _ => Err(Errno::BadFd),
+    }
+}
+
+fn read(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let buf = user_buffer(addr, len, true)?;
+    if fd == STDIN {
+        // EOF without a UART, and an empty read must not take the pending line
+        if !serial::is_present() || len == 0 {
+            return Ok(0);
+        }
+        loop {
+            if let Some(n) = serial::read_line(buf) {
+                return Ok(n);
+            }
+            task::sleep(STDIN_POLL_MS);
+        }
+    }
+    let index = fd.checked_sub(FIRST_FILE).ok_or(Errno::BadFd)?;
+    let (file, offset) =
+        task::with_current(|task| task.files.get(index).copied().flatten()).ok_or(Errno::BadFd)?;
+    let n = file.read(offset, buf)?;
+    task::with_current(|task| {
+        if let Some((_, offset)) = &mut task.files[index] {
+            *offset += n;
+        }
+    });
+    Ok(n)
+}
+
+fn open(addr: usize, len: usize) -> Result<usize, Errno> {
+    let file = fs::open(user_str(addr, len)?)?.ok_or(Errno::NoEnt)?;
+    task::with_current(|task| {
+        let index = task.files.iter().position(|slot| slot.is_none())?;
+        task.files[index] = Some((file, 0));
+        Some(FIRST_FILE + index)
+    })
+    .ok_or(Errno::Again)
+}
+
+fn close(fd: usize) -> Result<usize, Errno> {
+    let index = fd.checked_sub(FIRST_FILE).ok_or(Errno::BadFd)?;
+    task::with_current(|task| task.files.get_mut(index)?.take())
+        .map(|_| 0)
+        .ok_or(Errno::BadFd)
+}
+
+fn mmap(len: usize, prot: u64) -> Result<usize, Errno> {
+    if len == 0 {
+        return Err(Errno::Invalid);
+    }
+    let size = len
+        .div_ceil(PAGE_SIZE)
+        .checked_mul(PAGE_SIZE)
+        .ok_or(Errno::Invalid)?;
+    let mut flags = paging::USER;
+    if prot & PROT_WRITE != 0 {
+        flags |= paging::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        flags |= paging::EXECUTE_DISABLE;
+    }
+    task::with_current(|task| {
+        let address_space: &mut AddressSpace = task.address_space.as_mut().ok_or(Errno::Fault)?;
+        let addr = task.mmap_next.max(MMAP_START);
+        let end = addr.checked_add(size).ok_or(Errno::Invalid)?;
+        if end > MMAP_END {
+            return Err(Errno::NoMem);
+        }
+        address_space.allocate(addr, size, flags)?;
+        task.mmap_next = end;
+        Ok(addr)
+    })
+}
+
+fn munmap(addr: usize, len: usize) -> Result<usize, Errno> {
+    let end = addr.checked_add(len).ok_or(Errno::Invalid)?;
+    if !addr.is_multiple_of(PAGE_SIZE) || addr < MMAP_START || end > MMAP_END {
+        return Err(Errno::Invalid);
+    }
+    task::with_current(|task| {
+        task.address_space
+            .as_mut()
+            .ok_or(Errno::Fault)?
+            .unmap(addr, end - addr)?;
+        Ok(0)
+    })
+}
+
+/// Only the monotonic clock exists, counted from boot
+fn clock_gettime(clock: u64, addr: usize) -> Result<usize, Errno> {
+    if clock != CLOCK_MONOTONIC {
+        return Err(Errno::Invalid);
+    }
+    let buf = user_buffer(addr, size_of::<Timespec>(), true)?;
+    let ns = time::uptime_ns();
+    let timespec = Timespec {
+        sec: (ns / 1_000_000_000) as i64,
+        nsec: (ns % 1_000_000_000) as i64,
+    };
+    unsafe { (buf.as_mut_ptr() as *mut Timespec).write_unaligned(timespec) };
+    Ok(0)
+}
+
+/// Rejects a duration whose deadline overflows the tick count
+fn sleep(ms: u64) -> Result<usize, Errno> {
+    task::sleep_until(time::deadline(ms).ok_or(Errno::Invalid)?);
+    Ok(0)
+}
+
+/// Sets the level of every tag without its own when `tag` is empty, otherwise of `tag` alone,
+/// where a `level` past trace restores the global one
+fn log_level(addr: usize, len: usize, level: u64) -> Result<usize, Errno> {
+    let tag = if len == 0 { "" } else { user_str(addr, len)? };
+    let level = log::Level::new(level);
+    if tag.is_empty() {
+        log::set_level(level.ok_or(Errno::Invalid)?);
+    } else {
+        log::set_tag_level(tag, level).map_err(crate::Error::from)?;
+    }
+    Ok(0)
+}
+
+fn perf_event(number: u64) -> Result<pmu::Event, Errno> {
+    pmu::Event::new(number).ok_or(Errno::Invalid)
+}
+
+fn perf_count(events: u64) -> Result<usize, Errno> {
+    let mut list = [pmu::Event::Cycles; u64::BITS as usize];
+    let mut len = 0;
+    for number in (0..u64::BITS as u64).filter(|&number| events & (1 << number) != 0) {
+        list[len] = perf_event(number)?;
+        len += 1;
+    }
+    pmu::start(&list[..len])?;
+    Ok(0)
+}
+
+fn perf_sample(event: u64, period: u64) -> Result<usize, Errno> {
+    pmu::start_sampling(perf_event(event)?, period)?;
+    Ok(0)
+}
+
+fn perf_read(event: u64) -> Result<usize, Errno> {
+    Ok(pmu::read(perf_event(event)?).ok_or(Errno::Invalid)? as usize)
+}
+
+fn spawn(addr: usize, len: usize) -> Result<usize, Errno> {
+    let path = user_str(addr, len)?;
+    Ok(elf::exec(path, &[path], &[])?)
+}
@@ -0,0 +1,97 @@
+//! Context
+
+use core::arch::naked_asm;
+
+/// Callee-saved registers in the order `switch` pops them
+#[repr(C)]
+struct Frame {
+    r15: u64,
+    r14: u64,
+    r13: u64,
+    r12: u64,
+    rbp: u64,
+    rbx: u64,
+
+    rip: u64,
+}
+
+/// Saves the callee-saved state of the current task to `*old` and resumes `new`
+///
+/// Must be called with interrupts disabled.
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch(old: *mut usize, new: usize) {
+    naked_asm!(
+        "push rbx",
+        "push rbp",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+        "mov [rdi], rsp",
+        "mov rsp, rsi",
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rbp",
+        "pop rbx",
+        "ret",
+    );
+}
+
+/// First code run by a new task, with `entry` in R12 and its argument in R13
+#[unsafe(naked)]
+unsafe extern "C" fn trampoline() {
+    naked_asm!(
+        "sti",
+        "mov rdi, r12",
+        "mov rsi, r13",
+        "call {}",
+        "ud2",
+        sym start,
+    );
+}
+
+extern "C" fn start(entry: usize, arg: usize) -> ! {
+    let entry: fn(usize) -> usize = unsafe { core::mem::transmute(entry) };
+    super::exit(entry(arg))
+}
+
+/// First code run by a user task, with its user RIP in R12 and user RSP in R13
+#[unsafe(naked)]
+unsafe extern "C" fn user_trampoline() {
+    naked_asm!(
+        "mov rdi, r12",
+        "mov rsi, r13",
+        "jmp {}",
+        sym crate::x86_64::syscall::enter,
+    );
+}
+
+/// Builds a frame on the stack ending at `top` that resumes at `rip` and returns its RSP
+fn build(top: usize, rip: usize, r12: usize, r13: usize) -> usize {
+    // 16-byte aligned once `rip` is entered
+    let rsp = (top & !0xF) - 16 - size_of::<Frame>();
+    unsafe {
+        (rsp as *mut Frame).write(Frame {
+            r15: 0,
+            r14: 0,
+            r13: r13 as u64,
+            r12: r12 as u64,
+            rbp: 0,
+            rbx: 0,
+            rip: rip as u64,
+        })
+    };
+    rsp
+}
+
+/// Builds the initial frame of a kernel task on the stack ending at `top` and returns its RSP
+pub fn init(top: usize, entry: fn(usize) -> usize, arg: usize) -> usize {
+    build(top, trampoline as *const () as usize, entry as usize, arg)
+}
+
+/// Builds the initial frame of a task that drops to ring 3 at `rip` with `rsp`
+pub fn init_user(top: usize, rip: usize, rsp: usize) -> usize {
+    build(top, user_trampoline as *const () as usize, rip, rsp)
+}
@@ -0,0 +1,24 @@
+//! Error
+
+pub enum Error {
+    InvalidCount,
+    InvalidID(usize),
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::Task(err)
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "Task ".out();
+        match self {
+            Error::InvalidCount => "Count Overflow",
+            Error::InvalidID(id) => {
+                id.out();
+                " ID"
+            }
+        }
+        .out();
+    }
+}
@@ -0,0 +1,296 @@
+//! Task
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    fs::File,
+    mem::{paging::AddressSpace, physical},
+    time,
+    x86_64::{fpu, gdt},
+};
+
+mod context;
+mod error;
+pub mod scheduler;
+
+pub use error::Error;
+
+const MAX_TASK_COUNT: usize = 64;
+
+const STACK_SIZE: usize = 0x10000;
+
+pub const MAX_FILE_COUNT: usize = 16;
+
+/// Task whose extended state is in the registers
+static FPU_OWNER: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Ready,
+    Running,
+
+    /// Until the given tick
+    Sleeping(u64),
+
+    /// Until the given task exits
+    Joining(usize),
+
+    /// With the given exit code, until joined
+    Exited(usize),
+}
+
+pub struct Task {
+    state: State,
+
+    /// Task that spawned this one, the only one a user program lets wait for it
+    /// - None: Task 0, or its parent was reclaimed
+    parent: Option<usize>,
+
+    /// Set by `wake` while not sleeping, so that the next sleep returns at once
+    woken: bool,
+
+    /// Saved stack pointer while not running
+    rsp: usize,
+
+    /// - 0: Boot stack
+    stack: usize,
+
+    /// - None: Kernel only
+    pub address_space: Option<AddressSpace>,
+
+    /// Open files with their offsets
+    pub files: [Option<(File, usize)>; MAX_FILE_COUNT],
+
+    /// Next free address of the anonymous mapping region
+    pub mmap_next: usize,
+
+    /// Extended state save area
+    /// - 0: Never used the FPU
+    fpu: usize,
+}
+impl Task {
+    fn new(
+        state: State,
+        parent: Option<usize>,
+        stack: usize,
+        address_space: Option<AddressSpace>,
+    ) -> Self {
+        Self {
+            state,
+            parent,
+            woken: false,
+            rsp: 0,
+            stack,
+            address_space,
+            files: [None; MAX_FILE_COUNT],
+            mmap_next: 0,
+            fpu: 0,
+        }
+    }
+
+    /// Installs the kernel stack and address space of the task before it resumes
+    fn activate(&self) {
+        if self.stack != 0 {
+            gdt::tss::set_rsp0((self.stack + STACK_SIZE) as u64);
+        }
+        match &self.address_space {
+            Some(address_space) => address_space.activate(),
+            None => AddressSpace::activate_kernel(),
+        }
+        fpu::set_task_switched();
+    }
+}
+
+/// Adopts the boot thread as task 0
+pub fn init() {
+    scheduler::lock().tasks()[0] = Some(Task::new(State::Running, None, 0, None));
+}
+
+pub fn spawn(entry: fn(usize) -> usize, arg: usize) -> Result<usize, crate::Error> {
+    add(None, |top| context::init(top, entry, arg)).map_err(|(err, _)| err)
+}
+
+/// Starts a task running at `rip` with `rsp` in ring 3 of `address_space`, handing it back on
+/// failure for the caller to delete
+pub fn spawn_user(
+    address_space: AddressSpace,
+    rip: usize,
+    rsp: usize,
+) -> Result<usize, (crate::Error, AddressSpace)> {
+    add(Some(address_space), |top| context::init_user(top, rip, rsp))
+        .map_err(|(err, address_space)| (err, address_space.expect("Address space taken")))
+}
+
+/// Hands `address_space` back along with the error if the task cannot be added
+fn add(
+    address_space: Option<AddressSpace>,
+    init: impl FnOnce(usize) -> usize,
+) -> Result<usize, (crate::Error, Option<AddressSpace>)> {
+    let mut scheduler = scheduler::lock();
+    let Some(id) = (1..MAX_TASK_COUNT).find(|&id| scheduler.tasks()[id].is_none()) else {
+        return Err((Error::InvalidCount.into(), address_space));
+    };
+    let stack = match physical::allocate(STACK_SIZE) {
+        Ok(stack) => stack,
+        Err(err) => return Err((err.into(), address_space)),
+    };
+    let parent = scheduler.current_id();
+    let mut task = Task::new(State::Ready, Some(parent), stack, address_space);
+    task.rsp = init(stack + STACK_SIZE);
+    scheduler.tasks()[id] = Some(task);
+    scheduler.ready(id);
+    Ok(id)
+}
+
+/// Runs `f` on the current task with the scheduler locked
+pub fn with_current<R>(f: impl FnOnce(&mut Task) -> R) -> R {
+    f(scheduler::lock().current())
+}
+
+pub fn id() -> usize {
+    scheduler::lock().current_id()
+}
+
+pub fn yield_now() {
+    scheduler::schedule();
+}
+
+/// Sleeps forever if `ms` from now overflows
+pub fn sleep(ms: u64) {
+    sleep_until(time::deadline(ms).unwrap_or(u64::MAX));
+}
+
+/// Sleeps until the tick count reaches `until` or `wake` is called
+pub fn sleep_until(until: u64) {
+    {
+        let mut scheduler = scheduler::lock();
+        let task = scheduler.current();
+        if core::mem::take(&mut task.woken) {
+            return;
+        }
+        task.state = State::Sleeping(until);
+    }
+    scheduler::schedule();
+}
+
+/// Cuts the sleep of task `id` short, or the next one if it is awake
+///
+/// Safe from interrupt handlers, which use it to hand work to a task.
+pub fn wake(id: usize) {
+    let mut scheduler = scheduler::lock();
+    let Some(task) = scheduler.tasks().get_mut(id).and_then(Option::as_mut) else {
+        return;
+    };
+    if let State::Sleeping(_) = task.state {
+        scheduler.ready(id);
+    } else {
+        task.woken = true;
+    }
+}
+
+/// Blocks until the task exits, reclaims it and returns its exit code
+pub fn join(id: usize) -> Result<usize, crate::Error> {
+    loop {
+        {
+            let mut scheduler = scheduler::lock();
+            if id == scheduler.current_id() || id >= MAX_TASK_COUNT {
+                return Err(Error::InvalidID(id).into());
+            }
+            let Some(task) = scheduler.tasks()[id].as_ref() else {
+                return Err(Error::InvalidID(id).into());
+            };
+            if let State::Exited(code) = task.state {
+                let task = scheduler.tasks()[id].take().unwrap();
+                for child in scheduler.tasks().iter_mut().flatten() {
+                    if child.parent == Some(id) {
+                        child.parent = None;
+                    }
+                }
+                let _ = FPU_OWNER.compare_exchange(
+                    id,
+                    usize::MAX,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+                drop(scheduler);
+                physical::deallocate(task.stack)?;
+                if task.fpu != 0 {
+                    fpu::deallocate(task.fpu)?;
+                }
+                if let Some(address_space) = task.address_space {
+                    address_space.delete()?;
+                }
+                return Ok(code);
+            }
+            scheduler.current().state = State::Joining(id);
+        }
+        scheduler::schedule();
+    }
+}
+
+/// `join` for user programs, which may only reclaim the tasks they spawned
+pub fn wait(id: usize) -> Result<usize, crate::Error> {
+    {
+        let mut scheduler = scheduler::lock();
+        let current = scheduler.current_id();
+        let is_child = scheduler
+            .tasks()
+            .get(id)
+            .and_then(Option::as_ref)
+            .is_some_and(|task| task.parent == Some(current));
+        if !is_child {
+            return Err(Error::InvalidID(id).into());
+        }
+    }
+    join(id)
+}
+
+/// Ends the current task, waking any task joining it
+pub fn exit(code: usize) -> ! {
+    {
+        let mut scheduler = scheduler::lock();
+        let current = scheduler.current_id();
+        scheduler.current().state = State::Exited(code);
+        for id in 0..MAX_TASK_COUNT {
+            if let Some(Task {
+                state: State::Joining(target),
+                ..
+            }) = scheduler.tasks()[id]
+                && target == current
+            {
+                scheduler.ready(id);
+            }
+        }
+    }
+    scheduler::schedule();
+    unreachable!()
+}
+
+/// Handles #NM by moving the FPU from its previous owner to the current task
+pub fn device_not_available() -> Result<(), crate::Error> {
+    fpu::clear_task_switched();
+    let mut scheduler = scheduler::lock();
+    let current = scheduler.current_id();
+    let owner = FPU_OWNER.load(Ordering::Relaxed);
+    if owner == current {
+        return Ok(());
+    }
+    if let Some(Some(task)) = scheduler.tasks().get(owner) {
+        fpu::save(task.fpu);
+    }
+    let task = scheduler.current();
+    if task.fpu == 0 {
+        task.fpu = fpu::allocate()?;
+    }
+    fpu::restore(task.fpu);
+    FPU_OWNER.store(current, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Body of the boot task once initialization is done
+pub fn idle() -> ! {
+    loop {
+        crate::x86_64::wait_for_interrupt();
+        yield_now();
+    }
+}
@@ -0,0 +1,167 @@
+//! Scheduler
+
+use crate::{
+    sync::IrqSpinlock,
+    time,
+    x86_64::{self, rflags},
+};
+
+use super::{MAX_TASK_COUNT, State, Task, context};
+
+mod round_robin;
+
+use round_robin::RoundRobin;
+
+static SCHEDULER: IrqSpinlock<Scheduler<RoundRobin>> =
+    IrqSpinlock::new(Scheduler::new(RoundRobin::new()));
+
+/// Decides which ready task runs next
+pub trait Policy {
+    /// Makes a ready task eligible to be picked
+    fn enqueue(&mut self, id: usize);
+
+    /// Picks the next task to run
+    fn dequeue(&mut self) -> Option<usize>;
+
+    /// Called on every timer tick with the running task and returns whether to preempt it
+    fn tick(&mut self, current: usize) -> bool;
+}
+
+enum Next {
+    /// Keep running the current task
+    Stay,
+
+    /// Save the current context into the first and resume the second
+    Switch(*mut usize, usize),
+
+    /// Nothing is ready and the current task is blocked
+    Wait,
+}
+
+pub struct Scheduler<P: Policy> {
+    tasks: [Option<Task>; MAX_TASK_COUNT],
+
+    current: usize,
+
+    policy: P,
+}
+impl<P: Policy> Scheduler<P> {
+    const fn new(policy: P) -> Self {
+        Self {
+            tasks: [const { None }; MAX_TASK_COUNT],
+            current: 0,
+            policy,
+        }
+    }
+
+    pub fn tasks(&mut self) -> &mut [Option<Task>; MAX_TASK_COUNT] {
+        &mut self.tasks
+    }
+
+    pub fn current(&mut self) -> &mut Task {
+        self.tasks[self.current]
+            .as_mut()
+            .expect("Scheduler without current task")
+    }
+
+    pub fn current_id(&self) -> usize {
+        self.current
+    }
+
+    /// Marks a task ready and hands it to the policy
+    pub fn ready(&mut self, id: usize) {
+        if let Some(task) = self.tasks[id].as_mut() {
+            task.state = State::Ready;
+            self.policy.enqueue(id);
+        }
+    }
+
+    fn wake(&mut self, now: u64) {
+        for id in 0..MAX_TASK_COUNT {
+            if let Some(Task {
+                state: State::Sleeping(until),
+                ..
+            }) = self.tasks[id]
+                && until <= now
+            {
+                self.ready(id);
+            }
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        match self.tasks[self.current] {
+            Some(Task {
+                state: State::Running,
+                ..
+            }) => self.policy.tick(self.current),
+            _ => false,
+        }
+    }
+
+    fn next(&mut self) -> Next {
+        let current = self.current;
+        let Some(task) = self.tasks[current].as_mut() else {
+            return Next::Stay;
+        };
+        if task.state == State::Running {
+            self.ready(current);
+        }
+
+        let Some(next) = self.policy.dequeue() else {
+            return Next::Wait;
+        };
+        let Some(task) = self.tasks[next].as_mut() else {
+            return Next::Wait;
+        };
+        task.state = State::Running;
+        if next == current {
+            return Next::Stay;
+        }
+        task.activate();
+        let rsp = task.rsp;
+
+        self.current = next;
+        let old = self.tasks[current].as_mut().unwrap();
+        Next::Switch(&mut old.rsp, rsp)
+    }
+}
+
+pub fn lock() -> crate::sync::IrqSpinlockGuard<'static, Scheduler<RoundRobin>> {
+    SCHEDULER.lock()
+}
+
+/// Gives up the CPU, waiting for an interrupt if no task is runnable
+pub fn schedule() {
+    let enabled = rflags::interrupts_enabled();
+    rflags::disable_interrupts();
+    loop {
+        let next = SCHEDULER.lock().next();
+        match next {
+            Next::Stay => break,
+            Next::Switch(old, new) => {
+                unsafe { context::switch(old, new) };
+                break;
+            }
+            Next::Wait => {
+                x86_64::wait_for_interrupt();
+                rflags::disable_interrupts();
+            }
+        }
+    }
+    if enabled {
+        rflags::enable_interrupts();
+    }
+}
+
+/// Timer interrupt hook
+pub fn tick() {
+    let preempt = {
+        let mut scheduler = SCHEDULER.lock();
+        scheduler.wake(time::ticks());
+        scheduler.tick()
+    };
+    if preempt {
+        schedule();
+    }
+}
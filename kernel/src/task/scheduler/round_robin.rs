@@ -0,0 +1,46 @@
+//! Round Robin
+
+use super::{super::MAX_TASK_COUNT, Policy};
+
+/// Ticks a task may run before being preempted
+const TIME_SLICE: u64 = 5;
+
+pub struct RoundRobin {
+    queue: [usize; MAX_TASK_COUNT],
+    head: usize,
+    len: usize,
+
+    remaining: u64,
+}
+impl RoundRobin {
+    pub const fn new() -> Self {
+        Self {
+            queue: [0; MAX_TASK_COUNT],
+            head: 0,
+            len: 0,
+            remaining: TIME_SLICE,
+        }
+    }
+}
+impl Policy for RoundRobin {
+    fn enqueue(&mut self, id: usize) {
+        self.queue[(self.head + self.len) % MAX_TASK_COUNT] = id;
+        self.len += 1;
+    }
+
+    fn dequeue(&mut self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        let id = self.queue[self.head];
+        self.head = (self.head + 1) % MAX_TASK_COUNT;
+        self.len -= 1;
+        self.remaining = TIME_SLICE;
+        Some(id)
+    }
+
+    fn tick(&mut self, _current: usize) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining == 0 && self.len != 0
+    }
+}
@@ -0,0 +1,38 @@
+//! Time
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::acpi::pm_timer;
+
+/// Timer interrupts per second
+pub const HZ: u64 = 100;
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+pub fn tick() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Timer interrupts since boot
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+pub fn uptime_ms() -> u64 {
+    ticks() * 1000 / HZ
+}
+
+/// Down to the ACPI PM timer where there is one, the timer interrupt otherwise
+pub fn uptime_ns() -> u64 {
+    pm_timer::uptime_ns().unwrap_or_else(|| uptime_ms() * 1_000_000)
+}
+
+/// Rounded up so that any non-zero duration waits at least one tick, `None` on overflow
+pub fn ms_to_ticks(ms: u64) -> Option<u64> {
+    Some(ms.checked_mul(HZ)?.div_ceil(1000))
+}
+
+/// Tick count `ms` from now, `None` on overflow
+pub fn deadline(ms: u64) -> Option<u64> {
+    ticks().checked_add(ms_to_ticks(ms)?)
+}
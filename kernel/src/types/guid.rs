@@ -1,9 +1,9 @@
 //! Globally Unique Identifier
 
-use crate::io::text::Output;
+use crate::{io::text::Output, random};
 
 #[repr(C, packed)]
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct GUID {
     time_low: u32,
     time_mid: u16,
@@ -42,8 +42,26 @@ impl GUID {
     pub const EFI_SYSTEM_PARTITION: Self = Self::from_str("C12A7328-F81F-11D2-BA4B-00A0C93EC93B");
     pub const WINDOWS_BASIC_DATA_PARTITION: Self =
         Self::from_str("EBD0A0A2-B9E5-4433-87C0-68B6B72699C7");
+    /// Vendor of the UEFI variables the specification defines, such as `BootOrder`
+    pub const EFI_GLOBAL_VARIABLE: Self = Self::from_str("8BE4DF61-93CA-11D2-AA0D-00E098032B8C");
 
-    const fn from_str(guid: &'static str) -> Self {
+    /// Random GUID, version 4 of RFC 4122
+    pub fn new_v4() -> Self {
+        let mut bytes = [0u8; 16];
+        random::fill(&mut bytes);
+        Self {
+            time_low: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            time_mid: u16::from_le_bytes([bytes[4], bytes[5]]),
+            time_high_and_version: (u16::from_le_bytes([bytes[6], bytes[7]]) & 0x0FFF) | 0x4000,
+            clock_seq_high_and_reserved: (bytes[8] & 0x3F) | 0x80,
+            clock_seq_low: bytes[9],
+            node: [
+                bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15],
+            ],
+        }
+    }
+
+    pub const fn from_str(guid: &'static str) -> Self {
         const fn chars_to_byte(low: u8, high: u8) -> u8 {
             const fn char_to_nibble(c: u8) -> u8 {
                 match c {
@@ -0,0 +1,78 @@
+//! Error
+
+pub enum Error {
+    AccessDenied,
+    /// Bytes the variable holds
+    BufferTooSmall(usize),
+    DeviceError,
+    InvalidName,
+    InvalidParameter,
+    InvalidSignature(&'static str),
+    NotFound,
+    OutOfResources,
+    SecurityViolation,
+    Status(usize),
+    Unsupported(&'static str),
+    WriteProtected,
+}
+impl Error {
+    /// EFI_STATUS with the high bit set
+    /// - 2: EFI_INVALID_PARAMETER
+    /// - 3: EFI_UNSUPPORTED
+    /// - 7: EFI_DEVICE_ERROR
+    /// - 8: EFI_WRITE_PROTECTED
+    /// - 9: EFI_OUT_OF_RESOURCES
+    /// - 14: EFI_NOT_FOUND
+    /// - 15: EFI_ACCESS_DENIED
+    /// - 26: EFI_SECURITY_VIOLATION
+    pub fn from_status(status: usize) -> Self {
+        match status & !(1 << 63) {
+            2 => Error::InvalidParameter,
+            3 => Error::Unsupported("Runtime Service"),
+            7 => Error::DeviceError,
+            8 => Error::WriteProtected,
+            9 => Error::OutOfResources,
+            14 => Error::NotFound,
+            15 => Error::AccessDenied,
+            26 => Error::SecurityViolation,
+            _ => Error::Status(status),
+        }
+    }
+}
+impl From<Error> for crate::Error {
+    fn from(err: Error) -> Self {
+        crate::Error::UEFI(err)
+    }
+}
+impl crate::Output for Error {
+    fn out(&self) {
+        "UEFI ".out();
+        match self {
+            Error::AccessDenied => "Access Denied",
+            Error::BufferTooSmall(size) => {
+                size.out();
+                " Bytes Needed"
+            }
+            Error::DeviceError => "Device Error",
+            Error::InvalidName => "Variable Name",
+            Error::InvalidParameter => "Parameter",
+            Error::InvalidSignature(table) => {
+                table.out();
+                " Signature"
+            }
+            Error::NotFound => "Not Found",
+            Error::OutOfResources => "Out of Resources",
+            Error::SecurityViolation => "Security Violation",
+            Error::Status(status) => {
+                (*status as u64).out();
+                " Status"
+            }
+            Error::Unsupported(feature) => {
+                feature.out();
+                " Unsupported"
+            }
+            Error::WriteProtected => "Write Protected",
+        }
+        .out();
+    }
+}
@@ -0,0 +1,235 @@
+//! Unified Extensible Firmware Interface
+//!
+//! Runtime services, the part of the firmware that outlives `ExitBootServices`. Their regions
+//! keep the identity mapping the kernel half is made of, so `SetVirtualAddressMap` is handed the
+//! physical addresses back and firmware pointers stay valid in every address space.
+//! Firmware is not reentrant, so calls go one at a time with interrupts off.
+
+use core::{convert::Infallible, ptr::read_volatile, slice::from_raw_parts};
+
+use crate::{
+    info,
+    io::text::Output,
+    mem::{
+        self, Memory, PAGE_SIZE,
+        paging::{self, CACHE_DISABLE, EXECUTE_DISABLE, WRITABLE},
+        physical::{self, Descriptor},
+    },
+    sync::IrqSpinlock,
+    types::guid::GUID,
+    warn,
+};
+
+mod error;
+pub mod time;
+pub mod variable;
+
+pub use error::Error;
+use time::Time;
+
+/// "IBI SYST"
+const SYSTEM_TABLE_SIGNATURE: u64 = 0x5453_5953_2049_4249;
+
+/// "RUNTSERV"
+const RUNTIME_SERVICES_SIGNATURE: u64 = 0x5652_4553_544E_5552;
+
+/// Layout of `Descriptor` that `SetVirtualAddressMap` is told about
+const DESCRIPTOR_VERSION: u32 = 1;
+
+/// Memory types
+const RUNTIME_SERVICES_CODE: u32 = 5;
+const MMIO: u32 = 11;
+const MMIO_PORT_SPACE: u32 = 12;
+
+/// Longest firmware vendor string read, in UCS-2 characters
+const MAX_VENDOR_LEN: usize = 64;
+
+static RUNTIME_SERVICES: IrqSpinlock<Option<&'static RuntimeServices>> = IrqSpinlock::new(None);
+
+#[repr(C)]
+struct TableHeader {
+    signature: u64,
+
+    /// Major in bits 16 ..= 31, minor in bits 0 ..= 15, such as 2.70
+    revision: u32,
+
+    header_size: u32,
+    crc32: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+struct SystemTable {
+    header: TableHeader,
+
+    /// Null-terminated UCS-2
+    firmware_vendor: usize,
+    firmware_revision: u32,
+
+    console_in_handle: usize,
+    con_in: usize,
+    console_out_handle: usize,
+    con_out: usize,
+    standard_error_handle: usize,
+    std_err: usize,
+
+    runtime_services: usize,
+
+    /// Null after `ExitBootServices`
+    boot_services: usize,
+
+    number_of_table_entries: usize,
+    configuration_table: usize,
+}
+impl Memory for SystemTable {}
+
+#[repr(C)]
+struct RuntimeServices {
+    header: TableHeader,
+
+    get_time: unsafe extern "efiapi" fn(*mut Time, *mut u8) -> usize,
+    set_time: unsafe extern "efiapi" fn(*const Time) -> usize,
+    get_wakeup_time: usize,
+    set_wakeup_time: usize,
+
+    set_virtual_address_map: unsafe extern "efiapi" fn(usize, usize, u32, *mut Descriptor) -> usize,
+    convert_pointer: usize,
+
+    get_variable:
+        unsafe extern "efiapi" fn(*const u16, *const GUID, *mut u32, *mut usize, *mut u8) -> usize,
+    get_next_variable_name: unsafe extern "efiapi" fn(*mut usize, *mut u16, *mut GUID) -> usize,
+    set_variable:
+        unsafe extern "efiapi" fn(*const u16, *const GUID, u32, usize, *const u8) -> usize,
+
+    get_next_high_monotonic_count: usize,
+    reset_system: unsafe extern "efiapi" fn(u32, usize, usize, *const u8) -> !,
+
+    update_capsule: usize,
+    query_capsule_capabilities: usize,
+    query_variable_info: usize,
+}
+impl Memory for RuntimeServices {}
+
+#[derive(Clone, Copy)]
+pub enum ResetType {
+    Cold = 0,
+    Warm = 1,
+    Shutdown = 2,
+}
+
+/// Hands `f` the runtime services and turns the EFI_STATUS it returns into a result
+///
+/// Warnings, nonzero with the high bit clear, count as success.
+fn call(f: impl FnOnce(&RuntimeServices) -> usize) -> Result<(), Error> {
+    let services = RUNTIME_SERVICES.lock();
+    let services = services.ok_or(Error::Unsupported("Runtime Services"))?;
+    match f(services) {
+        status if status & 1 << 63 != 0 => Err(Error::from_status(status)),
+        _ => Ok(()),
+    }
+}
+
+/// UCS-2 text, such as the firmware vendor or a variable name
+struct Ucs2<'a>(&'a [u16]);
+impl Output for Ucs2<'_> {
+    fn out(&self) {
+        char::decode_utf16(self.0.iter().copied()).for_each(|c| c.unwrap_or('?').out());
+    }
+}
+
+pub fn is_available() -> bool {
+    RUNTIME_SERVICES.lock().is_some()
+}
+
+/// Resets or powers off through firmware, returning only if runtime services are unavailable
+///
+/// Drives are not flushed, which `acpi::power` takes care of.
+pub fn reset_system(type_: ResetType) -> Result<Infallible, Error> {
+    let services = RUNTIME_SERVICES.lock();
+    let services = services.ok_or(Error::Unsupported("Runtime Services"))?;
+    unsafe { (services.reset_system)(type_ as u32, 0, 0, core::ptr::null()) }
+}
+
+/// Maps the runtime regions of the memory map and moves the runtime services of the system table
+/// at `addr` onto them
+///
+/// - `addr`: Zero when the bootloader did not pass one
+pub fn init(
+    addr: usize,
+    entry: usize,
+    descriptor_size: usize,
+    descriptor_count: usize,
+) -> Result<(), crate::Error> {
+    if addr == 0 {
+        info!("UEFI", "No system table, runtime services unavailable");
+        return Ok(());
+    }
+    let system_table = SystemTable::get_ref(addr);
+    if system_table.header.signature != SYSTEM_TABLE_SIGNATURE {
+        return Err(Error::InvalidSignature("System Table").into());
+    }
+    let services = RuntimeServices::get_ref(system_table.runtime_services);
+    if services.header.signature != RUNTIME_SERVICES_SIGNATURE {
+        return Err(Error::InvalidSignature("Runtime Services").into());
+    }
+
+    for descriptor in physical::descriptors(entry, descriptor_size, descriptor_count)
+        .filter(|descriptor| descriptor.is_runtime())
+    {
+        let flags = match descriptor.type_ {
+            RUNTIME_SERVICES_CODE => WRITABLE,
+            MMIO | MMIO_PORT_SPACE => WRITABLE | CACHE_DISABLE | EXECUTE_DISABLE,
+            _ => WRITABLE | EXECUTE_DISABLE,
+        };
+        let size = (descriptor.page_count as usize)
+            .checked_mul(PAGE_SIZE)
+            .ok_or(mem::Error::InvalidAddress)?;
+        paging::identity_map(descriptor.phys_start as usize, size, flags)?;
+        descriptor.virt_start = descriptor.phys_start;
+    }
+    let status = unsafe {
+        (services.set_virtual_address_map)(
+            descriptor_count * descriptor_size,
+            descriptor_size,
+            DESCRIPTOR_VERSION,
+            entry as *mut Descriptor,
+        )
+    };
+    if status != 0 {
+        return Err(Error::from_status(status).into());
+    }
+    *RUNTIME_SERVICES.lock() = Some(services);
+
+    let vendor = unsafe {
+        let vendor = system_table.firmware_vendor as *const u16;
+        let len = (0..MAX_VENDOR_LEN)
+            .position(|i| read_volatile(vendor.add(i)) == 0)
+            .unwrap_or(MAX_VENDOR_LEN);
+        from_raw_parts(vendor, len)
+    };
+    let revision = services.header.revision;
+    info!(
+        "UEFI",
+        Ucs2(vendor),
+        " ",
+        system_table.firmware_revision as u64,
+        ", runtime services ",
+        (revision >> 16) as usize,
+        ".",
+        (revision & 0xFFFF) as usize
+    );
+    match time::get_time() {
+        Ok(time) => info!("UEFI", "Time ", time),
+        Err(err) => warn!("UEFI", err),
+    }
+    let mut boot_current = [0; 2];
+    if variable::get_variable("BootCurrent", &GUID::EFI_GLOBAL_VARIABLE, &mut boot_current).is_ok()
+    {
+        info!(
+            "UEFI",
+            "Booted from Boot option ",
+            u16::from_le_bytes(boot_current) as u64
+        );
+    }
+    Ok(())
+}
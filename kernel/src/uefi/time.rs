@@ -0,0 +1,91 @@
+//! Time
+
+use core::ptr::null_mut;
+
+use crate::io::text::Output;
+
+use super::Error;
+
+/// `time_zone` of a time kept in local time
+pub const UNSPECIFIED_TIMEZONE: i16 = 0x07FF;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Time {
+    /// 1900 ..= 9999
+    pub year: u16,
+
+    /// 1 ..= 12
+    pub month: u8,
+
+    /// 1 ..= 31
+    pub day: u8,
+
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+
+    pad1: u8,
+
+    pub nanosecond: u32,
+
+    /// Minutes from UTC, -1440 ..= 1440, or `UNSPECIFIED_TIMEZONE`
+    pub time_zone: i16,
+
+    /// - Bit 0: EFI_TIME_ADJUST_DAYLIGHT
+    /// - Bit 1: EFI_TIME_IN_DAYLIGHT
+    pub daylight: u8,
+
+    pad2: u8,
+}
+impl Time {
+    /// Local time, which is what the real-time clock of a PC keeps
+    pub const fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            pad1: 0,
+            nanosecond: 0,
+            time_zone: UNSPECIFIED_TIMEZONE,
+            daylight: 0,
+            pad2: 0,
+        }
+    }
+}
+impl Output for Time {
+    /// `2026-10-19 08:05:09`
+    fn out(&self) {
+        let two_digits = |value: u8| {
+            if value < 10 {
+                '0'.out();
+            }
+            (value as usize).out();
+        };
+        (self.year as usize).out();
+        '-'.out();
+        two_digits(self.month);
+        '-'.out();
+        two_digits(self.day);
+        ' '.out();
+        two_digits(self.hour);
+        ':'.out();
+        two_digits(self.minute);
+        ':'.out();
+        two_digits(self.second);
+    }
+}
+
+/// Current time of the real-time clock
+pub fn get_time() -> Result<Time, Error> {
+    let mut time = Time::new(0, 0, 0, 0, 0, 0);
+    super::call(|services| unsafe { (services.get_time)(&mut time, null_mut()) })?;
+    Ok(time)
+}
+
+pub fn set_time(time: &Time) -> Result<(), Error> {
+    super::call(|services| unsafe { (services.set_time)(time) })
+}
@@ -0,0 +1,127 @@
+//! Variable
+//!
+//! Named values firmware keeps for a vendor GUID, in NVRAM when marked non-volatile.
+
+use crate::{io::text::Output, types::guid::GUID};
+
+use super::{Error, Ucs2};
+
+/// Attributes
+pub const NON_VOLATILE: u32 = 1 << 0;
+pub const BOOTSERVICE_ACCESS: u32 = 1 << 1;
+pub const RUNTIME_ACCESS: u32 = 1 << 2;
+
+/// UCS-2 characters of a name, the terminating NUL included
+const MAX_NAME_LEN: usize = 64;
+
+/// EFI_BUFFER_TOO_SMALL
+const BUFFER_TOO_SMALL: usize = 1 << 63 | 5;
+
+/// Null-terminated UCS-2 name of a variable
+#[derive(Clone, Copy)]
+pub struct Name {
+    chars: [u16; MAX_NAME_LEN],
+}
+impl Name {
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let mut chars = [0; MAX_NAME_LEN];
+        for (i, c) in name.encode_utf16().enumerate() {
+            if c == 0 || i + 1 == MAX_NAME_LEN {
+                return Err(Error::InvalidName);
+            }
+            chars[i] = c;
+        }
+        Ok(Self { chars })
+    }
+
+    /// Characters before the NUL
+    pub fn as_slice(&self) -> &[u16] {
+        let len = self.chars.iter().position(|&c| c == 0).unwrap_or(0);
+        &self.chars[..len]
+    }
+}
+impl Output for Name {
+    fn out(&self) {
+        Ucs2(self.as_slice()).out();
+    }
+}
+
+/// Reads variable `name` of `vendor` into `buffer` and returns its size and attributes
+///
+/// When it does not fit, the error carries the size it needs.
+pub fn get_variable(name: &str, vendor: &GUID, buffer: &mut [u8]) -> Result<(usize, u32), Error> {
+    let name = Name::new(name)?;
+    let mut attributes = 0;
+    let mut size = buffer.len();
+    let status = super::call(|services| unsafe {
+        (services.get_variable)(
+            name.chars.as_ptr(),
+            vendor,
+            &mut attributes,
+            &mut size,
+            buffer.as_mut_ptr(),
+        )
+    });
+    match status {
+        Ok(()) => Ok((size, attributes)),
+        Err(Error::Status(BUFFER_TOO_SMALL)) => Err(Error::BufferTooSmall(size)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Creates or replaces variable `name` of `vendor`, or deletes it if `data` is empty
+///
+/// - `attributes`: `NON_VOLATILE` to keep it across resets, with `BOOTSERVICE_ACCESS` and
+///   `RUNTIME_ACCESS` for it to be reachable now
+pub fn set_variable(name: &str, vendor: &GUID, attributes: u32, data: &[u8]) -> Result<(), Error> {
+    let name = Name::new(name)?;
+    super::call(|services| unsafe {
+        (services.set_variable)(
+            name.chars.as_ptr(),
+            vendor,
+            attributes,
+            data.len(),
+            data.as_ptr(),
+        )
+    })
+}
+
+/// Replaces `name` and `vendor` with the variable after them, the first one after an empty name,
+/// and returns whether there was one
+pub fn next_variable_name(name: &mut Name, vendor: &mut GUID) -> Result<bool, Error> {
+    let mut size = size_of_val(&name.chars);
+    let status = super::call(|services| unsafe {
+        (services.get_next_variable_name)(&mut size, name.chars.as_mut_ptr(), vendor)
+    });
+    match status {
+        Ok(()) => Ok(true),
+        Err(Error::NotFound) => Ok(false),
+        Err(Error::Status(BUFFER_TOO_SMALL)) => Err(Error::BufferTooSmall(size)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Name and vendor of every variable, in the order firmware keeps them
+pub fn variables() -> impl Iterator<Item = Result<(Name, GUID), Error>> {
+    let mut name = Name {
+        chars: [0; MAX_NAME_LEN],
+    };
+    let mut vendor = GUID::from_str("00000000-0000-0000-0000-000000000000");
+    let mut done = false;
+    core::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        match next_variable_name(&mut name, &mut vendor) {
+            Ok(true) => Some(Ok((name, vendor))),
+            Ok(false) => {
+                done = true;
+                None
+            }
+            Err(err) => {
+                done = true;
+                Some(Err(err))
+            }
+        }
+    })
+}
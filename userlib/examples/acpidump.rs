@@ -0,0 +1,12 @@
+//! ACPI Dump
+
+#![no_std]
+#![no_main]
+
+use userlib::{Args, acpi_dump};
+
+#[unsafe(no_mangle)]
+fn main(_: Args) -> usize {
+    acpi_dump();
+    0
+}
@@ -0,0 +1,12 @@
+//! Kernel Log
+
+#![no_std]
+#![no_main]
+
+use userlib::{Args, dmesg};
+
+#[unsafe(no_mangle)]
+fn main(_: Args) -> usize {
+    dmesg();
+    0
+}
@@ -0,0 +1,25 @@
+//! Hello
+
+#![no_std]
+#![no_main]
+
+use userlib::{Args, CLOCK_MONOTONIC, clock_gettime, getpid, println, string::String};
+
+#[unsafe(no_mangle)]
+fn main(args: Args) -> usize {
+    let mut line = String::from("Hello from ring 3:");
+    for arg in args {
+        line.push(' ');
+        line.push_str(arg);
+    }
+    println!("{}", line);
+
+    let now = clock_gettime(CLOCK_MONOTONIC).unwrap_or_default();
+    println!(
+        "Task {} at {}.{:03} s",
+        getpid(),
+        now.sec,
+        now.nsec / 1_000_000
+    );
+    0
+}
@@ -0,0 +1,52 @@
+//! Perf
+//!
+//! Counts and then samples the whole system while `/hello` runs.
+
+#![no_std]
+#![no_main]
+
+use userlib::{
+    Args, PERF_CYCLES, PERF_INSTRUCTIONS, eprintln, perf_count, perf_dump, perf_read, perf_sample,
+    perf_stop, println, spawn, wait,
+};
+
+const PROGRAM: &str = "/hello";
+
+/// Cycles between two samples
+const PERIOD: u64 = 100_000;
+
+fn run() -> bool {
+    match spawn(PROGRAM).and_then(wait) {
+        Ok(_) => true,
+        Err(err) => {
+            eprintln!("{}: {:?}", PROGRAM, err);
+            false
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+fn main(_: Args) -> usize {
+    if let Err(err) = perf_count(&[PERF_CYCLES, PERF_INSTRUCTIONS]) {
+        eprintln!("perf_count: {:?}", err);
+        return 1;
+    }
+    if !run() {
+        return 1;
+    }
+    perf_stop();
+    println!(
+        "{} cycles, {} instructions",
+        perf_read(PERF_CYCLES).unwrap_or(0),
+        perf_read(PERF_INSTRUCTIONS).unwrap_or(0)
+    );
+
+    if let Err(err) = perf_sample(PERF_CYCLES, PERIOD) {
+        eprintln!("perf_sample: {:?}", err);
+        return 1;
+    }
+    let ran = run();
+    perf_stop();
+    perf_dump();
+    !ran as usize
+}
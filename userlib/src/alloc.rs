@@ -0,0 +1,54 @@
+//! Allocator
+//!
+//! Bump allocator over chunks obtained from `mmap`. Only the most recent
+//! allocation is ever given back.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::null_mut,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::syscall::{PROT_READ, PROT_WRITE, mmap};
+
+const CHUNK_SIZE: usize = 0x10000;
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator {
+    next: AtomicUsize::new(0),
+    end: AtomicUsize::new(0),
+};
+
+struct Allocator {
+    next: AtomicUsize,
+    end: AtomicUsize,
+}
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut next = self
+            .next
+            .load(Ordering::Relaxed)
+            .next_multiple_of(layout.align());
+        if self.next.load(Ordering::Relaxed) == 0
+            || next + layout.size() > self.end.load(Ordering::Relaxed)
+        {
+            let size = (layout.size() + layout.align()).max(CHUNK_SIZE);
+            let Ok(chunk) = mmap(size, PROT_READ | PROT_WRITE) else {
+                return null_mut();
+            };
+            next = (chunk as usize).next_multiple_of(layout.align());
+            self.end.store(chunk as usize + size, Ordering::Relaxed);
+        }
+        self.next.store(next + layout.size(), Ordering::Relaxed);
+        next as *mut u8
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = self.next.compare_exchange(
+            ptr as usize + layout.size(),
+            ptr as usize,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+}
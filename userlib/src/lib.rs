@@ -0,0 +1,26 @@
+//! User Library
+//!
+//! Runtime for programs running in ring 3: the entry point, the system calls,
+//! console output, a heap and a panic handler.
+//!
+//! A program provides `#[unsafe(no_mangle)] fn main(args: Args) -> usize`.
+
+#![no_std]
+
+extern crate alloc as alloc_crate;
+
+pub mod alloc;
+pub mod io;
+mod start;
+pub mod syscall;
+
+pub use start::Args;
+pub use syscall::*;
+
+pub use alloc_crate::{boxed, string, vec};
+
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    eprintln!("{}", info);
+    syscall::exit(101)
+}
@@ -0,0 +1,50 @@
+//! Start
+
+use core::{arch::naked_asm, ffi::CStr};
+
+unsafe extern "Rust" {
+    fn main(args: Args) -> usize;
+}
+
+/// Entered with RSP pointing at argc, followed by argv, envp and auxv
+#[unsafe(naked)]
+#[unsafe(no_mangle)]
+unsafe extern "C" fn _start() -> ! {
+    naked_asm!(
+        "mov rdi, rsp",
+        "and rsp, -16",
+        "call {}",
+        "ud2",
+        sym start,
+    );
+}
+
+extern "C" fn start(sp: *const u64) -> ! {
+    let args = unsafe {
+        Args {
+            argv: sp.add(1) as *const *const u8,
+            argc: *sp as usize,
+            index: 0,
+        }
+    };
+    super::exit(unsafe { main(args) })
+}
+
+/// Command line arguments, the first being the program path
+pub struct Args {
+    argv: *const *const u8,
+    argc: usize,
+    index: usize,
+}
+impl Iterator for Args {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.argc {
+            return None;
+        }
+        let arg = unsafe { CStr::from_ptr(*self.argv.add(self.index) as *const _) };
+        self.index += 1;
+        arg.to_str().ok()
+    }
+}
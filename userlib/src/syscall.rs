@@ -0,0 +1,197 @@
+//! System Call
+//!
+//! Mirrors the kernel table: the number goes in RAX and the arguments in
+//! RDI, RSI, RDX, R10, R8 and R9. A negative return value is a negated `Errno`.
+
+use core::arch::asm;
+
+const EXIT: u64 = 0;
+const WRITE: u64 = 1;
+const READ: u64 = 2;
+const OPEN: u64 = 3;
+const CLOSE: u64 = 4;
+const MMAP: u64 = 5;
+const MUNMAP: u64 = 6;
+const CLOCK_GETTIME: u64 = 7;
+const SLEEP: u64 = 8;
+const SPAWN: u64 = 9;
+const WAIT: u64 = 10;
+const GETPID: u64 = 11;
+const ACPI_DUMP: u64 = 12;
+const LOG_LEVEL: u64 = 13;
+const DMESG: u64 = 14;
+const PERF_COUNT: u64 = 15;
+const PERF_SAMPLE: u64 = 16;
+const PERF_READ: u64 = 17;
+const PERF_STOP: u64 = 18;
+const PERF_DUMP: u64 = 19;
+
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+pub const PROT_READ: u64 = 1 << 0;
+pub const PROT_WRITE: u64 = 1 << 1;
+pub const PROT_EXEC: u64 = 1 << 2;
+
+pub const CLOCK_MONOTONIC: u64 = 1;
+
+/// PMU events
+pub const PERF_CYCLES: u64 = 0;
+pub const PERF_INSTRUCTIONS: u64 = 1;
+pub const PERF_CACHE_MISSES: u64 = 2;
+pub const PERF_BRANCH_MISSES: u64 = 3;
+
+/// Numbered as on Linux
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Errno(pub i64);
+impl Errno {
+    pub const NOENT: Self = Self(2);
+    pub const IO: Self = Self(5);
+    pub const NOEXEC: Self = Self(8);
+    pub const BADF: Self = Self(9);
+    pub const CHILD: Self = Self(10);
+    pub const AGAIN: Self = Self(11);
+    pub const NOMEM: Self = Self(12);
+    pub const FAULT: Self = Self(14);
+    pub const INVAL: Self = Self(22);
+    pub const NOSYS: Self = Self(38);
+    pub const NOTSUP: Self = Self(95);
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timespec {
+    pub sec: i64,
+    pub nsec: i64,
+}
+
+#[inline(always)]
+unsafe fn syscall(number: u64, a0: u64, a1: u64, a2: u64) -> i64 {
+    let ret: i64;
+    unsafe {
+        asm!(
+            "syscall",
+            inlateout("rax") number as i64 => ret,
+            in("rdi") a0,
+            in("rsi") a1,
+            in("rdx") a2,
+            lateout("rcx") _,
+            lateout("r11") _,
+            options(nostack),
+        )
+    };
+    ret
+}
+
+fn result(ret: i64) -> Result<usize, Errno> {
+    if ret < 0 {
+        Err(Errno(-ret))
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+pub fn exit(code: usize) -> ! {
+    unsafe { syscall(EXIT, code as u64, 0, 0) };
+    unreachable!()
+}
+
+pub fn write(fd: usize, buf: &[u8]) -> Result<usize, Errno> {
+    result(unsafe { syscall(WRITE, fd as u64, buf.as_ptr() as u64, buf.len() as u64) })
+}
+
+pub fn read(fd: usize, buf: &mut [u8]) -> Result<usize, Errno> {
+    result(unsafe { syscall(READ, fd as u64, buf.as_mut_ptr() as u64, buf.len() as u64) })
+}
+
+pub fn open(path: &str) -> Result<usize, Errno> {
+    result(unsafe { syscall(OPEN, path.as_ptr() as u64, path.len() as u64, 0) })
+}
+
+pub fn close(fd: usize) -> Result<(), Errno> {
+    result(unsafe { syscall(CLOSE, fd as u64, 0, 0) }).map(|_| ())
+}
+
+/// Maps `len` bytes of zeroed anonymous memory
+pub fn mmap(len: usize, prot: u64) -> Result<*mut u8, Errno> {
+    result(unsafe { syscall(MMAP, len as u64, prot, 0) }).map(|addr| addr as *mut u8)
+}
+
+pub fn munmap(addr: *mut u8, len: usize) -> Result<(), Errno> {
+    result(unsafe { syscall(MUNMAP, addr as u64, len as u64, 0) }).map(|_| ())
+}
+
+pub fn clock_gettime(clock: u64) -> Result<Timespec, Errno> {
+    let mut timespec = Timespec::default();
+    result(unsafe {
+        syscall(
+            CLOCK_GETTIME,
+            clock,
+            &mut timespec as *mut Timespec as u64,
+            0,
+        )
+    })
+    .map(|_| timespec)
+}
+
+pub fn sleep(ms: u64) {
+    unsafe { syscall(SLEEP, ms, 0, 0) };
+}
+
+/// Starts the executable at `path` and returns its task ID
+pub fn spawn(path: &str) -> Result<usize, Errno> {
+    result(unsafe { syscall(SPAWN, path.as_ptr() as u64, path.len() as u64, 0) })
+}
+
+/// Waits for a task started by `spawn` and returns its exit code
+pub fn wait(id: usize) -> Result<usize, Errno> {
+    result(unsafe { syscall(WAIT, id as u64, 0, 0) })
+}
+
+pub fn getpid() -> usize {
+    unsafe { syscall(GETPID, 0, 0, 0) as usize }
+}
+
+/// Prints every ACPI table to the kernel console, for bug reports
+pub fn acpi_dump() {
+    unsafe { syscall(ACPI_DUMP, 0, 0, 0) };
+}
+
+/// Most verbose level the kernel records, from 0 for errors to 4 for traces, for `tag` or for
+/// every tag without its own when `tag` is empty
+///
+/// `None` makes `tag` follow the global level again.
+pub fn log_level(tag: &str, level: Option<u64>) -> Result<(), Errno> {
+    let level = level.unwrap_or(u64::MAX);
+    result(unsafe { syscall(LOG_LEVEL, tag.as_ptr() as u64, tag.len() as u64, level) }).map(|_| ())
+}
+
+/// Prints the kernel log records still buffered to the console
+pub fn dmesg() {
+    unsafe { syscall(DMESG, 0, 0, 0) };
+}
+
+/// Counts each PMU event in `events` from zero, across the whole system
+pub fn perf_count(events: &[u64]) -> Result<(), Errno> {
+    let mask = events.iter().fold(0, |mask, event| mask | 1 << event);
+    result(unsafe { syscall(PERF_COUNT, mask, 0, 0) }).map(|_| ())
+}
+
+/// Records the RIP every `period` occurrences of `event`, across the whole system
+pub fn perf_sample(event: u64, period: u64) -> Result<(), Errno> {
+    result(unsafe { syscall(PERF_SAMPLE, event, period, 0) }).map(|_| ())
+}
+
+pub fn perf_read(event: u64) -> Result<u64, Errno> {
+    result(unsafe { syscall(PERF_READ, event, 0, 0) }).map(|count| count as u64)
+}
+
+pub fn perf_stop() {
+    unsafe { syscall(PERF_STOP, 0, 0, 0) };
+}
+
+/// Logs the hottest RIPs of the last sampling session, for `make symbolize`
+pub fn perf_dump() {
+    unsafe { syscall(PERF_DUMP, 0, 0, 0) };
+}